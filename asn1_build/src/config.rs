@@ -0,0 +1,169 @@
+//! Options controlling the shape of the generated code.
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use std::collections::HashMap;
+
+use crate::Error;
+
+/// How generated types implement the serde traits.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Serde {
+    /// Derive with `serde_derive::Serialize` and `serde_derive::Deserialize`.
+    #[default]
+    SerdeDerive,
+    /// Derive with `serde::Serialize` and `serde::Deserialize`, which needs
+    /// the `derive` feature of serde.
+    Serde,
+    /// Don't implement the serde traits.
+    Disabled,
+}
+
+/// Options for code generation. The default generates private types which
+/// derive the serde traits, `Debug` and `PartialEq`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    /// Visibility of the generated types, for example `pub` or `pub(crate)`.
+    /// Types are private if this is empty.
+    pub visibility: String,
+    /// Paths of traits to derive in addition to the default derives.
+    pub derives: Vec<String>,
+    /// Whether to wrap the generated items in a module named after the ASN.1
+    /// module.
+    pub module: bool,
+    /// How the serde traits are implemented. Types which implement them also
+    /// implement `serde_asn1::AsnType`, which the codecs of `serde_asn1` use.
+    pub serde: Serde,
+    /// Attributes, such as `#[derive(Hash)]`, to add to the type generated for
+    /// the ASN.1 type with the given name.
+    pub attributes: HashMap<String, Vec<String>>,
+    /// Paths of rust types to use in place of the ASN.1 type with the given
+    /// name, or of a component given as `Type.component`. Overridden types are
+    /// not generated. The rust types must implement the derived traits.
+    pub types: HashMap<String, String>,
+    /// Whether constrained type aliases, such as `Speed ::= INTEGER (0..100)`,
+    /// are generated as newtypes rather than rust type aliases, so that they
+    /// are distinct types.
+    pub newtypes: bool,
+    /// Whether the newtypes of constrained integer type aliases only allow
+    /// values in range. Their value is private, and they are constructed
+    /// with `TryFrom` and dereference to the value. Implies `newtypes`.
+    /// Ranges with an end of MIN or MAX, or an extension marker, aren't
+    /// checked. Components with an inline constraint, such as
+    /// `level INTEGER (0..100)` in `Reading`, get a checked newtype of their
+    /// own, named `ReadingLevel`.
+    /// Generated code then uses `serde_asn1::RangeError`.
+    pub checked: bool,
+    /// Whether generated types implement `serde_asn1::Validate`, which checks
+    /// the constraints their rust types don't enforce, such as the SIZE of
+    /// strings and lists.
+    pub validate: bool,
+    /// Whether SEQUENCEs whose components are all OPTIONAL or have a DEFAULT
+    /// value implement `Default`, leaving out the OPTIONAL components and
+    /// taking the DEFAULT values.
+    pub defaults: bool,
+    /// Whether each SEQUENCE has a builder, which is created with
+    /// `Type::builder(..)` from the values of its required components.
+    pub builders: bool,
+}
+
+impl Config {
+    /// Sets the visibility of the generated types, failing if it isn't a
+    /// rust visibility.
+    pub fn set_visibility(&mut self, visibility: &str) -> Result<(), Error> {
+        parse_visibility(visibility)?;
+        self.visibility = visibility.to_string();
+        Ok(())
+    }
+
+    /// Adds a trait to derive, failing if it isn't a rust path.
+    pub fn add_derive(&mut self, derive: &str) -> Result<(), Error> {
+        parse_derive(derive)?;
+        self.derives.push(derive.to_string());
+        Ok(())
+    }
+
+    /// Adds an attribute to the type generated for an ASN.1 type, failing
+    /// if it isn't a rust attribute.
+    pub fn add_attribute(&mut self, asn_name: &str, attribute: &str) -> Result<(), Error> {
+        parse_attribute(attribute)?;
+        self.attributes
+            .entry(asn_name.to_string())
+            .or_default()
+            .push(attribute.to_string());
+        Ok(())
+    }
+
+    /// Sets the rust type used in place of an ASN.1 type or component,
+    /// failing if it isn't a rust type.
+    pub fn set_type(&mut self, asn_path: &str, rust_type: &str) -> Result<(), Error> {
+        parse_type(rust_type)?;
+        self.types
+            .insert(asn_path.to_string(), rust_type.to_string());
+        Ok(())
+    }
+
+    pub(crate) fn visibility(&self) -> Result<TokenStream, Error> {
+        parse_visibility(&self.visibility).map(ToTokens::into_token_stream)
+    }
+
+    /// All traits derived by generated types.
+    pub(crate) fn derives(&self) -> Result<Vec<syn::Path>, Error> {
+        let serde: &[&str] = match self.serde {
+            Serde::SerdeDerive => &["serde_derive::Serialize", "serde_derive::Deserialize"],
+            Serde::Serde => &["serde::Serialize", "serde::Deserialize"],
+            Serde::Disabled => &[],
+        };
+
+        serde
+            .iter()
+            .copied()
+            .chain(["Debug", "PartialEq"].iter().copied())
+            .chain(self.derives.iter().map(String::as_str))
+            .map(parse_derive)
+            .collect()
+    }
+
+    /// The user supplied attributes of the type generated for the given ASN.1
+    /// type.
+    pub(crate) fn attributes(&self, asn_name: &str) -> Result<Vec<syn::Attribute>, Error> {
+        let attributes = match self.attributes.get(asn_name) {
+            Some(attributes) => attributes,
+            None => return Ok(vec![]),
+        };
+
+        let mut parsed = vec![];
+        for attribute in attributes {
+            parsed.extend(parse_attribute(attribute)?);
+        }
+
+        Ok(parsed)
+    }
+
+    /// The user supplied rust type for an ASN.1 type or component.
+    pub(crate) fn type_override(&self, asn_path: &str) -> Result<Option<syn::Type>, Error> {
+        self.types
+            .get(asn_path)
+            .map(|rust_type| parse_type(rust_type))
+            .transpose()
+    }
+}
+
+fn parse_visibility(visibility: &str) -> Result<syn::Visibility, Error> {
+    syn::parse_str(visibility)
+        .map_err(|_| Error::InvalidOption(format!("invalid visibility `{}`", visibility)))
+}
+
+fn parse_derive(derive: &str) -> Result<syn::Path, Error> {
+    syn::parse_str(derive).map_err(|_| Error::InvalidOption(format!("invalid derive `{}`", derive)))
+}
+
+fn parse_attribute(attribute: &str) -> Result<Vec<syn::Attribute>, Error> {
+    syn::parse::Parser::parse_str(syn::Attribute::parse_outer, attribute)
+        .map_err(|_| Error::InvalidOption(format!("invalid attribute `{}`", attribute)))
+}
+
+fn parse_type(rust_type: &str) -> Result<syn::Type, Error> {
+    syn::parse_str(rust_type)
+        .map_err(|_| Error::InvalidOption(format!("invalid type `{}`", rust_type)))
+}
@@ -0,0 +1,33 @@
+use std::fmt::{self, Display};
+
+/// An ASN.1 module which code couldn't be generated for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The ASN.1 module couldn't be parsed.
+    Parse(asn1_schema::Error),
+    /// An option of the `Config` isn't valid rust, such as a visibility of
+    /// `pub(`.
+    InvalidOption(String),
+    /// The module can't be generated as configured, such as when two ASN.1
+    /// names map to the same rust name.
+    Unsupported(String),
+}
+
+impl From<asn1_schema::Error> for Error {
+    fn from(error: asn1_schema::Error) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(error) => write!(formatter, "{}", error),
+            Error::InvalidOption(message) | Error::Unsupported(message) => {
+                formatter.write_str(message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
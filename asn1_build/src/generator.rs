@@ -0,0 +1,1372 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use std::collections::HashSet;
+
+use crate::{naming, recursion, Config, Error, Serde};
+use asn1_schema::ast::{
+    AsnChoice, AsnDocs, AsnEnumerated, AsnField, AsnModule, AsnNamedNumber, AsnPresence,
+    AsnSequence, AsnType, AsnValue, AsnValueAssignment,
+};
+use asn1_schema::StringKind;
+
+/// Returns the rust type used to represent the given ASN type. Types
+/// referred to by name are generated, so they are used by name too.
+fn rust_type(asn_type: &AsnType, config: &Config) -> Result<TokenStream, Error> {
+    Ok(match asn_type {
+        AsnType::Custom(type_name) => match config.type_override(type_name)? {
+            Some(rust_type) => quote! { #rust_type },
+            None => type_ident(type_name),
+        },
+        AsnType::SequenceOf(element_type) | AsnType::SetOf(element_type) => {
+            let element_type = rust_type(element_type, config)?;
+            quote! { ::std::vec::Vec<#element_type> }
+        }
+        AsnType::Constrained(asn_type, _) => return rust_type(asn_type, config),
+        _ => return builtin_type(asn_type),
+    })
+}
+
+/// Returns the rust type built into the language used to represent the
+/// given ASN type, which must not be a reference to a SEQUENCE or CHOICE.
+fn builtin_type(asn_type: &AsnType) -> Result<TokenStream, Error> {
+    let rust_type_name = match asn_type {
+        AsnType::Integer => "i128",
+        // values outside of an extensible range are valid too
+        AsnType::BoundedInteger {
+            extensible: true, ..
+        }
+        | AsnType::ConstrainedReference {
+            extensible: true, ..
+        } => "i128",
+        AsnType::BoundedInteger { min, max, .. }
+        | AsnType::ConstrainedReference { min, max, .. } => integer_type(*min, *max).0,
+        AsnType::Boolean => "bool",
+        AsnType::CharacterString(_) => return Ok(quote! { ::std::string::String }),
+        AsnType::OctetString => return Ok(quote! { ::std::vec::Vec<u8> }),
+        AsnType::ObjectIdentifier => {
+            return Err(Error::Unsupported(
+                "OBJECT IDENTIFIER is only supported in values".into(),
+            ))
+        }
+        AsnType::Constrained(asn_type, _) => return builtin_type(asn_type),
+        AsnType::SequenceOf(_) | AsnType::SetOf(_) | AsnType::Custom(_) => {
+            panic!("{:?} is not a built in type", asn_type)
+        }
+    };
+    let rust_type = naming::ident(rust_type_name);
+
+    Ok(quote! { #rust_type })
+}
+
+/// The rust integer types which constrained INTEGERs are represented as,
+/// along with their bounds, in order of preference.
+const INTEGER_TYPES: [(&str, i128, i128); 7] = [
+    ("u8", u8::MIN as i128, u8::MAX as i128),
+    ("u16", u16::MIN as i128, u16::MAX as i128),
+    ("u64", u64::MIN as i128, u64::MAX as i128),
+    ("i8", i8::MIN as i128, i8::MAX as i128),
+    ("i16", i16::MIN as i128, i16::MAX as i128),
+    ("i32", i32::MIN as i128, i32::MAX as i128),
+    ("i64", i64::MIN as i128, i64::MAX as i128),
+];
+
+/// The smallest rust integer type which holds every value in the range
+/// `min..=max`, along with its bounds. Ranges which no other type holds,
+/// including those with an end of MIN or MAX, are represented as `i128`.
+fn integer_type(min: Option<i128>, max: Option<i128>) -> (&'static str, i128, i128) {
+    INTEGER_TYPES
+        .iter()
+        .copied()
+        .find(|(_, type_min, type_max)| {
+            min.is_some_and(|min| *type_min <= min) && max.is_some_and(|max| max <= *type_max)
+        })
+        .unwrap_or(("i128", i128::MIN, i128::MAX))
+}
+
+/// The identifier of the rust type generated for an ASN.1 type reference.
+fn type_ident(asn_name: &str) -> TokenStream {
+    let ident = naming::ident(&naming::type_name(asn_name));
+    quote! { #ident }
+}
+
+/// The identifier of the rust constant generated for an ASN.1 value reference.
+fn const_ident(asn_name: &str) -> proc_macro2::Ident {
+    naming::ident(&naming::const_name(asn_name))
+}
+
+/// Generates the fields of a struct, or the variants of an enum, boxing
+/// the ones which would otherwise make the type contain itself. OPTIONAL
+/// components are generated as an `Option`. Each field is
+/// returned with its doc comments and a serde attribute renaming it back to its
+/// ASN.1 name, if `convert_name` changed it, so that serialized values keep the
+/// ASN.1 names.
+fn fields(
+    context: &str,
+    type_name: &str,
+    fields: &[AsnField],
+    convert_name: fn(&str) -> String,
+    boxed_fields: &HashSet<(&str, &str)>,
+    config: &Config,
+) -> Result<Vec<(TokenStream, proc_macro2::Ident, TokenStream)>, Error> {
+    naming::check_collisions(context, fields.iter().map(|f| f.name), convert_name)?;
+
+    fields
+        .iter()
+        .map(|field| {
+            let rust_name = naming::ident(&convert_name(field.name));
+            // serde already removes the prefix of raw identifiers
+            let rename = if config.serde == Serde::Disabled
+                || rust_name.to_string().trim_start_matches("r#") == field.name
+            {
+                quote! {}
+            } else {
+                let asn_name = field.name;
+                quote! { #[serde(rename = #asn_name)] }
+            };
+
+            let mut rust_field_type = component_type(type_name, field, boxed_fields, config)?;
+            if field.presence == AsnPresence::Optional {
+                rust_field_type = quote! { ::std::option::Option<#rust_field_type> };
+            }
+
+            let docs = docs(&field.docs);
+            let attributes = quote! {
+                #docs
+                #rename
+            };
+
+            Ok((attributes, rust_name, rust_field_type))
+        })
+        .collect()
+}
+
+/// Whether a component is generated with a newtype of its own, which holds
+/// the constants of its named numbers, or which only holds values in the
+/// range of its inline constraint.
+fn is_component_newtype(field: &AsnField, config: &Config) -> bool {
+    !field.named_numbers.is_empty() || is_checked_component(field, config)
+}
+
+/// Whether the newtype of a component only holds values in range. Named
+/// numbers take precedence, as they do for type aliases.
+fn is_checked_component(field: &AsnField, config: &Config) -> bool {
+    config.checked && field.named_numbers.is_empty() && bounds(&field.field_type).is_some()
+}
+
+/// The rust type of the value of a component, which is boxed if it would
+/// otherwise make the type contain itself.
+fn component_type(
+    type_name: &str,
+    field: &AsnField,
+    boxed_fields: &HashSet<(&str, &str)>,
+    config: &Config,
+) -> Result<TokenStream, Error> {
+    Ok(
+        match config.type_override(&format!("{}.{}", type_name, field.name))? {
+            Some(rust_type) => quote! { #rust_type },
+            None if is_component_newtype(field, config) => {
+                let rust_name = naming::ident(&naming::component_type_name(type_name, field.name));
+                quote! { #rust_name }
+            }
+            None => {
+                let rust_type = rust_type(&field.field_type, config)?;
+                if boxed_fields.contains(&(type_name, field.name)) {
+                    quote! { ::std::boxed::Box<#rust_type> }
+                } else {
+                    rust_type
+                }
+            }
+        },
+    )
+}
+
+/// Doc attributes with the comments of an ASN.1 definition, followed by
+/// the definition itself.
+fn docs(docs: &AsnDocs) -> TokenStream {
+    let mut lines: Vec<String> = docs
+        .comments
+        .iter()
+        .map(|comment| format!(" {}", expand_tabs(comment)))
+        .collect();
+    if !lines.is_empty() {
+        lines.push(String::new());
+    }
+
+    let definition: Vec<&str> = docs.definition.lines().collect();
+    if let [definition] = definition.as_slice() {
+        let definition: Vec<&str> = definition.split_whitespace().collect();
+        lines.push(format!(" `{}`", definition.join(" ")));
+    } else {
+        lines.push(" ```asn1".into());
+        lines.extend(
+            definition
+                .iter()
+                .map(|line| format!(" {}", expand_tabs(line.trim_end()))),
+        );
+        lines.push(" ```".into());
+    }
+
+    quote! { #(#[doc = #lines])* }
+}
+
+/// Replaces the tabs of a line with spaces up to the next multiple of four
+/// columns, since tabs in doc comments are rendered with varying widths.
+fn expand_tabs(line: &str) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    for character in line.chars() {
+        if character == '\t' {
+            let spaces = 4 - expanded.chars().count() % 4;
+            expanded.push_str(&" ".repeat(spaces));
+        } else {
+            expanded.push(character);
+        }
+    }
+    expanded
+}
+
+/// The bounds of a constrained integer type, unless they are extensible or
+/// either of them is MIN or MAX.
+fn bounds(asn_type: &AsnType) -> Option<(i128, i128)> {
+    match asn_type {
+        AsnType::BoundedInteger {
+            min: Some(min),
+            max: Some(max),
+            extensible: false,
+        }
+        | AsnType::ConstrainedReference {
+            min: Some(min),
+            max: Some(max),
+            extensible: false,
+            ..
+        } => Some((*min, *max)),
+        _ => None,
+    }
+}
+
+/// A slice of character ranges.
+fn alphabet_literal(alphabet: &[(char, char)]) -> TokenStream {
+    let ranges = alphabet.iter().map(|(from, to)| quote! { (#from, #to) });
+    quote! { &[#(#ranges),*] }
+}
+
+/// Negative literals don't survive being passed through a token
+/// stream, so the sign is emitted separately.
+fn integer_literal(value: i128) -> TokenStream {
+    let literal = Literal::u128_unsuffixed(value.unsigned_abs());
+    if value < 0 {
+        quote! { -#literal }
+    } else {
+        quote! { #literal }
+    }
+}
+
+/// An end of a range, which is `None` if it is MIN or MAX.
+fn bound_literal(bound: Option<i128>) -> TokenStream {
+    match bound {
+        Some(bound) => {
+            let bound = integer_literal(bound);
+            quote! { ::std::option::Option::Some(#bound) }
+        }
+        None => quote! { ::std::option::Option::None },
+    }
+}
+
+/// Generates rust types for the ASN.1 module in the given string, as
+/// configured, failing if the module can't be parsed, if an option of the
+/// `Config` isn't valid rust, or if the module can't be generated as
+/// configured.
+pub fn try_generate_with_config(asn1_string: &str, config: &Config) -> Result<TokenStream, Error> {
+    let asn_module = AsnModule::parse(asn1_string)?;
+    // the generated `AsnType` impls parse the module again, which mustn't fail
+    asn1_schema::Module::parse(asn1_string)?;
+
+    let is_type = |name: &&str| {
+        asn_module.sequences.contains_key(name)
+            || asn_module.choices.contains_key(name)
+            || asn_module.enumerations.contains_key(name)
+            || asn_module.type_aliases.contains_key(name)
+    };
+    // the newtypes of components are named `{Type}{Component}`, after
+    // their path `Type.component`
+    let component_paths: Vec<String> = asn_module
+        .assignments
+        .iter()
+        .filter_map(|name| {
+            let fields = asn_module
+                .sequences
+                .get(name)
+                .map(|sequence| &sequence.fields)
+                .or_else(|| {
+                    asn_module
+                        .choices
+                        .get(name)
+                        .map(|choice| &choice.alternatives)
+                })?;
+            Some((name, fields))
+        })
+        .flat_map(|(name, fields)| {
+            fields
+                .iter()
+                .filter(|field| is_component_newtype(field, config))
+                .map(move |field| format!("{}.{}", name, field.name))
+        })
+        .filter(|path| !config.types.contains_key(path))
+        .collect();
+    naming::check_collisions(
+        &format!("module {}", asn_module.name),
+        asn_module
+            .assignments
+            .iter()
+            .copied()
+            .filter(is_type)
+            .chain(component_paths.iter().map(String::as_str)),
+        |name| match name.split_once('.') {
+            Some((type_name, component)) => naming::component_type_name(type_name, component),
+            None => naming::type_name(name),
+        },
+    )?;
+    naming::check_collisions(
+        &format!("module {}", asn_module.name),
+        asn_module
+            .assignments
+            .iter()
+            .copied()
+            .filter(|name| asn_module.values.contains_key(name)),
+        naming::const_name,
+    )?;
+
+    if config.builders {
+        for type_name in asn_module.assignments.iter().copied().filter(is_type) {
+            let rust_name = naming::type_name(type_name);
+            if let Some(sequence_name) = rust_name.strip_suffix("Builder").and_then(|name| {
+                asn_module
+                    .sequences
+                    .keys()
+                    .find(|s| naming::type_name(s) == name)
+            }) {
+                return Err(Error::Unsupported(format!(
+                    "type `{}` collides with the builder of SEQUENCE {}",
+                    type_name, sequence_name
+                )));
+            }
+        }
+    }
+
+    for asn_name in config.attributes.keys() {
+        if !is_type(&asn_name.as_str()) {
+            return Err(Error::Unsupported(format!(
+                "attributes given for unknown type `{}`",
+                asn_name
+            )));
+        }
+    }
+    for asn_path in config.types.keys() {
+        let known = match asn_path.split_once('.') {
+            Some((type_name, component_name)) => asn_module
+                .sequences
+                .get(type_name)
+                .map(|sequence| &sequence.fields)
+                .or_else(|| {
+                    asn_module
+                        .choices
+                        .get(type_name)
+                        .map(|choice| &choice.alternatives)
+                })
+                .is_some_and(|fields| fields.iter().any(|field| field.name == component_name)),
+            None => is_type(&asn_path.as_str()),
+        };
+        if !known {
+            return Err(Error::Unsupported(format!(
+                "type given for unknown type or component `{}`",
+                asn_path
+            )));
+        }
+    }
+
+    let generator = Generator {
+        asn_module: &asn_module,
+        config,
+        visibility: config.visibility()?,
+        derives: config.derives()?,
+        boxed_fields: recursion::boxed_fields(&asn_module),
+    };
+
+    // items are generated in the order they are defined in, so the output
+    // is the same on every build
+    let mut out = TokenStream::new();
+    // the first type with an `AsnType` impl holds the parsed module
+    let mut module_owner = None;
+    for name in &asn_module.assignments {
+        if config.types.contains_key(*name) {
+            continue;
+        }
+
+        if let Some(sequence) = asn_module.sequences.get(name) {
+            out.extend(generator.sequence(name, sequence)?);
+            out.extend(generator.component_newtypes(name, &sequence.fields)?);
+            if config.defaults {
+                out.extend(generator.default_impl(name, sequence)?);
+            }
+            if config.builders {
+                out.extend(generator.builder(name, sequence)?);
+            }
+        } else if let Some(choice) = asn_module.choices.get(name) {
+            out.extend(generator.choice(name, choice)?);
+            out.extend(generator.component_newtypes(name, &choice.alternatives)?);
+        } else if let Some(enumerated) = asn_module.enumerations.get(name) {
+            out.extend(generator.enumerated(name, enumerated)?);
+        } else if let Some(named_numbers) = asn_module.named_numbers.get(name) {
+            out.extend(generator.named_numbers(name, named_numbers)?);
+        } else if let Some(alias) = asn_module.type_aliases.get(name) {
+            out.extend(generator.type_alias(name, alias)?);
+        } else if let Some(value_assignment) = asn_module.values.get(name) {
+            out.extend(generator.value(name, value_assignment)?);
+        }
+
+        if config.validate {
+            out.extend(generator.validate(name)?);
+        }
+        if config.serde != Serde::Disabled && generator.is_rust_type(name) {
+            out.extend(generator.asn_type(name, asn1_string, &mut module_owner));
+        }
+    }
+
+    if config.module {
+        let module_name = naming::ident(&naming::field_name(asn_module.name));
+        let visibility = &generator.visibility;
+        out = quote! {
+            #visibility mod #module_name {
+                #out
+            }
+        };
+    }
+
+    Ok(out)
+}
+
+/// Generates the items of a module.
+struct Generator<'a> {
+    asn_module: &'a AsnModule<'a>,
+    config: &'a Config,
+    visibility: TokenStream,
+    derives: Vec<syn::Path>,
+    boxed_fields: HashSet<(&'a str, &'a str)>,
+}
+
+impl<'a> Generator<'a> {
+    /// The doc comments, derives and attributes of the type generated for
+    /// an ASN.1 type.
+    fn type_attributes(&self, type_name: &str) -> Result<TokenStream, Error> {
+        let type_docs = docs(&self.asn_module.docs[type_name]);
+        let derives = &self.derives;
+        let attributes = self.config.attributes(type_name)?;
+
+        Ok(quote! {
+            #type_docs
+            #[derive(#(#derives),*)]
+            #(#attributes)*
+        })
+    }
+
+    fn sequence(
+        &self,
+        type_name: &'a str,
+        sequence: &'a AsnSequence,
+    ) -> Result<TokenStream, Error> {
+        let struct_name = type_ident(type_name);
+        let attributes = self.type_attributes(type_name)?;
+        let visibility = &self.visibility;
+        let fields = fields(
+            &format!("SEQUENCE {}", type_name),
+            type_name,
+            &sequence.fields,
+            naming::field_name,
+            &self.boxed_fields,
+            self.config,
+        )?
+        .into_iter()
+        .map(|(attributes, name, rust_field_type)| {
+            quote! {
+                #attributes
+                pub #name : #rust_field_type ,
+            }
+        });
+
+        Ok(quote! {
+            #attributes
+            #visibility struct #struct_name {
+                #(#fields)*
+            }
+        })
+    }
+
+    fn choice(&self, type_name: &'a str, choice: &'a AsnChoice) -> Result<TokenStream, Error> {
+        let enum_name = type_ident(type_name);
+        let attributes = self.type_attributes(type_name)?;
+        let visibility = &self.visibility;
+        let variants = fields(
+            &format!("CHOICE {}", type_name),
+            type_name,
+            &choice.alternatives,
+            naming::type_name,
+            &self.boxed_fields,
+            self.config,
+        )?
+        .into_iter()
+        .map(|(attributes, name, rust_field_type)| {
+            quote! {
+                #attributes
+                #name(#rust_field_type),
+            }
+        });
+
+        Ok(quote! {
+            #attributes
+            #visibility enum #enum_name {
+                #(#variants)*
+            }
+        })
+    }
+
+    /// An ENUMERATED type, whose items are unit variants.
+    fn enumerated(
+        &self,
+        type_name: &str,
+        enumerated: &AsnEnumerated,
+    ) -> Result<TokenStream, Error> {
+        naming::check_collisions(
+            &format!("ENUMERATED {}", type_name),
+            enumerated.items.iter().map(|item| item.name),
+            naming::type_name,
+        )?;
+
+        let enum_name = type_ident(type_name);
+        let attributes = self.type_attributes(type_name)?;
+        let visibility = &self.visibility;
+        let variants = enumerated.items.iter().map(|item| {
+            let variant_name = naming::ident(&naming::type_name(item.name));
+            // serialized values keep the ASN.1 names
+            let asn_name = item.name;
+            let rename = if self.config.serde == Serde::Disabled
+                || variant_name.to_string().trim_start_matches("r#") == asn_name
+            {
+                quote! {}
+            } else {
+                quote! { #[serde(rename = #asn_name)] }
+            };
+            quote! {
+                #rename
+                #variant_name,
+            }
+        });
+
+        Ok(quote! {
+            #attributes
+            #visibility enum #enum_name {
+                #(#variants)*
+            }
+        })
+    }
+
+    fn named_numbers(
+        &self,
+        type_name: &str,
+        named_numbers: &[AsnNamedNumber],
+    ) -> Result<TokenStream, Error> {
+        self.integer_newtype(
+            &format!("INTEGER {}", type_name),
+            self.type_attributes(type_name)?,
+            type_ident(type_name),
+            builtin_type(&self.asn_module.type_aliases[type_name])?,
+            named_numbers,
+        )
+    }
+
+    /// The newtypes of the components of a SEQUENCE or CHOICE which have
+    /// named numbers, unless the component has a user supplied type.
+    fn component_newtypes(
+        &self,
+        type_name: &str,
+        fields: &[AsnField],
+    ) -> Result<TokenStream, Error> {
+        let mut out = TokenStream::new();
+        let newtype_fields = fields
+            .iter()
+            .filter(|field| is_component_newtype(field, self.config));
+        for field in newtype_fields {
+            let asn_path = format!("{}.{}", type_name, field.name);
+            if self.config.types.contains_key(&asn_path) {
+                continue;
+            }
+
+            let field_docs = docs(&field.docs);
+            let derives = &self.derives;
+            let attributes = quote! {
+                #field_docs
+                #[derive(#(#derives),*)]
+            };
+            let rust_name = naming::ident(&naming::component_type_name(type_name, field.name));
+            let rust_type = builtin_type(&field.field_type)?;
+            out.extend(match bounds(&field.field_type) {
+                Some((min, max)) if is_checked_component(field, self.config) => {
+                    self.checked_newtype(attributes, quote! { #rust_name }, rust_type, min, max)
+                }
+                _ => self.integer_newtype(
+                    &format!("INTEGER {}", asn_path),
+                    attributes,
+                    quote! { #rust_name },
+                    rust_type,
+                    &field.named_numbers,
+                )?,
+            });
+        }
+
+        Ok(out)
+    }
+
+    /// A newtype of an integer, with an associated constant for each named
+    /// number.
+    fn integer_newtype(
+        &self,
+        context: &str,
+        attributes: TokenStream,
+        type_name: TokenStream,
+        rust_type: TokenStream,
+        named_numbers: &[AsnNamedNumber],
+    ) -> Result<TokenStream, Error> {
+        naming::check_collisions(
+            context,
+            named_numbers.iter().map(|named_number| named_number.name),
+            naming::const_name,
+        )?;
+
+        let visibility = &self.visibility;
+        let consts = named_numbers.iter().map(|named_number| {
+            let const_name = const_ident(named_number.name);
+            let value = integer_literal(named_number.value);
+            quote! {
+                pub const #const_name: #type_name = #type_name(#value);
+            }
+        });
+
+        let transparent = self.transparent();
+        Ok(quote! {
+            #attributes
+            #transparent
+            #visibility struct #type_name(pub #rust_type);
+
+            impl #type_name {
+                #(#consts)*
+            }
+        })
+    }
+
+    /// Whether the type generated for the given type alias is a newtype,
+    /// rather than a rust type alias.
+    fn is_newtype(&self, type_name: &str) -> bool {
+        self.asn_module.named_numbers.contains_key(type_name)
+            || ((self.config.newtypes || self.config.checked)
+                && matches!(
+                    self.asn_module.type_aliases.get(type_name),
+                    Some(AsnType::BoundedInteger { .. } | AsnType::ConstrainedReference { .. })
+                ))
+    }
+
+    fn type_alias(&self, type_name: &str, alias: &AsnType) -> Result<TokenStream, Error> {
+        let visibility = &self.visibility;
+        let rust_name = type_ident(type_name);
+
+        if let (true, Some((min, max))) = (self.config.checked, bounds(alias)) {
+            Ok(self.checked_newtype(
+                self.type_attributes(type_name)?,
+                rust_name,
+                builtin_type(alias)?,
+                min,
+                max,
+            ))
+        } else if self.is_newtype(type_name) {
+            let attributes = self.type_attributes(type_name)?;
+            let rust_type = builtin_type(alias)?;
+            let transparent = self.transparent();
+
+            Ok(quote! {
+                #attributes
+                #transparent
+                #visibility struct #rust_name(pub #rust_type);
+            })
+        } else {
+            let type_docs = docs(&self.asn_module.docs[type_name]);
+            let attributes = self.config.attributes(type_name)?;
+            let rust_type = rust_type(alias, self.config)?;
+
+            Ok(quote! {
+                #type_docs
+                #(#attributes)*
+                #visibility type #rust_name = #rust_type;
+            })
+        }
+    }
+
+    /// A newtype which can only hold values in the range of its constraint.
+    /// It is deserialized through its `TryFrom` impl, so values out of range
+    /// are rejected while decoding.
+    fn checked_newtype(
+        &self,
+        attributes: TokenStream,
+        rust_name: TokenStream,
+        rust_type: TokenStream,
+        min: i128,
+        max: i128,
+    ) -> TokenStream {
+        let visibility = &self.visibility;
+        let try_from = if self.config.serde == Serde::Disabled {
+            quote! {}
+        } else {
+            let rust_type = rust_type.to_string();
+            quote! { #[serde(try_from = #rust_type)] }
+        };
+        let (min, max) = (integer_literal(min), integer_literal(max));
+
+        quote! {
+            #attributes
+            #try_from
+            #visibility struct #rust_name(#rust_type);
+
+            impl #rust_name {
+                pub const MIN: #rust_type = #min;
+                pub const MAX: #rust_type = #max;
+            }
+
+            impl ::std::convert::TryFrom<#rust_type> for #rust_name {
+                type Error = serde_asn1::RangeError;
+
+                fn try_from(value: #rust_type) -> ::std::result::Result<Self, Self::Error> {
+                    if (Self::MIN..=Self::MAX).contains(&value) {
+                        ::std::result::Result::Ok(#rust_name(value))
+                    } else {
+                        ::std::result::Result::Err(serde_asn1::RangeError {
+                            value: value.into(),
+                            min: #min,
+                            max: #max,
+                        })
+                    }
+                }
+            }
+
+            impl ::std::ops::Deref for #rust_name {
+                type Target = #rust_type;
+
+                fn deref(&self) -> &Self::Target {
+                    &self.0
+                }
+            }
+        }
+    }
+
+    /// The `Validate` impl of the type generated for an ASN.1 type, if it is
+    /// a rust type rather than a type alias.
+    fn validate(&self, type_name: &str) -> Result<TokenStream, Error> {
+        let asn_module = self.asn_module;
+        let body = if let Some(sequence) = asn_module.sequences.get(type_name) {
+            let mut fields = vec![];
+            for field in &sequence.fields {
+                let rust_name = naming::ident(&naming::field_name(field.name));
+                let checks = self.component_checks(type_name, field)?;
+                fields.push(if checks.is_empty() {
+                    checks
+                } else if field.presence == AsnPresence::Optional {
+                    quote! {
+                        if let ::std::option::Option::Some(value) = &self.#rust_name {
+                            #checks
+                        }
+                    }
+                } else {
+                    quote! {
+                        {
+                            let value = &self.#rust_name;
+                            #checks
+                        }
+                    }
+                });
+            }
+            quote! { #(#fields)* }
+        } else if let Some(choice) = asn_module.choices.get(type_name) {
+            let enum_name = type_ident(type_name);
+            let mut variants = vec![];
+            for alternative in &choice.alternatives {
+                let variant_name = naming::ident(&naming::type_name(alternative.name));
+                let checks = self.component_checks(type_name, alternative)?;
+                variants.push(if checks.is_empty() {
+                    quote! { #enum_name::#variant_name(_) => {} }
+                } else {
+                    quote! { #enum_name::#variant_name(value) => { #checks } }
+                });
+            }
+            quote! {
+                match self {
+                    #(#variants)*
+                }
+            }
+        } else if self.is_newtype(type_name) {
+            let alias = &asn_module.type_aliases[type_name];
+            if self.config.checked && bounds(alias).is_some() {
+                // the value was checked when the newtype was constructed
+                quote! {}
+            } else {
+                let checks = self.checks(alias, &quote! { value }, &quote! { path })?;
+                quote! {
+                    let value = &self.0;
+                    #checks
+                }
+            }
+        } else {
+            return Ok(quote! {});
+        };
+
+        let rust_name = type_ident(type_name);
+        Ok(quote! {
+            impl serde_asn1::Validate for #rust_name {
+                #[allow(unused_variables)]
+                fn validate_at(
+                    &self,
+                    path: &str,
+                    violations: &mut ::std::vec::Vec<serde_asn1::Violation>,
+                ) {
+                    #body
+                }
+            }
+        })
+    }
+
+    /// Checks the constraints of a component of a SEQUENCE or CHOICE, which
+    /// is bound to `value`. Components with a user supplied type aren't
+    /// checked.
+    fn component_checks(&self, type_name: &str, field: &AsnField) -> Result<TokenStream, Error> {
+        if self
+            .config
+            .types
+            .contains_key(&format!("{}.{}", type_name, field.name))
+        {
+            return Ok(quote! {});
+        }
+
+        // the value of a checked newtype was checked when it was constructed
+        if is_checked_component(field, self.config) {
+            return Ok(quote! {});
+        }
+
+        let value = if is_component_newtype(field, self.config) {
+            quote! { &value.0 }
+        } else {
+            quote! { value }
+        };
+        let checks = self.checks(&field.field_type, &value, &quote! { &path })?;
+        if checks.is_empty() {
+            return Ok(checks);
+        }
+
+        let asn_name = field.name;
+        Ok(quote! {
+            let path = serde_asn1::constraints::component_path(path, #asn_name);
+            #checks
+        })
+    }
+
+    /// Statements adding the violations of the constraints of an ASN.1 type
+    /// to `violations`, given expressions for a reference to the value and
+    /// for its path. Types which are generated as rust types are checked by
+    /// their own `Validate` impl, while the constraints of type aliases are
+    /// checked wherever they are used.
+    fn checks(
+        &self,
+        asn_type: &AsnType,
+        value: &TokenStream,
+        path: &TokenStream,
+    ) -> Result<TokenStream, Error> {
+        let asn_module = self.asn_module;
+        Ok(match asn_type {
+            AsnType::Custom(type_name) => {
+                if self.config.types.contains_key(*type_name) {
+                    quote! {}
+                } else if asn_module.sequences.contains_key(type_name)
+                    || asn_module.choices.contains_key(type_name)
+                    || self.is_newtype(type_name)
+                {
+                    quote! { serde_asn1::Validate::validate_at(#value, #path, violations); }
+                } else if asn_module.enumerations.contains_key(type_name) {
+                    // every value of the rust type is valid
+                    quote! {}
+                } else {
+                    match asn_module.type_aliases.get(type_name) {
+                        Some(alias) => self.checks(alias, value, path)?,
+                        None => {
+                            return Err(Error::Unsupported(format!("unknown type `{}`", type_name)))
+                        }
+                    }
+                }
+            }
+            AsnType::BoundedInteger {
+                extensible: true, ..
+            }
+            | AsnType::ConstrainedReference {
+                extensible: true, ..
+            } => quote! {},
+            AsnType::BoundedInteger { min, max, .. }
+            | AsnType::ConstrainedReference { min, max, .. } => {
+                // ranges which fall on the bounds of the rust type, or are
+                // unbounded, can't be violated
+                let (_, type_min, type_max) = integer_type(*min, *max);
+                if min.is_none_or(|min| min == type_min) && max.is_none_or(|max| max == type_max) {
+                    return Ok(quote! {});
+                }
+
+                let (min, max) = (bound_literal(*min), bound_literal(*max));
+                quote! {
+                    serde_asn1::constraints::check_range(
+                        #path,
+                        i128::from(*#value),
+                        #min,
+                        #max,
+                        violations,
+                    );
+                }
+            }
+            AsnType::CharacterString(kind) => {
+                match StringKind::from_name(kind).and_then(StringKind::alphabet) {
+                    Some(alphabet) => {
+                        let alphabet = alphabet_literal(alphabet);
+                        quote! {
+                            serde_asn1::constraints::check_alphabet(#path, #value, #alphabet, violations);
+                        }
+                    }
+                    None => quote! {},
+                }
+            }
+            AsnType::SequenceOf(element_type) | AsnType::SetOf(element_type) => {
+                let checks = self.checks(element_type, &quote! { element }, &quote! { &path })?;
+                if checks.is_empty() {
+                    return Ok(checks);
+                }
+
+                quote! {
+                    for (index, element) in #value.iter().enumerate() {
+                        let path = serde_asn1::constraints::element_path(#path, index);
+                        #checks
+                    }
+                }
+            }
+            AsnType::Constrained(constrained_type, constraint) => {
+                if let AsnType::Custom(type_name) = **constrained_type {
+                    if self.config.types.contains_key(type_name) {
+                        return Ok(quote! {});
+                    }
+                }
+
+                // sizes outside of an extensible constraint are valid too
+                let size = constraint.size.filter(|_| !constraint.extensible).map(|(min, max)| {
+                    let size = match asn_module.resolve(constrained_type) {
+                        AsnType::Constrained(inner, _)
+                            if matches!(**inner, AsnType::CharacterString(_)) =>
+                        {
+                            quote! { #value.chars().count() }
+                        }
+                        AsnType::CharacterString(_) => quote! { #value.chars().count() },
+                        _ => quote! { #value.len() },
+                    };
+                    let min = Literal::u64_unsuffixed(min);
+                    let max = match max {
+                        Some(max) => {
+                            let max = Literal::u64_unsuffixed(max);
+                            quote! { ::std::option::Option::Some(#max) }
+                        }
+                        None => quote! { ::std::option::Option::None },
+                    };
+                    quote! {
+                        serde_asn1::constraints::check_size(#path, #size, #min, #max, violations);
+                    }
+                });
+                let alphabet = constraint.alphabet.as_ref().map(|alphabet| {
+                    let alphabet = alphabet_literal(alphabet);
+                    quote! {
+                        serde_asn1::constraints::check_alphabet(#path, #value, #alphabet, violations);
+                    }
+                });
+                let checks = self.checks(constrained_type, value, path)?;
+
+                quote! {
+                    #size
+                    #alphabet
+                    #checks
+                }
+            }
+            AsnType::Integer
+            | AsnType::Boolean
+            | AsnType::OctetString
+            | AsnType::ObjectIdentifier => quote! {},
+        })
+    }
+
+    /// Whether the type generated for an ASN.1 type is a rust type, rather
+    /// than a rust type alias.
+    fn is_rust_type(&self, type_name: &str) -> bool {
+        self.asn_module.sequences.contains_key(type_name)
+            || self.asn_module.choices.contains_key(type_name)
+            || self.asn_module.enumerations.contains_key(type_name)
+            || self.is_newtype(type_name)
+    }
+
+    /// The `AsnType` impl of the type generated for an ASN.1 type. The module
+    /// is parsed once, by the impl of the first type, which is kept in
+    /// `module_owner`.
+    fn asn_type(
+        &self,
+        type_name: &str,
+        asn1_string: &str,
+        module_owner: &mut Option<TokenStream>,
+    ) -> TokenStream {
+        let rust_name = type_ident(type_name);
+        let module = match module_owner {
+            Some(owner) => quote! { <#owner as serde_asn1::AsnType>::module() },
+            None => {
+                *module_owner = Some(rust_name.clone());
+                quote! {
+                    static MODULE: ::std::sync::OnceLock<serde_asn1::asn1_schema::Module> =
+                        ::std::sync::OnceLock::new();
+                    MODULE.get_or_init(|| {
+                        serde_asn1::asn1_schema::Module::parse(#asn1_string)
+                            .expect("the module was parsed when generating code")
+                    })
+                }
+            }
+        };
+
+        quote! {
+            impl serde_asn1::AsnType for #rust_name {
+                const NAME: &'static str = #type_name;
+
+                fn module() -> &'static serde_asn1::asn1_schema::Module {
+                    #module
+                }
+            }
+        }
+    }
+
+    /// Newtypes are transparent, so they don't change the wire format.
+    fn transparent(&self) -> TokenStream {
+        if self.config.serde == Serde::Disabled {
+            quote! {}
+        } else {
+            quote! { #[serde(transparent)] }
+        }
+    }
+
+    fn value(
+        &self,
+        value_name: &str,
+        value_assignment: &AsnValueAssignment,
+    ) -> Result<TokenStream, Error> {
+        let asn_module = self.asn_module;
+        let const_name = const_ident(value_name);
+        let value_docs = docs(&asn_module.docs[value_name]);
+
+        Ok(match &value_assignment.value {
+            AsnValue::Integer(integer_value) => {
+                let value_type = &value_assignment.value_type;
+                let (rust_type, value) =
+                    match self.integer_value(value_name, value_type, *integer_value)? {
+                        Some(value) => (rust_type(value_type, self.config)?, value),
+                        // a literal can't be converted to a user supplied type,
+                        // so these values have the type the override replaced
+                        None => (
+                            builtin_type(asn_module.resolve(value_type))?,
+                            integer_literal(*integer_value),
+                        ),
+                    };
+
+                quote! {
+                    #value_docs
+                    pub const #const_name: #rust_type = #value;
+                }
+            }
+            AsnValue::ObjectIdentifier(arcs) => quote! {
+                #value_docs
+                pub const #const_name: &[u64] = &[#(#arcs),*];
+            },
+            AsnValue::Boolean(value) => quote! {
+                #value_docs
+                pub const #const_name: bool = #value;
+            },
+            AsnValue::String(value) => quote! {
+                #value_docs
+                pub const #const_name: &str = #value;
+            },
+            AsnValue::OctetString(octets) => quote! {
+                #value_docs
+                pub const #const_name: &[u8] = &[#(#octets),*];
+            },
+            AsnValue::Enumerated(item) => {
+                let (rust_type, value) =
+                    self.enumerated_value(value_name, &value_assignment.value_type, item)?;
+                quote! {
+                    #value_docs
+                    pub const #const_name: #rust_type = #value;
+                }
+            }
+        })
+    }
+
+    /// The rust type of an ENUMERATED type, and its variant for the item
+    /// with the given name. Fails if the type is replaced by a user supplied
+    /// type, which the variant can't be converted to.
+    fn enumerated_value(
+        &self,
+        value_name: &str,
+        asn_type: &AsnType,
+        item: &str,
+    ) -> Result<(TokenStream, TokenStream), Error> {
+        let mut asn_type = asn_type;
+        loop {
+            let type_name = match asn_type {
+                AsnType::Custom(type_name) => *type_name,
+                _ => unreachable!("ENUMERATED values are of ENUMERATED types"),
+            };
+            if self.config.types.contains_key(type_name) {
+                return Err(Error::Unsupported(format!(
+                    "the value {} can't be converted to the user supplied type of {}",
+                    value_name, type_name
+                )));
+            }
+            match self.asn_module.type_aliases.get(type_name) {
+                Some(alias) => asn_type = alias,
+                None => {
+                    let rust_type = type_ident(type_name);
+                    let variant = naming::ident(&naming::type_name(item));
+                    return Ok((rust_type.clone(), quote! { #rust_type::#variant }));
+                }
+            }
+        }
+    }
+
+    /// An INTEGER value of the given type. Follows the type aliases of the
+    /// type, in case it is, or is an alias of, a newtype, which the value is
+    /// then wrapped in. Returns `None` if one of the types is replaced by a
+    /// user supplied type, which a literal can't be converted to.
+    fn integer_value(
+        &self,
+        value_name: &str,
+        asn_type: &AsnType,
+        value: i128,
+    ) -> Result<Option<TokenStream>, Error> {
+        let asn_module = self.asn_module;
+        let literal = integer_literal(value);
+
+        let mut asn_type = asn_type;
+        while let AsnType::Custom(type_name) = asn_type {
+            if self.config.types.contains_key(*type_name) {
+                return Ok(None);
+            }
+            if self.is_newtype(type_name) {
+                if let (true, Some((min, max))) = (
+                    self.config.checked,
+                    asn_module.type_aliases.get(type_name).and_then(bounds),
+                ) {
+                    if !(min..=max).contains(&value) {
+                        return Err(Error::Unsupported(format!(
+                            "value {} is out of the range of {}",
+                            value_name, type_name
+                        )));
+                    }
+                }
+                let newtype = type_ident(type_name);
+                return Ok(Some(quote! { #newtype(#literal) }));
+            }
+            match asn_module.type_aliases.get(type_name) {
+                Some(alias) => asn_type = alias,
+                None => break,
+            }
+        }
+
+        Ok(Some(literal))
+    }
+
+    /// The DEFAULT value of a component of a SEQUENCE.
+    fn default_value(&self, type_name: &str, field: &AsnField) -> Result<TokenStream, Error> {
+        let asn_path = format!("{}.{}", type_name, field.name);
+        let unsupported = || {
+            Error::Unsupported(format!(
+                "the DEFAULT value of `{}` can't be converted to its user supplied type",
+                asn_path
+            ))
+        };
+        if self.config.types.contains_key(&asn_path) {
+            return Err(unsupported());
+        }
+
+        Ok(match &self.asn_module.defaults[&(type_name, field.name)] {
+            AsnValue::Integer(value) if is_component_newtype(field, self.config) => {
+                if let (true, Some((min, max))) = (
+                    is_checked_component(field, self.config),
+                    bounds(&field.field_type),
+                ) {
+                    if !(min..=max).contains(value) {
+                        return Err(Error::Unsupported(format!(
+                            "the DEFAULT value of `{}` is out of its range",
+                            asn_path
+                        )));
+                    }
+                }
+                let rust_name = naming::ident(&naming::component_type_name(type_name, field.name));
+                let value = integer_literal(*value);
+                quote! { #rust_name(#value) }
+            }
+            AsnValue::Integer(value) => self
+                .integer_value(&asn_path, &field.field_type, *value)?
+                .ok_or_else(unsupported)?,
+            AsnValue::Boolean(value) => quote! { #value },
+            AsnValue::String(value) => quote! { ::std::string::String::from(#value) },
+            AsnValue::OctetString(octets) => quote! { vec![#(#octets),*] },
+            AsnValue::Enumerated(item) => {
+                self.enumerated_value(&asn_path, &field.field_type, item)?.1
+            }
+            AsnValue::ObjectIdentifier(_) => {
+                return Err(Error::Unsupported(
+                    "OBJECT IDENTIFIER values are only supported in value assignments".into(),
+                ))
+            }
+        })
+    }
+
+    /// A `Default` impl for a SEQUENCE whose components are all OPTIONAL or
+    /// have a DEFAULT value. Other SEQUENCEs have no sensible default.
+    fn default_impl(&self, type_name: &str, sequence: &AsnSequence) -> Result<TokenStream, Error> {
+        if sequence
+            .fields
+            .iter()
+            .any(|field| field.presence == AsnPresence::Required)
+        {
+            return Ok(quote! {});
+        }
+
+        let struct_name = type_ident(type_name);
+        let mut fields = vec![];
+        for field in &sequence.fields {
+            let rust_name = naming::ident(&naming::field_name(field.name));
+            let value = match field.presence {
+                AsnPresence::Default => self.default_value(type_name, field)?,
+                _ => quote! { ::std::option::Option::None },
+            };
+            fields.push(quote! { #rust_name: #value, });
+        }
+
+        Ok(quote! {
+            impl ::std::default::Default for #struct_name {
+                fn default() -> Self {
+                    #struct_name {
+                        #(#fields)*
+                    }
+                }
+            }
+        })
+    }
+
+    /// A builder of a SEQUENCE, which is created from the values of its
+    /// required components and has a setter for each of the others.
+    /// Components with a DEFAULT value which weren't set take that value.
+    fn builder(&self, type_name: &'a str, sequence: &'a AsnSequence) -> Result<TokenStream, Error> {
+        if let Some(field) = sequence.fields.iter().find(|field| {
+            field.presence != AsnPresence::Required && naming::field_name(field.name) == "build"
+        }) {
+            return Err(Error::Unsupported(format!(
+                "component `{}` of SEQUENCE {} collides with the `build` method of its builder",
+                field.name, type_name
+            )));
+        }
+
+        let struct_name = type_ident(type_name);
+        let builder_name = naming::ident(&format!("{}Builder", naming::type_name(type_name)));
+        let visibility = &self.visibility;
+        let builder_docs = format!(" Builds a [`{}`], one component at a time.", struct_name);
+
+        let mut builder_fields = vec![];
+        let mut parameters = vec![];
+        let mut initial_fields = vec![];
+        let mut setters = vec![];
+        let mut built_fields = vec![];
+        for field in &sequence.fields {
+            let name = naming::ident(&naming::field_name(field.name));
+            let value_type = component_type(type_name, field, &self.boxed_fields, self.config)?;
+            // boxed components are boxed by the builder
+            let boxed = self.boxed_fields.contains(&(type_name, field.name))
+                && !self
+                    .config
+                    .types
+                    .contains_key(&format!("{}.{}", type_name, field.name));
+            let (parameter_type, value) = if boxed {
+                (
+                    rust_type(&field.field_type, self.config)?,
+                    quote! { ::std::boxed::Box::new(#name) },
+                )
+            } else {
+                (value_type.clone(), quote! { #name })
+            };
+
+            if field.presence == AsnPresence::Required {
+                builder_fields.push(quote! { #name: #value_type, });
+                parameters.push(quote! { #name: #parameter_type });
+                initial_fields.push(quote! { #name: #value, });
+                built_fields.push(quote! { #name: self.#name, });
+                continue;
+            }
+
+            // OPTIONAL components are set to the value they hold
+            builder_fields.push(quote! { #name: ::std::option::Option<#value_type>, });
+            initial_fields.push(quote! { #name: ::std::option::Option::None, });
+            setters.push(quote! {
+                pub fn #name(mut self, #name: #parameter_type) -> Self {
+                    self.#name = ::std::option::Option::Some(#value);
+                    self
+                }
+            });
+
+            let value = match self.asn_module.defaults.get(&(type_name, field.name)) {
+                None => quote! { self.#name },
+                Some(AsnValue::String(_) | AsnValue::OctetString(_)) => {
+                    let default_value = self.default_value(type_name, field)?;
+                    quote! { self.#name.unwrap_or_else(|| #default_value) }
+                }
+                Some(_) => {
+                    let default_value = self.default_value(type_name, field)?;
+                    quote! { self.#name.unwrap_or(#default_value) }
+                }
+            };
+            built_fields.push(quote! { #name: #value, });
+        }
+
+        Ok(quote! {
+            #[doc = #builder_docs]
+            #[derive(Debug)]
+            #visibility struct #builder_name {
+                #(#builder_fields)*
+            }
+
+            impl #struct_name {
+                #[allow(clippy::too_many_arguments)]
+                pub fn builder(#(#parameters),*) -> #builder_name {
+                    #builder_name {
+                        #(#initial_fields)*
+                    }
+                }
+            }
+
+            impl #builder_name {
+                #(#setters)*
+
+                pub fn build(self) -> #struct_name {
+                    #struct_name {
+                        #(#built_fields)*
+                    }
+                }
+            }
+        })
+    }
+}
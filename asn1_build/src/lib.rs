@@ -0,0 +1,257 @@
+//! Generates rust types from ASN.1 modules.
+//!
+//! This is the library behind the `asn1_codegen::from!` macro. It can also be
+//! used from a build script, which writes the generated code to a file so it
+//! is visible to editors and is only regenerated when the ASN.1 file changes.
+//!
+//! ```no_run
+//! // in the main function of build.rs
+//! asn1_build::Builder::new()
+//!     .file("geo.asn")
+//!     .generate()
+//!     .unwrap();
+//! ```
+//!
+//! The generated file is named after the ASN.1 file, and can be included
+//! with `include!(concat!(env!("OUT_DIR"), "/geo.rs"));`.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+mod config;
+mod error;
+mod generator;
+mod naming;
+mod recursion;
+
+pub use config::{Config, Serde};
+pub use error::Error;
+pub use generator::try_generate_with_config;
+
+/// Formats generated code as rust source.
+pub fn format(tokens: proc_macro2::TokenStream) -> String {
+    let file = syn::parse2::<syn::File>(tokens).expect("generated code should be valid rust");
+    format!(
+        "// @generated by asn1_build, do not edit\n\n{}",
+        prettyplease::unparse(&file)
+    )
+}
+
+/// Generates rust source files for ASN.1 files, for use in build scripts.
+#[derive(Debug, Default)]
+pub struct Builder {
+    files: Vec<PathBuf>,
+    out_dir: Option<PathBuf>,
+    config: Config,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an ASN.1 file to generate code for.
+    pub fn file(mut self, path: impl AsRef<Path>) -> Self {
+        self.files.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the directory generated files are written to. Defaults to the
+    /// `OUT_DIR` of the build script.
+    pub fn out_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the options used to generate code.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Writes one `.rs` file per ASN.1 file, named after the ASN.1 file.
+    /// ASN.1 files which code can't be generated for fail with
+    /// `io::ErrorKind::InvalidData`, wrapping an `asn1_build::Error`.
+    pub fn generate(self) -> io::Result<()> {
+        let out_dir = match self.out_dir {
+            Some(out_dir) => out_dir,
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR is not set"))?,
+        };
+
+        for path in &self.files {
+            println!("cargo:rerun-if-changed={}", path.display());
+
+            let asn1_string = std::fs::read_to_string(path)?;
+            let tokens = try_generate_with_config(&asn1_string, &self.config).map_err(|error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    GenerateError {
+                        path: path.clone(),
+                        error,
+                    },
+                )
+            })?;
+            let source = format(tokens);
+
+            let file_stem = path.file_stem().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "ASN.1 path has no file name")
+            })?;
+            let out_path = out_dir.join(file_stem).with_extension("rs");
+            std::fs::write(out_path, source)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An ASN.1 file which code couldn't be generated for.
+#[derive(Debug)]
+struct GenerateError {
+    path: PathBuf,
+    error: Error,
+}
+
+impl std::fmt::Display for GenerateError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for GenerateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_writes_formatted_file() {
+        let out_dir = std::env::temp_dir().join(format!("asn1_build_{}", std::process::id()));
+        std::fs::create_dir_all(&out_dir).unwrap();
+
+        Builder::new()
+            .file("../test-asn/geo.asn")
+            .out_dir(&out_dir)
+            .generate()
+            .unwrap();
+
+        let source = std::fs::read_to_string(out_dir.join("geo.rs")).unwrap();
+        std::fs::remove_dir_all(&out_dir).unwrap();
+
+        assert!(source.starts_with("// @generated"));
+        assert!(source.contains("/// `x INTEGER`\n    pub x: i128,\n"));
+        // the tabs of the ASN.1 definitions are expanded in doc comments
+        assert!(!source.contains('\t'));
+    }
+
+    #[test]
+    fn builder_errors() {
+        let out_dir =
+            std::env::temp_dir().join(format!("asn1_build_errors_{}", std::process::id()));
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let asn1_path = out_dir.join("broken.asn");
+        std::fs::write(
+            &asn1_path,
+            "Broken DEFINITIONS ::= BEGIN\nPoint ::= SEQUENCE {\nEND\n",
+        )
+        .unwrap();
+
+        let parse_error = Builder::new()
+            .file(&asn1_path)
+            .out_dir(&out_dir)
+            .generate()
+            .unwrap_err();
+        let config = Config {
+            visibility: "pub(".into(),
+            ..Config::default()
+        };
+        let option_error = Builder::new()
+            .file("../test-asn/geo.asn")
+            .out_dir(&out_dir)
+            .config(config)
+            .generate()
+            .unwrap_err();
+        std::fs::remove_dir_all(&out_dir).unwrap();
+
+        assert_eq!(io::ErrorKind::InvalidData, parse_error.kind());
+        assert_eq!(
+            format!(
+                "{}: line 3: unexpected end of ASN.1 input",
+                asn1_path.display()
+            ),
+            parse_error.to_string()
+        );
+        assert_eq!(io::ErrorKind::InvalidData, option_error.kind());
+        let source = std::error::Error::source(option_error.get_ref().unwrap()).unwrap();
+        assert_eq!(
+            Some(&Error::InvalidOption("invalid visibility `pub(`".into())),
+            source.downcast_ref::<Error>()
+        );
+    }
+
+    #[test]
+    fn undefined_type_error() {
+        let asn1_string = "Undefined DEFINITIONS ::= BEGIN\nA ::= SEQUENCE {\n x Foo\n}\nEND";
+        assert_eq!(
+            Err(Error::Parse(asn1_schema::Error {
+                line: 3,
+                message: "type `Foo` is not defined".into(),
+            })),
+            try_generate_with_config(asn1_string, &Config::default()).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn builders_without_serde() {
+        let config = Config {
+            serde: Serde::Disabled,
+            builders: true,
+            ..Config::default()
+        };
+        let asn1_string = include_str!("../../test-asn/defaults.asn");
+        let source = format(try_generate_with_config(asn1_string, &config).unwrap());
+
+        // the generated code doesn't need serde_asn1
+        assert!(source.contains("pub fn builder(id: i128, command: ::std::string::String)"));
+        assert!(!source.contains("serde_asn1"));
+    }
+
+    #[test]
+    fn component_newtype_collision() {
+        let asn1_string = "Colliding DEFINITIONS ::= BEGIN\n\
+            Msg ::= SEQUENCE { code INTEGER { ok(0) } }\n\
+            MsgCode ::= INTEGER\n\
+            END";
+        assert_eq!(
+            Err(Error::Unsupported(
+                "ASN.1 names `MsgCode` and `Msg.code` in module Colliding both map to the rust name `MsgCode`".into()
+            )),
+            try_generate_with_config(asn1_string, &Config::default()).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn generated_in_source_order() {
+        let asn1_string = include_str!("../../test-asn/geo.asn");
+        let generate = || try_generate_with_config(asn1_string, &Config::default()).unwrap();
+        let source = format(generate());
+        assert_eq!(source, format(generate()));
+
+        let positions: Vec<usize> = [
+            "struct Point",
+            "struct Line",
+            "struct Rectangle",
+            "struct TinyRectangle",
+            "struct SmallRectangle",
+        ]
+        .iter()
+        .map(|item| source.find(item).unwrap())
+        .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
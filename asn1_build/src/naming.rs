@@ -0,0 +1,189 @@
+//! Conversion of ASN.1 names into rust identifiers. ASN.1 names may contain
+//! hyphens and may be rust keywords, neither of which is a valid identifier.
+
+use proc_macro2::{Ident, Span};
+use std::collections::HashMap;
+
+use crate::Error;
+
+/// Keywords which can't be used as raw identifiers. An underscore is
+/// appended to these instead.
+const NON_RAW_KEYWORDS: [&str; 4] = ["crate", "self", "Self", "super"];
+
+/// All strict and reserved keywords, as of the 2018 edition.
+const KEYWORDS: [&str; 51] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield", "try",
+];
+
+/// Converts an ASN.1 type reference such as `Message-Header` into a rust
+/// type name, i.e. `MessageHeader`.
+pub fn type_name(asn_name: &str) -> String {
+    asn_name
+        .split('-')
+        .map(|part| {
+            let mut characters = part.chars();
+            match characters.next() {
+                Some(first) => first.to_uppercase().chain(characters).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// The name of the rust type generated for a component of a SEQUENCE or
+/// CHOICE, such as `MessageCode` for the component `code` of `Message`.
+pub fn component_type_name(type_name: &str, component: &str) -> String {
+    self::type_name(type_name) + &self::type_name(component)
+}
+
+/// Converts an ASN.1 identifier such as `max-speed` or `maxSpeed` into a
+/// rust field name, i.e. `max_speed`.
+pub fn field_name(asn_name: &str) -> String {
+    words(asn_name).join("_").to_lowercase()
+}
+
+/// Converts an ASN.1 value reference such as `maxItems` or `id-ce`
+/// into the name of a rust constant, i.e. `MAX_ITEMS` or `ID_CE`.
+pub fn const_name(asn_name: &str) -> String {
+    words(asn_name).join("_").to_uppercase()
+}
+
+/// Splits a name into words at hyphens and at changes from lower to upper
+/// case. A run of upper case letters is kept together as an acronym, with
+/// its last letter starting a new word if that is followed by lower case.
+fn words(name: &str) -> Vec<&str> {
+    let mut words = vec![];
+    let characters: Vec<(usize, char)> = name.char_indices().collect();
+
+    let mut word_start = 0;
+    for (i, &(index, character)) in characters.iter().enumerate() {
+        if character == '-' {
+            if index > word_start {
+                words.push(&name[word_start..index]);
+            }
+            word_start = index + 1;
+            continue;
+        }
+
+        let previous = i.checked_sub(1).map(|i| characters[i].1);
+        let next = characters.get(i + 1).map(|&(_, c)| c);
+        let starts_word = character.is_uppercase()
+            && match previous {
+                Some(previous) if previous.is_lowercase() || previous.is_ascii_digit() => true,
+                Some(previous) if previous.is_uppercase() => next.is_some_and(char::is_lowercase),
+                _ => false,
+            };
+        if starts_word && index > word_start {
+            words.push(&name[word_start..index]);
+            word_start = index;
+        }
+    }
+    if word_start < name.len() {
+        words.push(&name[word_start..]);
+    }
+
+    words
+}
+
+/// Creates an identifier from a converted name, escaping it if it is a keyword.
+pub fn ident(name: &str) -> Ident {
+    if NON_RAW_KEYWORDS.contains(&name) {
+        Ident::new(&format!("{}_", name), Span::call_site())
+    } else if KEYWORDS.contains(&name) {
+        Ident::new_raw(name, Span::call_site())
+    } else {
+        Ident::new(name, Span::call_site())
+    }
+}
+
+/// Fails if two different ASN.1 names are converted to the same rust name
+/// by `convert`, since the generated code wouldn't compile. `context` describes
+/// where the names are defined, for the error message.
+pub fn check_collisions<'a, I, F>(context: &str, names: I, convert: F) -> Result<(), Error>
+where
+    I: IntoIterator<Item = &'a str>,
+    F: Fn(&str) -> String,
+{
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    for asn_name in names {
+        let rust_name = convert(asn_name);
+        if let Some(other) = seen.insert(rust_name.clone(), asn_name) {
+            if other != asn_name {
+                return Err(Error::Unsupported(format!(
+                    "ASN.1 names `{}` and `{}` in {} both map to the rust name `{}`",
+                    other, asn_name, context, rust_name
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_collisions, component_type_name, const_name, field_name, ident, type_name};
+    use crate::Error;
+
+    #[test]
+    fn type_names() {
+        assert_eq!("Point", type_name("Point"));
+        assert_eq!("MessageHeader", type_name("Message-Header"));
+        assert_eq!("Lit", type_name("lit"));
+        assert_eq!("NoError", type_name("noError"));
+        assert_eq!("PDU", type_name("PDU"));
+    }
+
+    #[test]
+    fn component_type_names() {
+        assert_eq!("MessageCode", component_type_name("Message", "code"));
+        assert_eq!("CarMaxSpeed", component_type_name("Car", "max-speed"));
+    }
+
+    #[test]
+    fn field_names() {
+        assert_eq!("x", field_name("x"));
+        assert_eq!("p1", field_name("p1"));
+        assert_eq!("max_speed", field_name("max-speed"));
+        assert_eq!("max_speed", field_name("maxSpeed"));
+        assert_eq!("http_server", field_name("HTTPServer"));
+        assert_eq!("x509_cert", field_name("x509Cert"));
+    }
+
+    #[test]
+    fn const_names() {
+        assert_eq!("MAX_ITEMS", const_name("maxItems"));
+        assert_eq!("ID_CE", const_name("id-ce"));
+        assert_eq!(
+            "ID_CE_SUBJECT_KEY_IDENTIFIER",
+            const_name("id-ce-subjectKeyIdentifier")
+        );
+        assert_eq!("NO_ERROR", const_name("noError"));
+    }
+
+    #[test]
+    fn keywords() {
+        assert_eq!("r#type", ident("type").to_string());
+        assert_eq!("r#match", ident("match").to_string());
+        assert_eq!("self_", ident("self").to_string());
+        assert_eq!("speed", ident("speed").to_string());
+    }
+
+    #[test]
+    fn collisions() {
+        assert_eq!(
+            Err(Error::Unsupported(
+                "ASN.1 names `max-speed` and `maxSpeed` in SEQUENCE Car both map to the rust name `max_speed`".into()
+            )),
+            check_collisions("SEQUENCE Car", vec!["max-speed", "maxSpeed"], field_name)
+        );
+        assert_eq!(
+            Ok(()),
+            check_collisions("SEQUENCE Car", vec!["speed", "speed"], field_name)
+        );
+    }
+}
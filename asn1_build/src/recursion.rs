@@ -0,0 +1,247 @@
+//! Detection of recursive types. A rust type can't contain itself
+//! directly, so some of the fields which form a cycle need to be boxed.
+
+use std::collections::HashSet;
+
+use asn1_schema::ast::{AsnField, AsnModule, AsnType};
+
+/// Cycles with more candidate fields than this are broken using the
+/// back edges of a depth first search, rather than searching for the
+/// smallest set of fields to box.
+const MAX_EXHAUSTIVE_SEARCH_EDGES: usize = 16;
+
+/// A field of one type which refers to another type.
+struct Edge<'a> {
+    from: usize,
+    field: &'a str,
+    to: usize,
+}
+
+/// Returns the `(type name, field name)` pairs of the fields which need to be
+/// boxed so that none of the generated types contain themselves.
+///
+/// Only fields which directly, or through type aliases, refer to a SEQUENCE or
+/// CHOICE are considered. A SEQUENCE OF field is stored in a `Vec`, which is
+/// already a level of indirection.
+pub fn boxed_fields<'a>(asn_module: &AsnModule<'a>) -> HashSet<(&'a str, &'a str)> {
+    // sort the type names so the same fields are boxed on every build
+    let mut type_names: Vec<&str> = asn_module
+        .sequences
+        .keys()
+        .chain(asn_module.choices.keys())
+        .copied()
+        .collect();
+    type_names.sort_unstable();
+
+    let mut edges = vec![];
+    for (from, type_name) in type_names.iter().enumerate() {
+        for field in fields(asn_module, type_name) {
+            let referenced = referenced_type(&field.field_type, asn_module);
+            if let Some(to) = referenced.and_then(|t| type_names.iter().position(|n| *n == t)) {
+                edges.push(Edge {
+                    from,
+                    field: field.name,
+                    to,
+                });
+            }
+        }
+    }
+
+    // Each strongly connected component of the type graph is handled on its
+    // own, identified by the first type in it. Every edge within a component
+    // is part of some cycle.
+    let reachable = reachability(type_names.len(), &edges);
+    let component_of: Vec<usize> = (0..type_names.len())
+        .map(|t| {
+            (0..type_names.len())
+                .find(|&other| reachable[t][other] && reachable[other][t])
+                .unwrap()
+        })
+        .collect();
+
+    let mut boxed = HashSet::new();
+    for component in 0..type_names.len() {
+        let cycle_edges: Vec<&Edge> = edges
+            .iter()
+            .filter(|edge| {
+                component_of[edge.from] == component && component_of[edge.to] == component
+            })
+            .collect();
+        if cycle_edges.is_empty() {
+            continue;
+        }
+
+        for edge in edges_to_box(type_names.len(), &cycle_edges) {
+            boxed.insert((type_names[edge.from], edge.field));
+        }
+    }
+
+    boxed
+}
+
+fn fields<'a, 'b>(asn_module: &'b AsnModule<'a>, type_name: &str) -> &'b [AsnField<'a>] {
+    if let Some(sequence) = asn_module.sequences.get(type_name) {
+        &sequence.fields
+    } else {
+        &asn_module.choices[type_name].alternatives
+    }
+}
+
+/// Returns the name of the SEQUENCE or CHOICE which is stored inline
+/// in a field of the given type, if there is one.
+fn referenced_type<'a>(asn_type: &AsnType<'a>, asn_module: &AsnModule<'a>) -> Option<&'a str> {
+    match asn_module.resolve(asn_type) {
+        AsnType::Custom(type_name) => Some(type_name),
+        _ => None,
+    }
+}
+
+/// `reachable[a][b]` is true if type `b` can be reached from type `a` by
+/// following zero or more edges.
+fn reachability(type_count: usize, edges: &[Edge]) -> Vec<Vec<bool>> {
+    let mut reachable = vec![vec![false; type_count]; type_count];
+    for (start, reachable_from_start) in reachable.iter_mut().enumerate() {
+        let mut stack = vec![start];
+        while let Some(t) = stack.pop() {
+            if reachable_from_start[t] {
+                continue;
+            }
+            reachable_from_start[t] = true;
+            stack.extend(edges.iter().filter(|e| e.from == t).map(|e| e.to));
+        }
+    }
+
+    reachable
+}
+
+/// Picks the edges of a strongly connected component to box. The smallest
+/// set of edges which breaks every cycle is searched for, trying sets in
+/// order of size, unless there are too many edges for that to be feasible.
+fn edges_to_box<'e, 'a>(type_count: usize, cycle_edges: &[&'e Edge<'a>]) -> Vec<&'e Edge<'a>> {
+    if cycle_edges.len() <= MAX_EXHAUSTIVE_SEARCH_EDGES {
+        for size in 1..=cycle_edges.len() {
+            let mut chosen = vec![];
+            if find_breaking_subset(type_count, cycle_edges, size, 0, &mut chosen) {
+                return chosen.into_iter().map(|i| cycle_edges[i]).collect();
+            }
+        }
+    }
+
+    back_edges(type_count, cycle_edges)
+}
+
+/// Recursively tries every combination of `size` edges starting at `start`,
+/// returning true once `chosen` holds a combination that breaks all cycles.
+fn find_breaking_subset(
+    type_count: usize,
+    cycle_edges: &[&Edge],
+    size: usize,
+    start: usize,
+    chosen: &mut Vec<usize>,
+) -> bool {
+    if chosen.len() == size {
+        let remaining: Vec<&Edge> = cycle_edges
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !chosen.contains(i))
+            .map(|(_, edge)| *edge)
+            .collect();
+        return is_acyclic(type_count, &remaining);
+    }
+
+    for i in start..cycle_edges.len() {
+        chosen.push(i);
+        if find_breaking_subset(type_count, cycle_edges, size, i + 1, chosen) {
+            return true;
+        }
+        chosen.pop();
+    }
+
+    false
+}
+
+/// Kahn's algorithm, repeatedly removing types with no incoming edges.
+fn is_acyclic(type_count: usize, edges: &[&Edge]) -> bool {
+    let mut incoming = vec![0; type_count];
+    for edge in edges {
+        incoming[edge.to] += 1;
+    }
+
+    let mut ready: Vec<usize> = (0..type_count).filter(|&t| incoming[t] == 0).collect();
+    let mut removed = 0;
+    while let Some(t) = ready.pop() {
+        removed += 1;
+        for edge in edges.iter().filter(|e| e.from == t) {
+            incoming[edge.to] -= 1;
+            if incoming[edge.to] == 0 {
+                ready.push(edge.to);
+            }
+        }
+    }
+
+    removed == type_count
+}
+
+/// The edges which point back to a type still being visited by a depth
+/// first search. Removing them is guaranteed to break every cycle.
+fn back_edges<'e, 'a>(type_count: usize, cycle_edges: &[&'e Edge<'a>]) -> Vec<&'e Edge<'a>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        NotVisited,
+        InProgress,
+        Done,
+    }
+
+    fn visit<'e, 'a>(
+        t: usize,
+        cycle_edges: &[&'e Edge<'a>],
+        states: &mut [State],
+        out: &mut Vec<&'e Edge<'a>>,
+    ) {
+        states[t] = State::InProgress;
+        for edge in cycle_edges.iter().filter(|e| e.from == t) {
+            match states[edge.to] {
+                State::NotVisited => visit(edge.to, cycle_edges, states, out),
+                State::InProgress => out.push(edge),
+                State::Done => {}
+            }
+        }
+        states[t] = State::Done;
+    }
+
+    let mut states = vec![State::NotVisited; type_count];
+    let mut out = vec![];
+    for t in 0..type_count {
+        if states[t] == State::NotVisited {
+            visit(t, cycle_edges, &mut states, &mut out);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::boxed_fields;
+    use asn1_schema::ast::AsnModule;
+    use std::collections::HashSet;
+
+    #[test]
+    fn recursive() {
+        let asn1_string = include_str!("../../test-asn/recursive.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        // Node only refers to itself through a SEQUENCE OF, and boxing Expr.sum
+        // is enough to break both cycles through Sum.
+        let expected: HashSet<_> = vec![("Expr", "neg"), ("Expr", "sum")].into_iter().collect();
+        assert_eq!(expected, boxed_fields(&asn_module));
+    }
+
+    #[test]
+    fn not_recursive() {
+        let asn1_string = include_str!("../../test-asn/geo.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        assert!(boxed_fields(&asn_module).is_empty());
+    }
+}
@@ -1,82 +1,172 @@
 extern crate proc_macro;
 
-use crate::proc_macro::{TokenStream, TokenTree};
-use proc_macro2::{Ident, Span};
-use quote::quote;
+use crate::proc_macro::TokenStream;
+use asn1_build::{Config, Serde};
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parenthesized, Attribute, Ident, LitStr, Path, Token, Type, Visibility};
 
-mod asn_parser;
-use asn_parser::{AsnModule, AsnType};
+/// The path of the ASN.1 file, followed by optional code generation options.
+struct Input {
+    path: LitStr,
+    config: Config,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let mut config = Config::default();
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
 
-fn parse_input(input: TokenStream) -> String {
-    for token in input {
-        if let TokenTree::Literal(s) = token {
-            return format!("{}", s);
+            let option: Ident = input.parse()?;
+            match option.to_string().as_str() {
+                "visibility" => {
+                    input.parse::<Token![=]>()?;
+                    let visibility: Visibility = input.parse()?;
+                    config.visibility = visibility.into_token_stream().to_string();
+                }
+                "derive" => {
+                    let content;
+                    parenthesized!(content in input);
+                    let derives = Punctuated::<Path, Token![,]>::parse_terminated(&content)?;
+                    config.derives.extend(
+                        derives
+                            .iter()
+                            .map(|derive| derive.into_token_stream().to_string()),
+                    );
+                }
+                "serde" => {
+                    input.parse::<Token![=]>()?;
+                    let serde: Ident = input.parse()?;
+                    config.serde = match serde.to_string().as_str() {
+                        "serde_derive" => Serde::SerdeDerive,
+                        "serde" => Serde::Serde,
+                        "none" => Serde::Disabled,
+                        _ => {
+                            return Err(syn::Error::new(
+                                serde.span(),
+                                "expected `serde_derive`, `serde` or `none`",
+                            ))
+                        }
+                    };
+                }
+                "attributes" => {
+                    let content;
+                    parenthesized!(content in input);
+                    let asn_name: LitStr = content.parse()?;
+                    content.parse::<Token![,]>()?;
+                    let attributes = content.call(Attribute::parse_outer)?;
+                    config
+                        .attributes
+                        .entry(asn_name.value())
+                        .or_default()
+                        .extend(
+                            attributes
+                                .iter()
+                                .map(|attribute| attribute.into_token_stream().to_string()),
+                        );
+                }
+                "newtypes" => config.newtypes = true,
+                "checked_newtypes" => config.checked = true,
+                "validate" => config.validate = true,
+                "defaults" => config.defaults = true,
+                "builders" => config.builders = true,
+                "types" => {
+                    let content;
+                    parenthesized!(content in input);
+                    let types = Punctuated::<TypeOverride, Token![,]>::parse_terminated(&content)?;
+                    config.types.extend(types.into_iter().map(|type_override| {
+                        (
+                            type_override.asn_path.value(),
+                            type_override.rust_type.into_token_stream().to_string(),
+                        )
+                    }));
+                }
+                _ => return Err(syn::Error::new(
+                    option.span(),
+                    "expected `visibility`, `derive`, `serde`, `attributes`, `types`, `newtypes`, `checked_newtypes`, `validate`, `defaults` or `builders`",
+                )),
+            }
         }
-        panic!("need to pass a string literal");
+
+        Ok(Input { path, config })
+    }
+}
+
+/// A rust type replacing an ASN.1 type or component, as in
+/// `"Line.p1" = crate::Point`.
+struct TypeOverride {
+    asn_path: LitStr,
+    rust_type: Type,
+}
+
+impl Parse for TypeOverride {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let asn_path = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let rust_type = input.parse()?;
+        Ok(TypeOverride {
+            asn_path,
+            rust_type,
+        })
     }
-    panic!("need to pass a string literal");
 }
 
+/// Generates rust types for an ASN.1 file, given by its path relative to the
+/// crate root. Options may follow the path:
+///
+/// ```ignore
+/// asn1_codegen::from!(
+///     "geo.asn",
+///     visibility = pub(crate),
+///     derive(Clone, Eq),
+///     serde = serde,
+///     attributes("Point", #[derive(Hash)]),
+///     types("Unsigned16" = u32, "Line.p1" = crate::Point),
+///     newtypes,
+///     validate,
+///     defaults,
+///     builders,
+/// );
+/// ```
 #[proc_macro]
 pub fn from(input: TokenStream) -> TokenStream {
-    let crate_root = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let input = match syn::parse::<Input>(input) {
+        Ok(input) => input,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let crate_root = match std::env::var("CARGO_MANIFEST_DIR") {
+        Ok(crate_root) => crate_root,
+        Err(error) => return compile_error(&input.path, error),
+    };
     let mut path = std::path::PathBuf::from(crate_root);
-    let input_path_string = parse_input(input);
-    let i = input_path_string
-        .trim_start_matches('"')
-        .trim_end_matches('"');
-    let input_path = std::path::PathBuf::from(&i);
-    path.push(&input_path);
+    path.push(input.path.value());
 
     if !path.is_file() {
-        panic!("Must provide path to a file");
+        return compile_error(&input.path, "Must provide path to a file");
     }
-    let asn1_string = std::fs::read_to_string(path).unwrap();
-    let asn_module = AsnModule::from(&*asn1_string);
-
-    let mut out = TokenStream::new();
+    let asn1_string = match std::fs::read_to_string(&path) {
+        Ok(asn1_string) => asn1_string,
+        Err(error) => return compile_error(&input.path, error),
+    };
 
-    for (struct_name, sequence) in &asn_module.sequences {
-        let struct_name = Ident::new(struct_name, Span::call_site());
-        let fields = sequence.fields.iter().map(|field| {
-            let field_type = if let AsnType::Custom(type_name) = field.field_type {
-                // if the type is custom, check for it in the known type aliases
-                if let Some(t) = &asn_module.type_aliases.get(type_name) {
-                    t
-                } else {
-                    &field.field_type
-                }
-            } else {
-                &field.field_type
-            };
-            let rust_field_type_as_string = match field_type {
-                AsnType::Integer => "i128",
-                // make note somewhere that the generated rust code doesn't enforce
-                // ranges which don't fall on std lib type boundaries
-                AsnType::BoundedInteger { min, max } => match (min, max) {
-                    (0..=255, 0..=255) => "u8",
-                    (0..=65535, 0..=65535) => "u16",
-                    (0..=18446744073709551615, 0..=18446744073709551615) => "u64",
-                    (min, max) => panic!("min: {}, max: {}", min, max),
-                },
-                AsnType::Custom(t) => t,
-            };
-            let name = Ident::new(field.name, Span::call_site());
-            let rust_field_type = Ident::new(rust_field_type_as_string, Span::call_site());
-            quote! {
-                pub #name : #rust_field_type ,
-            }
-        });
-
-        let gen: TokenStream = quote! {
-            #[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, PartialEq)]
-            struct #struct_name {
-                #(#fields)*
-            }
-        }
-        .into();
-        out.extend(gen);
+    match asn1_build::try_generate_with_config(&asn1_string, &input.config) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => compile_error(&input.path, error),
     }
+}
 
-    out
+/// Reports an error at the path of the ASN.1 file, rather than panicking,
+/// so the compiler points at the macro input.
+fn compile_error(path: &LitStr, error: impl std::fmt::Display) -> TokenStream {
+    syn::Error::new_spanned(path, error)
+        .to_compile_error()
+        .into()
 }
@@ -0,0 +1,361 @@
+//! Generates rust source from ASN.1 files, for projects which check the
+//! generated code in rather than generating it at build time.
+
+use asn1_build::{Config, Serde};
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "\
+Usage: asn1-compile [OPTIONS] <FILE>...
+
+Options:
+    -o, --output <PATH>       File to write, or directory with `--layout files`.
+                              Writes to stdout if omitted.
+        --visibility <VIS>    Visibility of generated types, e.g. `pub(crate)`
+        --derive <PATH>       Additional trait to derive, may be repeated
+        --serde <MODE>        `serde_derive` (default) or `serde` to choose the
+                              crate the serde traits are derived with, or
+                              `none` to not derive them
+        --attribute <TYPE>=<ATTRIBUTE>
+                              Attribute to add to the type generated for the
+                              named ASN.1 type, may be repeated
+        --type <TYPE>=<PATH>  Rust type to use in place of the named ASN.1 type,
+                              or component given as `Type.component`, may be
+                              repeated
+        --newtypes            Generate constrained type aliases as newtypes
+        --checked-newtypes    Generate constrained integer type aliases and
+                              components as newtypes which only allow values
+                              in range
+        --validate            Implement `serde_asn1::Validate` for generated
+                              types
+        --defaults            Implement `Default` for SEQUENCEs whose
+                              components are all OPTIONAL or DEFAULT
+        --builders            Generate a builder for each SEQUENCE
+        --layout <LAYOUT>     `flat` (default) puts all types in one file,
+                              `modules` wraps each ASN.1 module in a `mod`,
+                              `files` writes one file per ASN.1 file
+        --check               Fail if the output is not up to date, rather
+                              than writing it
+    -h, --help                Print this message";
+
+#[derive(Debug, PartialEq)]
+enum Layout {
+    Flat,
+    Modules,
+    Files,
+}
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    files: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    config: Config,
+    layout: Layout,
+    check: bool,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        files: vec![],
+        output: None,
+        config: Config::default(),
+        layout: Layout::Flat,
+        check: false,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for `{}`", arg))
+        };
+        match arg.as_str() {
+            "-o" | "--output" => parsed.output = Some(PathBuf::from(value()?)),
+            "--visibility" => parsed
+                .config
+                .set_visibility(&value()?)
+                .map_err(|e| e.to_string())?,
+            "--derive" => parsed
+                .config
+                .add_derive(&value()?)
+                .map_err(|e| e.to_string())?,
+            "--serde" => {
+                parsed.config.serde = match value()?.as_str() {
+                    "serde_derive" => Serde::SerdeDerive,
+                    "serde" => Serde::Serde,
+                    "none" => Serde::Disabled,
+                    serde => return Err(format!("unknown serde mode `{}`", serde)),
+                }
+            }
+            "--attribute" => {
+                let value = value()?;
+                let (asn_name, attribute) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected <TYPE>=<ATTRIBUTE>, got `{}`", value))?;
+                parsed
+                    .config
+                    .add_attribute(asn_name, attribute)
+                    .map_err(|e| e.to_string())?;
+            }
+            "--type" => {
+                let value = value()?;
+                let (asn_path, rust_type) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected <TYPE>=<PATH>, got `{}`", value))?;
+                parsed
+                    .config
+                    .set_type(asn_path, rust_type)
+                    .map_err(|e| e.to_string())?;
+            }
+            "--layout" => {
+                parsed.layout = match value()?.as_str() {
+                    "flat" => Layout::Flat,
+                    "modules" => Layout::Modules,
+                    "files" => Layout::Files,
+                    layout => return Err(format!("unknown layout `{}`", layout)),
+                }
+            }
+            "--newtypes" => parsed.config.newtypes = true,
+            "--checked-newtypes" => parsed.config.checked = true,
+            "--validate" => parsed.config.validate = true,
+            "--defaults" => parsed.config.defaults = true,
+            "--builders" => parsed.config.builders = true,
+            "--check" => parsed.check = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ => parsed.files.push(PathBuf::from(arg)),
+        }
+    }
+
+    if parsed.files.is_empty() {
+        return Err("no ASN.1 files given".into());
+    }
+    if parsed.output.is_none() && (parsed.check || parsed.layout == Layout::Files) {
+        return Err("an output path is required".into());
+    }
+    parsed.config.module = parsed.layout == Layout::Modules;
+
+    Ok(parsed)
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))
+}
+
+/// Generates the code for an ASN.1 file, with errors naming the file.
+fn generate(path: &Path, config: &Config) -> Result<proc_macro2::TokenStream, String> {
+    asn1_build::try_generate_with_config(&read(path)?, config)
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Generates the source of each output file. The path is `None` for stdout.
+fn outputs(args: &Args) -> Result<Vec<(Option<PathBuf>, String)>, String> {
+    if args.layout == Layout::Files {
+        let out_dir = args.output.as_ref().unwrap();
+        return args
+            .files
+            .iter()
+            .map(|path| {
+                let tokens = generate(path, &args.config)?;
+                let file_stem = path
+                    .file_stem()
+                    .ok_or_else(|| format!("{} has no file name", path.display()))?;
+                let out_path = out_dir.join(file_stem).with_extension("rs");
+                Ok((Some(out_path), asn1_build::format(tokens)))
+            })
+            .collect();
+    }
+
+    let mut tokens = proc_macro2::TokenStream::new();
+    for path in &args.files {
+        tokens.extend(generate(path, &args.config)?);
+    }
+    Ok(vec![(args.output.clone(), asn1_build::format(tokens))])
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let outputs = outputs(args)?;
+
+    if args.check {
+        let stale: Vec<String> = outputs
+            .iter()
+            .filter_map(|(path, source)| {
+                let path = path.as_ref().unwrap();
+                match std::fs::read_to_string(path) {
+                    Ok(existing) if existing == *source => None,
+                    _ => Some(path.display().to_string()),
+                }
+            })
+            .collect();
+        if !stale.is_empty() {
+            return Err(format!(
+                "generated code is out of date: {}",
+                stale.join(", ")
+            ));
+        }
+        return Ok(());
+    }
+
+    for (path, source) in outputs {
+        match path {
+            Some(path) => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)
+                        .map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+                }
+                std::fs::write(&path, source)
+                    .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+            }
+            None => print!("{}", source),
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+
+    if let Err(e) = parse_args(args).and_then(|args| run(&args)) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse() {
+        let parsed = args(&[
+            "a.asn",
+            "--visibility",
+            "pub(crate)",
+            "--derive",
+            "Clone",
+            "--derive",
+            "Eq",
+            "--serde",
+            "none",
+            "--attribute",
+            "Point=#[derive(Default)]",
+            "--type",
+            "Line.p1=crate::Point",
+            "--newtypes",
+            "--checked-newtypes",
+            "--validate",
+            "--defaults",
+            "--builders",
+            "--layout",
+            "modules",
+            "-o",
+            "out.rs",
+            "b.asn",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            Args {
+                files: vec![PathBuf::from("a.asn"), PathBuf::from("b.asn")],
+                output: Some(PathBuf::from("out.rs")),
+                config: Config {
+                    visibility: "pub(crate)".into(),
+                    derives: vec!["Clone".into(), "Eq".into()],
+                    module: true,
+                    serde: Serde::Disabled,
+                    attributes: vec![("Point".into(), vec!["#[derive(Default)]".into()])]
+                        .into_iter()
+                        .collect(),
+                    types: vec![("Line.p1".into(), "crate::Point".into())]
+                        .into_iter()
+                        .collect(),
+                    newtypes: true,
+                    checked: true,
+                    validate: true,
+                    defaults: true,
+                    builders: true,
+                },
+                layout: Layout::Modules,
+                check: false,
+            },
+            parsed
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(args(&[]).is_err());
+        assert!(args(&["a.asn", "--layout"]).is_err());
+        assert!(args(&["a.asn", "--layout", "nested"]).is_err());
+        assert!(args(&["a.asn", "--unknown"]).is_err());
+        assert!(args(&["a.asn", "--serde", "miniserde"]).is_err());
+        assert!(args(&["a.asn", "--attribute", "Point"]).is_err());
+        assert!(args(&["a.asn", "--check"]).is_err());
+        assert!(args(&["a.asn", "--layout", "files"]).is_err());
+        assert_eq!(
+            Err("invalid visibility `pub(`".into()),
+            args(&["a.asn", "--visibility", "pub("])
+        );
+        assert!(args(&["a.asn", "--derive", "Clone +"]).is_err());
+        assert!(args(&["a.asn", "--attribute", "Point=derive(Default)"]).is_err());
+        assert!(args(&["a.asn", "--type", "Point=crate::"]).is_err());
+    }
+
+    #[test]
+    fn generate_errors() {
+        let schema = std::env::temp_dir().join(format!("asn1_compile_{}.asn", std::process::id()));
+        std::fs::write(
+            &schema,
+            "Broken DEFINITIONS ::= BEGIN\nPoint ::= SEQUENCE {\nEND\n",
+        )
+        .unwrap();
+        let result = run(&args(&[schema.to_str().unwrap()]).unwrap());
+        std::fs::remove_file(&schema).unwrap();
+        let error = result.unwrap_err();
+        assert!(error.starts_with(&format!("{}: line ", schema.display())));
+
+        // options which are valid rust but don't match the module
+        let unknown = args(&[
+            "../test-asn/geo.asn",
+            "--attribute",
+            "Circle=#[derive(Hash)]",
+        ]);
+        assert_eq!(
+            Err("../test-asn/geo.asn: attributes given for unknown type `Circle`".into()),
+            run(&unknown.unwrap())
+        );
+    }
+
+    #[test]
+    fn check() {
+        let out_dir = std::env::temp_dir().join(format!("asn1_compile_{}", std::process::id()));
+        let out_dir = out_dir.to_str().unwrap();
+        let write = args(&["../test-asn/geo.asn", "--layout", "files", "-o", out_dir]).unwrap();
+        let check = args(&[
+            "../test-asn/geo.asn",
+            "--layout",
+            "files",
+            "-o",
+            out_dir,
+            "--check",
+        ])
+        .unwrap();
+
+        assert!(run(&check).is_err());
+        run(&write).unwrap();
+        assert!(run(&check).is_ok());
+
+        let out_path = Path::new(out_dir).join("geo.rs");
+        std::fs::write(&out_path, "struct Point;").unwrap();
+        assert!(run(&check).is_err());
+
+        std::fs::remove_dir_all(out_dir).unwrap();
+    }
+}
@@ -0,0 +1,51 @@
+//! Runs the `asn1-compile` binary, to check how it reports user errors.
+
+use std::process::{Command, Output};
+
+fn asn1_compile(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_asn1-compile"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Checks that the command failed with the given error, rather than
+/// panicking.
+fn assert_error(output: &Output, message: &str) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(Some(1), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert_eq!(format!("error: {}\n", message), stderr);
+}
+
+#[test]
+fn bad_schema() {
+    let schema = std::env::temp_dir().join(format!("asn1_compile_cli_{}.asn", std::process::id()));
+    std::fs::write(
+        &schema,
+        "Broken DEFINITIONS ::= BEGIN\nPoint ::= SEQUENCE {\nEND\n",
+    )
+    .unwrap();
+    let output = asn1_compile(&[schema.to_str().unwrap()]);
+    std::fs::remove_file(&schema).unwrap();
+
+    assert_error(
+        &output,
+        &format!(
+            "{}: line 3: unexpected end of ASN.1 input",
+            schema.display()
+        ),
+    );
+}
+
+#[test]
+fn bad_option() {
+    let output = asn1_compile(&["../test-asn/geo.asn", "--visibility", "pub("]);
+    assert_error(&output, "invalid visibility `pub(`");
+
+    let output = asn1_compile(&["../test-asn/geo.asn", "--type", "Circle=crate::Circle"]);
+    assert_error(
+        &output,
+        "../test-asn/geo.asn: type given for unknown type or component `Circle`",
+    );
+}
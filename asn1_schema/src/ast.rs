@@ -0,0 +1,2377 @@
+//! The syntax tree of an ASN.1 module, which borrows from the source it is
+//! parsed from. This is what code generation works with, so it follows the
+//! ASN.1 source closely, and may change along with the generated code. The
+//! types in the crate root are the stable view of a module.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::{Error, TagClass, TagDefault, TagMode};
+
+type Result<T> = std::result::Result<T, Error>;
+
+pub struct AsnModule<'a> {
+    pub name: &'a str,
+    /// How the tags of types are applied, and whether components are
+    /// tagged automatically.
+    pub tag_default: TagDefault,
+    /// The names of all type and value assignments, in the order they
+    /// appear in the module.
+    pub assignments: Vec<&'a str>,
+    pub sequences: HashMap<&'a str, AsnSequence<'a>>,
+    pub choices: HashMap<&'a str, AsnChoice<'a>>,
+    pub type_aliases: HashMap<&'a str, AsnType<'a>>,
+    /// Named number lists of INTEGER type assignments, keyed by the
+    /// name of the type. The named numbers of INTEGER components are kept
+    /// with the component instead.
+    pub named_numbers: HashMap<&'a str, Vec<AsnNamedNumber<'a>>>,
+    /// ENUMERATED type assignments, keyed by the name of the type.
+    pub enumerations: HashMap<&'a str, AsnEnumerated<'a>>,
+    pub values: HashMap<&'a str, AsnValueAssignment<'a>>,
+    /// The DEFAULT values of SEQUENCE components, keyed by the names of the
+    /// SEQUENCE and of the component.
+    pub defaults: HashMap<(&'a str, &'a str), AsnValue>,
+    /// The tags written before the types of type assignments, keyed by the
+    /// name of the type.
+    pub tags: HashMap<&'a str, AsnTag>,
+    /// Documentation of the type and value assignments, keyed by their names.
+    pub docs: HashMap<&'a str, AsnDocs<'a>>,
+}
+
+impl<'a> AsnModule<'a> {
+    /// Follows references to type aliases, including chains of them, to the
+    /// type they stand for. References to a SEQUENCE or CHOICE are returned
+    /// as they are.
+    pub fn resolve<'b>(&'b self, mut asn_type: &'b AsnType<'a>) -> &'b AsnType<'a> {
+        // parsing fails if there is a cycle of aliases, so this ends
+        while let AsnType::Custom(type_name) = asn_type {
+            match self.type_aliases.get(type_name) {
+                Some(alias) => asn_type = alias,
+                None => break,
+            }
+        }
+
+        asn_type
+    }
+}
+
+/// A SEQUENCE, or a SET, whose components may be encoded in any order.
+pub struct AsnSequence<'a> {
+    // Needs to be a vec to maintain field order
+    pub fields: Vec<AsnField<'a>>,
+    pub set: bool,
+    /// Whether the fields have an extension marker.
+    pub extensible: bool,
+}
+
+pub struct AsnChoice<'a> {
+    // Needs to be a vec to maintain alternative order, which
+    // determines the automatically assigned tags
+    pub alternatives: Vec<AsnField<'a>>,
+    /// Whether the alternatives have an extension marker.
+    pub extensible: bool,
+}
+
+pub struct AsnField<'a> {
+    pub name: &'a str,
+    pub field_type: AsnType<'a>,
+    /// The named numbers of an INTEGER type written in place, such as
+    /// `code INTEGER { ok(0) }`.
+    pub named_numbers: Vec<AsnNamedNumber<'a>>,
+    /// The tag written before the type, if any.
+    pub tag: Option<AsnTag>,
+    pub presence: AsnPresence,
+    /// Rec.ITU-T X.680 25.1
+    /// Whether the field follows the extension marker, as an extension
+    /// addition.
+    pub extension: bool,
+    pub docs: AsnDocs<'a>,
+}
+
+/// Rec.ITU-T X.680 25.1
+/// Whether a component of a SEQUENCE may be left out of its values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AsnPresence {
+    Required,
+    Optional,
+    /// The component has a DEFAULT value, which is kept in the `defaults`
+    /// of the module.
+    Default,
+}
+
+/// Rec.ITU-T X.680 31.1
+/// A tag as written before a type, such as `[APPLICATION 3] IMPLICIT`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AsnTag {
+    pub class: TagClass,
+    pub number: u32,
+    /// Whether the tag is IMPLICIT or EXPLICIT, if that is given.
+    pub mode: Option<TagMode>,
+}
+
+/// The comments attached to an assignment or component, and its definition
+/// as written in the ASN.1 source.
+#[derive(Debug, Default, PartialEq)]
+pub struct AsnDocs<'a> {
+    /// The lines of the comments, without the comment delimiters.
+    pub comments: Vec<&'a str>,
+    pub definition: &'a str,
+}
+
+/// A named number of an INTEGER type, such as `busy(1)`
+/// in `INTEGER { ok(0), busy(1) }`.
+#[derive(Debug, PartialEq)]
+pub struct AsnNamedNumber<'a> {
+    pub name: &'a str,
+    pub value: i128,
+}
+
+/// Rec.ITU-T X.680 20
+/// An ENUMERATED type, such as `ENUMERATED { red, green(5), ..., blue }`.
+#[derive(Debug, PartialEq)]
+pub struct AsnEnumerated<'a> {
+    /// The items in the order they are written in, with their numbers.
+    pub items: Vec<AsnEnumerationItem<'a>>,
+    /// Whether the items have an extension marker.
+    pub extensible: bool,
+}
+
+/// An item of an ENUMERATED type, such as `green(5)`.
+#[derive(Debug, PartialEq)]
+pub struct AsnEnumerationItem<'a> {
+    pub name: &'a str,
+    /// The number of the item, which is assigned to items without one.
+    pub value: i128,
+    /// Whether the item follows the extension marker, as an extension
+    /// addition.
+    pub extension: bool,
+}
+
+/// A value assignment, such as `maxItems INTEGER ::= 64`.
+pub struct AsnValueAssignment<'a> {
+    pub value_type: AsnType<'a>,
+    pub value: AsnValue,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AsnType<'a> {
+    /// ASN1 default integer type with no bounds specified.
+    Integer,
+    /// ASN1 default integer type with user specified bounds, which may be
+    /// extensible, as in `INTEGER (0..7, ...)`. A bound of `None` is MIN or
+    /// MAX, as in `INTEGER (0..MAX)`.
+    BoundedInteger {
+        min: Option<i128>,
+        max: Option<i128>,
+        extensible: bool,
+    },
+    /// ASN1 object identifier.
+    ObjectIdentifier,
+    /// ASN1 boolean.
+    Boolean,
+    /// ASN1 OCTET STRING.
+    OctetString,
+    /// One of the restricted character string types, such as `UTF8String`
+    /// or `IA5String`, given by its name.
+    CharacterString(&'a str),
+    /// ASN1 SEQUENCE OF the given type.
+    SequenceOf(Box<AsnType<'a>>),
+    /// ASN1 SET OF the given type, whose elements are unordered.
+    SetOf(Box<AsnType<'a>>),
+    /// Custom type defined by the user. At code generation,
+    /// check to see if the type is included in type aliases,
+    /// and if so, replace the type alias with the concrete type.
+    /// Otherwise, use the custom name as is, under the assumption
+    /// that it is defined already as a sequence or enum.
+    Custom(&'a str),
+    /// Reference to an integer type, with user specified bounds,
+    /// such as `Velocity (0..100)`.
+    ConstrainedReference {
+        name: &'a str,
+        min: Option<i128>,
+        max: Option<i128>,
+        extensible: bool,
+    },
+    /// A character string, OCTET STRING, SEQUENCE OF or reference to one of
+    /// them, with a SIZE or permitted alphabet constraint.
+    Constrained(Box<AsnType<'a>>, AsnConstraint),
+}
+
+/// Rec.ITU-T X.680 51.5 and 51.7
+/// The SIZE and permitted alphabet (FROM) constraints of a type.
+#[derive(Debug, Default, PartialEq)]
+pub struct AsnConstraint {
+    /// The minimum and, unless it is MAX, the maximum size.
+    pub size: Option<(u64, Option<u64>)>,
+    /// Whether the size constraint is extensible, as in `SIZE(1..8, ...)`.
+    pub extensible: bool,
+    /// Ranges of the permitted characters, both ends included.
+    pub alphabet: Option<Vec<(char, char)>>,
+}
+
+/// Rec.ITU-T X.680 41
+/// The restricted character string types which are supported.
+const CHARACTER_STRING_TYPES: [&str; 5] = [
+    "UTF8String",
+    "IA5String",
+    "PrintableString",
+    "VisibleString",
+    "NumericString",
+];
+
+/// A fully resolved value. References to other value assignments
+/// are replaced by the value they refer to while parsing.
+#[derive(Debug, PartialEq)]
+pub enum AsnValue {
+    Integer(i128),
+    /// The arcs of the object identifier, starting from the root.
+    ObjectIdentifier(Vec<u64>),
+    Boolean(bool),
+    String(String),
+    OctetString(Vec<u8>),
+    /// The name of an item of an ENUMERATED type.
+    Enumerated(String),
+}
+
+/// Cursor over the tokens of a module.
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Tokens<'a>,
+    index: usize,
+    /// The integer values assigned in the module, as far as they are known.
+    /// Constraints may refer to values assigned anywhere in the module, so
+    /// these are collected from the value assignments of each pass over it,
+    /// for the next pass.
+    integer_values: HashMap<&'a str, i128>,
+    /// The errors for the references to integer values which aren't known
+    /// in this pass, which stand for 0 until they are.
+    unresolved: Vec<Error>,
+    /// The names of the components with a DEFAULT value parsed since these
+    /// were last taken, along with the index of the value's first token.
+    /// The values are parsed once the whole module is, since they may refer
+    /// to types and values defined later on.
+    default_values: Vec<(&'a str, usize)>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str, integer_values: HashMap<&'a str, i128>) -> Result<Self> {
+        let tokens = tokenizer(source)?;
+
+        Ok(Self {
+            source,
+            tokens,
+            index: 0,
+            integer_values,
+            unresolved: vec![],
+            default_values: vec![],
+        })
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.tokens.get(self.index).copied()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<&'a str> {
+        self.tokens.tokens.get(self.index + n).copied()
+    }
+
+    fn next(&mut self) -> Result<&'a str> {
+        let token = self
+            .peek()
+            .ok_or_else(|| self.error("unexpected end of ASN.1 input"))?;
+        self.index += 1;
+
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        let token = self.next()?;
+        if token != expected {
+            return Err(self.error(format!("expected `{}`, found `{}`", expected, token)));
+        }
+
+        Ok(())
+    }
+
+    /// An error at the last token which was parsed.
+    fn error(&self, message: impl Into<String>) -> Error {
+        let token = self.tokens.tokens[..self.index.min(self.tokens.tokens.len())]
+            .last()
+            .copied();
+        let line = match token {
+            Some(token) => line(self.source, token),
+            None => 1,
+        };
+
+        Error {
+            line,
+            message: message.into(),
+        }
+    }
+
+    /// Collects the documentation of the definition spanning the tokens from
+    /// `start` up to the current token. Comments following the tokens before
+    /// `comments_end` on the same line are included, along with the comments
+    /// preceding the definition.
+    fn docs(&self, start: usize, comments_end: usize) -> AsnDocs<'a> {
+        let mut comments = self
+            .tokens
+            .leading_comments
+            .get(&start)
+            .cloned()
+            .unwrap_or_default();
+        for index in start..comments_end {
+            if let Some(trailing_comments) = self.tokens.trailing_comments.get(&index) {
+                comments.extend(trailing_comments);
+            }
+        }
+
+        let offset = |token: &str| token.as_ptr() as usize - self.source.as_ptr() as usize;
+        let last_token = self.tokens.tokens[self.index - 1];
+        let definition =
+            &self.source[offset(self.tokens.tokens[start])..offset(last_token) + last_token.len()];
+
+        AsnDocs {
+            comments,
+            definition,
+        }
+    }
+
+    /// Parses a SEQUENCE or SET body, starting at the opening brace.
+    fn parse_sequence(&mut self, set: bool) -> Result<AsnSequence<'a>> {
+        let (fields, extensible) = self.parse_fields()?;
+        Ok(AsnSequence {
+            fields,
+            set,
+            extensible,
+        })
+    }
+
+    /// Parses a CHOICE body, starting at the opening brace.
+    fn parse_choice(&mut self) -> Result<AsnChoice<'a>> {
+        let (alternatives, extensible) = self.parse_fields()?;
+        if let Some(alternative) = alternatives
+            .iter()
+            .find(|alternative| alternative.presence != AsnPresence::Required)
+        {
+            return Err(Error {
+                line: line(self.source, alternative.docs.definition),
+                message: format!(
+                    "CHOICE alternative `{}` can't be OPTIONAL or have a DEFAULT value",
+                    alternative.name
+                ),
+            });
+        }
+
+        Ok(AsnChoice {
+            alternatives,
+            extensible,
+        })
+    }
+
+    /// Parses a brace enclosed list of named types, such
+    /// as the fields of a SEQUENCE or alternatives of a CHOICE, and whether
+    /// it has an extension marker. The fields following the marker are
+    /// extension additions.
+    fn parse_fields(&mut self) -> Result<(Vec<AsnField<'a>>, bool)> {
+        self.expect("{")?;
+
+        let mut fields = vec![];
+        let mut extensible = false;
+        loop {
+            // Rec.ITU-T X.680 25.1, only a single extension marker, with
+            // the extension additions at the end, is supported
+            if self.peek() == Some("...") {
+                self.expect("...")?;
+                if extensible {
+                    return Err(self.error("a second extension marker is not supported"));
+                }
+                extensible = true;
+                match self.next()? {
+                    "," => continue,
+                    "}" => break,
+                    other => {
+                        return Err(self.error(format!(
+                            "expected `,` or `}}` after the extension marker, found `{}`",
+                            other
+                        )))
+                    }
+                }
+            }
+
+            let start = self.index;
+            let name = self.next()?;
+            let tag = self.parse_tag()?;
+            let (field_type, named_numbers) = if self.peek() == Some("INTEGER") {
+                self.expect("INTEGER")?;
+                self.parse_integer_type()?
+            } else {
+                (self.parse_type()?, vec![])
+            };
+            let presence = match self.peek() {
+                Some("OPTIONAL") => {
+                    self.expect("OPTIONAL")?;
+                    AsnPresence::Optional
+                }
+                Some("DEFAULT") => {
+                    self.expect("DEFAULT")?;
+                    self.default_values.push((name, self.index));
+                    self.skip_value()?;
+                    AsnPresence::Default
+                }
+                _ => AsnPresence::Required,
+            };
+            // a comment following the comma still documents this field
+            let comments_end = if self.peek() == Some(",") {
+                self.index + 1
+            } else {
+                self.index
+            };
+            let docs = self.docs(start, comments_end);
+            fields.push(AsnField {
+                name,
+                field_type,
+                named_numbers,
+                tag,
+                presence,
+                extension: extensible,
+                docs,
+            });
+
+            match self.next()? {
+                "," => continue,
+                "}" => break,
+                other => {
+                    return Err(self.error(format!(
+                        "expected `,` or `}}` after field `{}`, found `{}`",
+                        name, other
+                    )))
+                }
+            }
+        }
+
+        Ok((fields, extensible))
+    }
+
+    /// Rec.ITU-T X.680 31.1
+    /// Parses the tag preceding a type, such as `[APPLICATION 3] IMPLICIT`,
+    /// if there is one.
+    fn parse_tag(&mut self) -> Result<Option<AsnTag>> {
+        if self.peek() != Some("[") {
+            return Ok(None);
+        }
+        self.expect("[")?;
+
+        let class = match self.peek() {
+            Some("UNIVERSAL") => TagClass::Universal,
+            Some("APPLICATION") => TagClass::Application,
+            Some("PRIVATE") => TagClass::Private,
+            _ => TagClass::ContextSpecific,
+        };
+        if class != TagClass::ContextSpecific {
+            self.next()?;
+        }
+        let number = self.parse_integer_value()?;
+        let number = u32::try_from(number)
+            .map_err(|_| self.error(format!("{} is not a valid tag", number)))?;
+        self.expect("]")?;
+
+        let mode = match self.peek() {
+            Some("IMPLICIT") => Some(TagMode::Implicit),
+            Some("EXPLICIT") => Some(TagMode::Explicit),
+            _ => None,
+        };
+        if mode.is_some() {
+            self.next()?;
+        }
+
+        Ok(Some(AsnTag {
+            class,
+            number,
+            mode,
+        }))
+    }
+
+    /// Skips a value, which is either a single token or enclosed in braces.
+    fn skip_value(&mut self) -> Result<()> {
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                "{" => depth += 1,
+                "}" => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_type(&mut self) -> Result<AsnType<'a>> {
+        Ok(match self.next()? {
+            "INTEGER" => {
+                let (integer_type, _named_numbers) = self.parse_integer_type()?;
+                integer_type
+            }
+            "OBJECT" => {
+                self.expect("IDENTIFIER")?;
+                AsnType::ObjectIdentifier
+            }
+            keyword @ "SEQUENCE" | keyword @ "SET" => {
+                // the size may be given with or without parentheses
+                let constraint = if self.peek() == Some("SIZE") {
+                    self.expect("SIZE")?;
+                    let (size, extensible) = self.parse_size()?;
+                    Some(AsnConstraint {
+                        size: Some(size),
+                        alphabet: None,
+                        extensible,
+                    })
+                } else if self.at_constraint() {
+                    Some(self.parse_constraint()?)
+                } else {
+                    None
+                };
+                self.expect("OF")?;
+
+                let element = Box::new(self.parse_type()?);
+                let list = if keyword == "SET" {
+                    AsnType::SetOf(element)
+                } else {
+                    AsnType::SequenceOf(element)
+                };
+                match constraint {
+                    Some(constraint) => AsnType::Constrained(Box::new(list), constraint),
+                    None => list,
+                }
+            }
+            "BOOLEAN" => AsnType::Boolean,
+            "OCTET" => {
+                self.expect("STRING")?;
+                self.constrained(AsnType::OctetString)?
+            }
+            "ENUMERATED" => {
+                return Err(self.error("ENUMERATED is only supported in type assignments"))
+            }
+            other if CHARACTER_STRING_TYPES.contains(&other) => {
+                self.constrained(AsnType::CharacterString(other))?
+            }
+            other if self.at_constraint() => self.constrained(AsnType::Custom(other))?,
+            other if self.peek() == Some("(") => {
+                let (min, max, extensible) = self.parse_bounds()?;
+                AsnType::ConstrainedReference {
+                    name: other,
+                    min,
+                    max,
+                    extensible,
+                }
+            }
+            other => AsnType::Custom(other),
+        })
+    }
+
+    /// Whether the next tokens are a SIZE or permitted alphabet constraint.
+    fn at_constraint(&self) -> bool {
+        self.peek() == Some("(") && matches!(self.peek_nth(1), Some("SIZE") | Some("FROM"))
+    }
+
+    /// Adds the constraint following a type to it, if there is one.
+    fn constrained(&mut self, asn_type: AsnType<'a>) -> Result<AsnType<'a>> {
+        Ok(if self.at_constraint() {
+            AsnType::Constrained(Box::new(asn_type), self.parse_constraint()?)
+        } else {
+            asn_type
+        })
+    }
+
+    /// Expected input: "(SIZE(1..8) ^ FROM("A".."Z"))", where either part is
+    /// optional. The parts may also be given in separate parentheses, as in
+    /// "(SIZE(1..8))(FROM("A".."Z"))".
+    fn parse_constraint(&mut self) -> Result<AsnConstraint> {
+        let mut constraint = AsnConstraint::default();
+        while self.at_constraint() {
+            self.expect("(")?;
+            loop {
+                match self.next()? {
+                    "SIZE" => {
+                        let (size, extensible) = self.parse_size()?;
+                        constraint.size = Some(size);
+                        constraint.extensible = extensible;
+                    }
+                    "FROM" => constraint.alphabet = Some(self.parse_alphabet()?),
+                    other => return Err(self.error(format!("unsupported constraint `{}`", other))),
+                }
+
+                match self.next()? {
+                    "^" | "INTERSECTION" => continue,
+                    ")" => break,
+                    // an extension marker after the constraint, as in
+                    // "(SIZE(1..8), ...)"
+                    "," => {
+                        self.expect("...")?;
+                        constraint.extensible = true;
+                        self.expect(")")?;
+                        break;
+                    }
+                    other => {
+                        return Err(self.error(format!(
+                            "expected `^`, `,` or `)` in constraint, found `{}`",
+                            other
+                        )))
+                    }
+                }
+            }
+        }
+
+        Ok(constraint)
+    }
+
+    /// Expected input: "(1..8)", "(1..MAX)", "(8)" or "(1..8, ...)"
+    /// Output: ((min, max), extensible), where max is None for MAX
+    fn parse_size(&mut self) -> Result<((u64, Option<u64>), bool)> {
+        self.expect("(")?;
+        let min = self.parse_size_value()?;
+        let max = if self.peek() == Some("..") {
+            self.expect("..")?;
+            if self.peek() == Some("MAX") {
+                self.expect("MAX")?;
+                None
+            } else {
+                Some(self.parse_size_value()?)
+            }
+        } else {
+            Some(min)
+        };
+        let extensible = self.parse_extension_marker()?;
+        self.expect(")")?;
+
+        Ok(((min, max), extensible))
+    }
+
+    /// Parses the extension marker at the end of a constraint, such as the
+    /// `, ...` in `(0..7, ...)`, if there is one.
+    fn parse_extension_marker(&mut self) -> Result<bool> {
+        if self.peek() != Some(",") {
+            return Ok(false);
+        }
+        self.expect(",")?;
+        self.expect("...")?;
+
+        Ok(true)
+    }
+
+    fn parse_size_value(&mut self) -> Result<u64> {
+        let value = self.parse_integer_value()?;
+        u64::try_from(value).map_err(|_| self.error(format!("{} is not a valid size", value)))
+    }
+
+    /// Expected input: "("A".."Z" | "0".."9" | "-_")"
+    /// Output: the ranges of permitted characters
+    fn parse_alphabet(&mut self) -> Result<Vec<(char, char)>> {
+        self.expect("(")?;
+
+        let mut alphabet = vec![];
+        loop {
+            let from = self.parse_string_value()?;
+            if self.peek() == Some("..") {
+                self.expect("..")?;
+                let to = self.parse_string_value()?;
+                let single_character = |s: &str| {
+                    let mut characters = s.chars();
+                    match (characters.next(), characters.next()) {
+                        (Some(c), None) => Ok(c),
+                        _ => Err(self.error(format!("`{}` is not a single character", s))),
+                    }
+                };
+                alphabet.push((single_character(&from)?, single_character(&to)?));
+            } else {
+                alphabet.extend(from.chars().map(|c| (c, c)));
+            }
+
+            match self.next()? {
+                "|" | "UNION" => continue,
+                ")" => break,
+                other => {
+                    return Err(self.error(format!(
+                        "expected `|` or `)` in alphabet, found `{}`",
+                        other
+                    )))
+                }
+            }
+        }
+
+        Ok(alphabet)
+    }
+
+    /// Parses a character string literal, such as `"abc"`.
+    fn parse_string_value(&mut self) -> Result<String> {
+        let token = self.next()?;
+        match token
+            .strip_prefix('"')
+            .and_then(|token| token.strip_suffix('"'))
+        {
+            // Rec.ITU-T X.680 12.14
+            // a quotation mark is written twice within a string
+            Some(string) => Ok(string.replace("\"\"", "\"")),
+            None => Err(self.error(format!("`{}` is not a string", token))),
+        }
+    }
+
+    /// Parses the remainder of an INTEGER type, after the INTEGER keyword.
+    fn parse_integer_type(&mut self) -> Result<(AsnType<'a>, Vec<AsnNamedNumber<'a>>)> {
+        let named_numbers = if self.peek() == Some("{") {
+            self.parse_named_numbers()?
+        } else {
+            vec![]
+        };
+
+        let integer_type = if self.peek() == Some("(") {
+            let (min, max, extensible) = self.parse_bounds()?;
+            AsnType::BoundedInteger {
+                min,
+                max,
+                extensible,
+            }
+        } else {
+            AsnType::Integer
+        };
+
+        Ok((integer_type, named_numbers))
+    }
+
+    /// Expected input: "{ ok(0), busy(1) }"
+    fn parse_named_numbers(&mut self) -> Result<Vec<AsnNamedNumber<'a>>> {
+        self.expect("{")?;
+
+        let mut named_numbers = vec![];
+        loop {
+            let name = self.next()?;
+            self.expect("(")?;
+            let value = self.parse_integer_value()?;
+            self.expect(")")?;
+            named_numbers.push(AsnNamedNumber { name, value });
+
+            match self.next()? {
+                "," => continue,
+                "}" => break,
+                other => {
+                    return Err(self.error(format!(
+                        "expected `,` or `}}` after named number `{}`, found `{}`",
+                        name, other
+                    )))
+                }
+            }
+        }
+
+        Ok(named_numbers)
+    }
+
+    /// Rec.ITU-T X.680 20
+    /// Parses the items of an ENUMERATED type, after the ENUMERATED keyword.
+    /// Root items without a number take the smallest number which no other
+    /// root item has, in order. Extension additions have to be numbered in
+    /// ascending order, and those without a number take the smallest one
+    /// which follows the previous addition and which no root item has.
+    fn parse_enumerated(&mut self) -> Result<AsnEnumerated<'a>> {
+        self.expect("{")?;
+        let unresolved = self.unresolved.len();
+
+        let mut items: Vec<(&'a str, Option<i128>, bool)> = vec![];
+        let mut extensible = false;
+        loop {
+            let name = self.next()?;
+            if name == "..." {
+                if extensible {
+                    return Err(self.error("a second extension marker is not supported"));
+                }
+                extensible = true;
+            } else {
+                if items.iter().any(|(item, _, _)| *item == name) {
+                    return Err(self.error(format!("`{}` is enumerated more than once", name)));
+                }
+                let value = if self.peek() == Some("(") {
+                    self.expect("(")?;
+                    let value = self.parse_integer_value()?;
+                    self.expect(")")?;
+                    Some(value)
+                } else {
+                    None
+                };
+                items.push((name, value, extensible));
+            }
+
+            match self.next()? {
+                "," => continue,
+                "}" => break,
+                other => {
+                    return Err(self.error(format!(
+                        "expected `,` or `}}` in ENUMERATED, found `{}`",
+                        other
+                    )))
+                }
+            }
+        }
+
+        let mut root: Vec<i128> = items
+            .iter()
+            .filter(|(_, _, extension)| !extension)
+            .filter_map(|(_, value, _)| *value)
+            .collect();
+        // numbers which refer to values that aren't known yet are checked in
+        // a later pass
+        let checked = self.unresolved.len() == unresolved;
+        let mut numbered = vec![];
+        let mut previous_addition: Option<i128> = None;
+        for (name, value, extension) in items {
+            let value = match (value, extension) {
+                (Some(value), false) => value,
+                (None, false) => {
+                    let value = (0..).find(|value| !root.contains(value)).unwrap();
+                    root.push(value);
+                    value
+                }
+                (Some(value), true)
+                    if checked && previous_addition.is_some_and(|previous| value <= previous) =>
+                {
+                    return Err(self.error(format!(
+                        "`{}` has to be numbered above the previous extension additions",
+                        name
+                    )))
+                }
+                (Some(value), true) => value,
+                (None, true) => (previous_addition.map_or(0, |previous| previous + 1)..)
+                    .find(|value| !root.contains(value))
+                    .unwrap(),
+            };
+            if extension {
+                previous_addition = Some(value);
+            }
+            if checked
+                && numbered
+                    .iter()
+                    .any(|item: &AsnEnumerationItem| item.value == value)
+            {
+                return Err(self.error(format!("{} is enumerated more than once", value)));
+            }
+            numbered.push(AsnEnumerationItem {
+                name,
+                value,
+                extension,
+            });
+        }
+
+        Ok(AsnEnumerated {
+            items: numbered,
+            extensible,
+        })
+    }
+
+    /// Expected input: "(0..255)", "(0..MAX)", "(MIN..0)" or "(0..255, ...)"
+    /// Output: (min, max, extensible), where min is None for MIN and max is
+    /// None for MAX
+    fn parse_bounds(&mut self) -> Result<(Option<i128>, Option<i128>, bool)> {
+        self.expect("(")?;
+        let min = self.parse_bound("MIN")?;
+        self.expect("..")?;
+        let max = self.parse_bound("MAX")?;
+        let extensible = self.parse_extension_marker()?;
+        self.expect(")")?;
+
+        Ok((min, max, extensible))
+    }
+
+    /// Parses an end of a range, which is None if it is the given keyword,
+    /// MIN or MAX.
+    fn parse_bound(&mut self, keyword: &str) -> Result<Option<i128>> {
+        if self.peek() == Some(keyword) {
+            self.expect(keyword)?;
+            Ok(None)
+        } else {
+            self.parse_integer_value().map(Some)
+        }
+    }
+
+    /// Parses an integer literal or a reference to an integer value assignment.
+    fn parse_integer_value(&mut self) -> Result<i128> {
+        let token = self.next()?;
+        let error = self.error(format!("`{}` is not an integer value", token));
+        Ok(self.resolve_integer(token, error))
+    }
+
+    /// Resolves an integer literal or a reference to an integer value. A
+    /// reference to a value which isn't known in this pass stands for 0,
+    /// and `error` is reported if no later pass resolves it.
+    fn resolve_integer(&mut self, token: &str, error: Error) -> i128 {
+        integer_value(token, &self.integer_values).unwrap_or_else(|| {
+            self.unresolved.push(error);
+            0
+        })
+    }
+
+    /// Parses an object identifier value such as
+    /// `{ iso(1) member-body(2) 840 }` or `{ id-ce 14 }`, resolving
+    /// references to previously assigned object identifiers.
+    fn parse_object_identifier_value(
+        &mut self,
+        values: &HashMap<&'a str, AsnValueAssignment<'a>>,
+    ) -> Result<Vec<u64>> {
+        self.expect("{")?;
+
+        let mut arcs = vec![];
+        loop {
+            let token = self.next()?;
+            if token == "}" {
+                break;
+            }
+
+            if let Ok(arc) = token.parse() {
+                arcs.push(arc);
+            } else if self.peek() == Some("(") {
+                // name form with number, i.e. `iso(1)`
+                self.expect("(")?;
+                let arc = self.next()?;
+                arcs.push(
+                    arc.parse()
+                        .map_err(|_| self.error(format!("`{}` is not a valid arc", arc)))?,
+                );
+                self.expect(")")?;
+            } else if let Some(AsnValueAssignment {
+                value: AsnValue::ObjectIdentifier(prefix),
+                ..
+            }) = values.get(token)
+            {
+                arcs.extend(prefix);
+            } else {
+                // Rec.ITU-T X.660 A.2
+                // The root arcs may be referred to by name only.
+                arcs.push(match token {
+                    "itu-t" => 0,
+                    "iso" => 1,
+                    "joint-iso-itu-t" => 2,
+                    _ => {
+                        return Err(
+                            self.error(format!("`{}` is not a known object identifier", token))
+                        )
+                    }
+                });
+            }
+        }
+
+        Ok(arcs)
+    }
+
+    /// Rec.ITU-T X.680 12.10, 12.12 and 22.11
+    /// Parses an OCTET STRING value written in binary, as in `'00001111'B`,
+    /// or in hexadecimal, as in `'0F'H`. Values which don't fill their last
+    /// octet are padded with zero bits.
+    fn parse_octet_string_value(&mut self) -> Result<Vec<u8>> {
+        let token = self.next()?;
+        let not_octets = || self.error(format!("`{}` is not an OCTET STRING value", token));
+        let digits = token.strip_prefix('\'').ok_or_else(not_octets)?;
+        let (digits, bits_per_digit, radix) =
+            match (digits.strip_suffix("'H"), digits.strip_suffix("'B")) {
+                (Some(digits), _) => (digits, 4, 16),
+                (_, Some(digits)) => (digits, 1, 2),
+                _ => return Err(not_octets()),
+            };
+
+        let mut octets = vec![];
+        for (index, digit) in digits.chars().enumerate() {
+            let digit = digit.to_digit(radix).ok_or_else(not_octets)? as u8;
+            let bit = index * bits_per_digit;
+            if bit % 8 == 0 {
+                octets.push(0);
+            }
+            let shift = 8 - bits_per_digit - bit % 8;
+            *octets.last_mut().unwrap() |= digit << shift;
+        }
+
+        Ok(octets)
+    }
+
+    fn parse_value(
+        &mut self,
+        value_type: &AsnType<'a>,
+        values: &HashMap<&'a str, AsnValueAssignment<'a>>,
+        named_numbers: &HashMap<&'a str, Vec<AsnNamedNumber<'a>>>,
+        enumerations: &HashMap<&'a str, AsnEnumerated<'a>>,
+        type_aliases: &HashMap<&'a str, AsnType<'a>>,
+        assignments: &[&'a str],
+    ) -> Result<AsnValue> {
+        // values of type aliases are written as values of the aliased type,
+        // unless it has named numbers, which the value may refer to
+        let mut value_type = value_type;
+        let mut seen = vec![];
+        while let AsnType::Custom(type_name) = value_type {
+            if named_numbers.contains_key(type_name) {
+                break;
+            }
+            match type_aliases.get(type_name) {
+                Some(_) if seen.contains(type_name) => {
+                    return Err(self.error(format!("type alias `{}` refers to itself", type_name)))
+                }
+                Some(alias) => {
+                    seen.push(*type_name);
+                    value_type = alias;
+                }
+                None => break,
+            }
+        }
+
+        Ok(match value_type {
+            AsnType::ObjectIdentifier => {
+                AsnValue::ObjectIdentifier(self.parse_object_identifier_value(values)?)
+            }
+            // values of types with named numbers may be given by one of those names
+            AsnType::Custom(type_name) if named_numbers.contains_key(type_name) => {
+                let token = self.next()?;
+                let named_number = named_numbers[type_name]
+                    .iter()
+                    .find(|named_number| named_number.name == token);
+
+                AsnValue::Integer(match named_number {
+                    Some(named_number) => named_number.value,
+                    None => {
+                        let error =
+                            self.error(format!("`{}` is not a value of {}", token, type_name));
+                        self.resolve_integer(token, error)
+                    }
+                })
+            }
+            AsnType::Custom(type_name) if enumerations.contains_key(type_name) => {
+                let token = self.next()?;
+                if !enumerations[type_name]
+                    .items
+                    .iter()
+                    .any(|item| item.name == token)
+                {
+                    return Err(self.error(format!("`{}` is not a value of {}", token, type_name)));
+                }
+                AsnValue::Enumerated(token.to_string())
+            }
+            AsnType::Integer
+            | AsnType::BoundedInteger { .. }
+            | AsnType::ConstrainedReference { .. } => {
+                AsnValue::Integer(self.parse_integer_value()?)
+            }
+            AsnType::Boolean => match self.next()? {
+                "TRUE" => AsnValue::Boolean(true),
+                "FALSE" => AsnValue::Boolean(false),
+                other => return Err(self.error(format!("`{}` is not a BOOLEAN value", other))),
+            },
+            AsnType::CharacterString(_) => AsnValue::String(self.parse_string_value()?),
+            AsnType::OctetString => AsnValue::OctetString(self.parse_octet_string_value()?),
+            AsnType::Constrained(asn_type, _) => self.parse_value(
+                asn_type,
+                values,
+                named_numbers,
+                enumerations,
+                type_aliases,
+                assignments,
+            )?,
+            // the remaining types are SEQUENCE, SET and CHOICE types
+            AsnType::Custom(type_name) if assignments.contains(type_name) => {
+                return Err(self.error(format!("values of `{}` are not supported", type_name)))
+            }
+            AsnType::Custom(type_name) => {
+                return Err(self.error(format!("type `{}` is not defined", type_name)))
+            }
+            AsnType::SequenceOf(_) => {
+                return Err(self.error("SEQUENCE OF values are not supported"))
+            }
+            AsnType::SetOf(_) => return Err(self.error("SET OF values are not supported")),
+        })
+    }
+}
+
+/// Parses an integer literal, or resolves it as a reference to an integer
+/// value.
+fn integer_value(token: &str, integer_values: &HashMap<&str, i128>) -> Option<i128> {
+    token
+        .parse()
+        .ok()
+        .or_else(|| integer_values.get(token).copied())
+}
+
+/// The tokens of a module, along with the comments between them.
+#[derive(Debug, Default, PartialEq)]
+struct Tokens<'a> {
+    tokens: Vec<&'a str>,
+    /// Comments on lines of their own, keyed by the index of the token
+    /// following them.
+    leading_comments: HashMap<usize, Vec<&'a str>>,
+    /// Comments following a token on the same line, keyed by the index of
+    /// that token.
+    trailing_comments: HashMap<usize, Vec<&'a str>>,
+}
+
+fn tokenizer(s: &str) -> Result<Tokens<'_>> {
+    let mut out = Tokens::default();
+    let mut pending_comments = vec![];
+    let mut line_has_token = false;
+
+    let mut index = 0;
+    while index < s.len() {
+        let rest = &s[index..];
+        let character = rest.chars().next().unwrap();
+
+        if character == '\n' {
+            line_has_token = false;
+            index += 1;
+        } else if character.is_whitespace() {
+            index += character.len_utf8();
+        } else if rest.starts_with("--") || rest.starts_with("/*") {
+            let comment = if rest.starts_with("--") {
+                line_comment(rest)
+            } else {
+                block_comment(rest)
+            };
+            let lines: Vec<&str> = comment
+                .lines()
+                .map(|line| {
+                    line.trim()
+                        .trim_start_matches("--")
+                        .trim_start_matches("/*")
+                        .trim_end_matches("--")
+                        .trim_end_matches("*/")
+                        .trim_start_matches('*')
+                        .trim()
+                })
+                .filter(|line| !line.is_empty())
+                .collect();
+
+            if lines.is_empty() {
+                // nothing to keep, such as a line of dashes
+            } else if line_has_token {
+                out.trailing_comments
+                    .entry(out.tokens.len() - 1)
+                    .or_default()
+                    .extend(lines);
+            } else {
+                pending_comments.extend(lines);
+            }
+            if comment.contains('\n') {
+                line_has_token = false;
+            }
+            index += comment.len();
+        } else if character == '"' {
+            // strings may contain whitespace, and end at the next quotation
+            // mark which isn't doubled
+            let mut len = 1;
+            loop {
+                match rest[len..].find('"') {
+                    Some(quote) if rest[len + quote + 1..].starts_with('"') => len += quote + 2,
+                    Some(quote) => {
+                        len += quote + 1;
+                        break;
+                    }
+                    None => {
+                        return Err(Error {
+                            line: line(s, rest),
+                            message: "unterminated string in ASN.1 input".into(),
+                        })
+                    }
+                }
+            }
+
+            if !pending_comments.is_empty() {
+                out.leading_comments
+                    .insert(out.tokens.len(), std::mem::take(&mut pending_comments));
+            }
+            out.tokens.push(&rest[..len]);
+            line_has_token = true;
+            index += len;
+        } else {
+            let word_len = [
+                rest.find(char::is_whitespace),
+                rest.find("--"),
+                rest.find("/*"),
+            ]
+            .iter()
+            .flatten()
+            .min()
+            .copied()
+            .unwrap_or(rest.len());
+
+            for token in split_keep_separator(&rest[..word_len]) {
+                if !pending_comments.is_empty() {
+                    out.leading_comments
+                        .insert(out.tokens.len(), std::mem::take(&mut pending_comments));
+                }
+                out.tokens.push(token);
+            }
+            line_has_token = true;
+            index += word_len;
+        }
+    }
+
+    Ok(out)
+}
+
+/// Rec.ITU-T X.680 12.6.3
+/// Returns the comment at the start of the input, including its
+/// delimiters. A comment starting with `--` ends at the next `--` or at the
+/// end of the line.
+fn line_comment(s: &str) -> &str {
+    let body = &s[2..];
+    match (body.find("--"), body.find('\n')) {
+        (Some(end), Some(newline)) if newline < end => &s[..2 + newline],
+        (Some(end), _) => &s[..2 + end + 2],
+        (None, Some(newline)) => &s[..2 + newline],
+        (None, None) => s,
+    }
+}
+
+/// Rec.ITU-T X.680 12.6.4
+/// Returns the comment at the start of the input, including its delimiters.
+/// Comments starting with `/*` may be nested, and end with the matching `*/`.
+fn block_comment(s: &str) -> &str {
+    let mut depth = 0;
+    let mut index = 0;
+    while index < s.len() {
+        if s[index..].starts_with("/*") {
+            depth += 1;
+            index += 2;
+        } else if s[index..].starts_with("*/") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                return &s[..index];
+            }
+        } else {
+            index += s[index..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    s
+}
+
+/// Separators which are tokens in their own right, even when they are
+/// not surrounded by whitespace. Longer separators must come before
+/// any separator which is a prefix of them.
+const SEPARATORS: [&str; 11] = ["...", "..", ",", "{", "}", "(", ")", "[", "]", "|", "^"];
+
+/// Like the std lib split function, but allows us to keep the
+/// separators (commas, braces, parentheses and range dots).
+fn split_keep_separator(s: &str) -> Vec<&str> {
+    let mut out = vec![];
+
+    let mut slice_start = 0;
+    let mut index = 0;
+
+    while index < s.len() {
+        let separator = SEPARATORS
+            .iter()
+            .find(|separator| s[index..].starts_with(*separator));
+
+        if let Some(separator) = separator {
+            if index > slice_start {
+                // push characters before separator, if there are any
+                out.push(&s[slice_start..index]);
+            }
+            // push separator
+            let index_behind_separator = index + separator.len();
+            out.push(&s[index..index_behind_separator]);
+            slice_start = index_behind_separator;
+            index = index_behind_separator;
+        } else {
+            index += s[index..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    // push any characters after the last separator
+    if slice_start < s.len() {
+        out.push(&s[slice_start..s.len()]);
+    }
+
+    out
+}
+
+impl<'a> AsnModule<'a> {
+    /// Parses the first module in the given string.
+    pub fn parse(s: &'a str) -> Result<Self> {
+        // constraints may refer to integer values assigned anywhere in the
+        // module, so it is parsed again with the values the previous pass
+        // collected, until every reference is resolved or no more are
+        let mut integer_values = HashMap::new();
+        loop {
+            let mut parser = Parser::new(s, integer_values)?;
+            let known = parser.integer_values.len();
+            let module = Self::parse_module(&mut parser);
+            if parser.unresolved.is_empty() {
+                return module;
+            }
+            if parser.integer_values.len() == known {
+                return Err(parser.unresolved.remove(0));
+            }
+            integer_values = parser.integer_values;
+        }
+    }
+
+    /// A pass over the module.
+    fn parse_module(parser: &mut Parser<'a>) -> Result<Self> {
+        let s = parser.source;
+        let name = parser.next()?;
+
+        // Rec.ITU-T X.680 13.1
+        // the module header may give the tagging mode, which is EXPLICIT
+        // if it doesn't
+        let mut tag_default = TagDefault::Explicit;
+        loop {
+            match parser.next()? {
+                "BEGIN" => break,
+                "TAGS" => {}
+                token => {
+                    if parser.peek() == Some("TAGS") {
+                        tag_default = match token {
+                            "EXPLICIT" => TagDefault::Explicit,
+                            "IMPLICIT" => TagDefault::Implicit,
+                            "AUTOMATIC" => TagDefault::Automatic,
+                            other => {
+                                return Err(parser.error(format!("unknown tag default `{}`", other)))
+                            }
+                        };
+                    }
+                }
+            }
+        }
+
+        let mut sequences = HashMap::new();
+        let mut choices = HashMap::new();
+        let mut type_aliases = HashMap::new();
+        let mut named_numbers = HashMap::new();
+        let mut enumerations = HashMap::new();
+        let mut tags = HashMap::new();
+        let mut docs = HashMap::new();
+        let mut assignments = vec![];
+        let mut pending_values = vec![];
+        let mut pending_defaults = vec![];
+        while parser.peek() != Some("END") {
+            let start = parser.index;
+            let assignment_name = parser.next()?;
+            if assignments.contains(&assignment_name) {
+                return Err(
+                    parser.error(format!("`{}` is assigned more than once", assignment_name))
+                );
+            }
+            assignments.push(assignment_name);
+            // only comments up to the opening brace of a SEQUENCE, SET or CHOICE
+            // document the type, the rest belong to its components
+            let mut comments_end = None;
+
+            if parser.peek() == Some("::=") {
+                parser.expect("::=")?;
+                if let Some(tag) = parser.parse_tag()? {
+                    tags.insert(assignment_name, tag);
+                }
+
+                if matches!(parser.peek(), Some("SEQUENCE") | Some("SET"))
+                    && parser.peek_nth(1) == Some("{")
+                {
+                    let set = parser.next()? == "SET";
+                    comments_end = Some(parser.index + 1);
+                    sequences.insert(assignment_name, parser.parse_sequence(set)?);
+                    pending_defaults.extend(
+                        parser
+                            .default_values
+                            .drain(..)
+                            .map(|(field_name, index)| (assignment_name, field_name, index)),
+                    );
+                } else if parser.peek() == Some("CHOICE") {
+                    parser.expect("CHOICE")?;
+                    comments_end = Some(parser.index + 1);
+                    choices.insert(assignment_name, parser.parse_choice()?);
+                } else if parser.peek() == Some("ENUMERATED") {
+                    parser.expect("ENUMERATED")?;
+                    enumerations.insert(assignment_name, parser.parse_enumerated()?);
+                } else if parser.peek() == Some("INTEGER") {
+                    parser.expect("INTEGER")?;
+                    let (integer_type, numbers) = parser.parse_integer_type()?;
+                    if !numbers.is_empty() {
+                        named_numbers.insert(assignment_name, numbers);
+                    }
+                    type_aliases.insert(assignment_name, integer_type);
+                } else {
+                    type_aliases.insert(assignment_name, parser.parse_type()?);
+                }
+            } else {
+                let value_type = parser.parse_type()?;
+                parser.expect("::=")?;
+                // values are parsed once the whole module is, since their
+                // types may be defined later on
+                pending_values.push((assignment_name, value_type, parser.index));
+                parser.skip_value()?;
+            }
+
+            let comments_end = comments_end.unwrap_or(parser.index);
+            docs.insert(assignment_name, parser.docs(start, comments_end));
+        }
+
+        // every alias has to stand for a type, so that `resolve` can't fail
+        // in the order of the module, so the first alias of a cycle is reported
+        for type_name in &assignments {
+            let mut asn_type = match type_aliases.get(type_name) {
+                Some(alias) => alias,
+                None => continue,
+            };
+            let mut seen = vec![*type_name];
+            while let AsnType::Custom(referenced) = asn_type {
+                if seen.contains(referenced) {
+                    return Err(Error {
+                        line: line(s, docs[type_name].definition),
+                        message: format!("type alias `{}` refers to itself", type_name),
+                    });
+                }
+                seen.push(referenced);
+                match type_aliases.get(referenced) {
+                    Some(alias) => asn_type = alias,
+                    None => break,
+                }
+            }
+        }
+
+        let mut values = HashMap::new();
+        for (value_name, value_type, index) in pending_values {
+            parser.index = index;
+            let unresolved = parser.unresolved.len();
+            let value = parser.parse_value(
+                &value_type,
+                &values,
+                &named_numbers,
+                &enumerations,
+                &type_aliases,
+                &assignments,
+            )?;
+            if let AsnValue::Integer(integer) = value {
+                if parser.unresolved.len() == unresolved {
+                    parser.integer_values.insert(value_name, integer);
+                }
+            }
+            values.insert(value_name, AsnValueAssignment { value_type, value });
+        }
+
+        let mut defaults = HashMap::new();
+        for (type_name, field_name, index) in pending_defaults {
+            let sequence: &AsnSequence = &sequences[type_name];
+            let field = sequence
+                .fields
+                .iter()
+                .find(|field| field.name == field_name)
+                .unwrap();
+            parser.index = index;
+            // the value may be one of the named numbers of the component
+            let named_number = field
+                .named_numbers
+                .iter()
+                .find(|named_number| parser.peek() == Some(named_number.name));
+            let value = match named_number {
+                Some(named_number) => {
+                    parser.next()?;
+                    AsnValue::Integer(named_number.value)
+                }
+                None => parser.parse_value(
+                    &field.field_type,
+                    &values,
+                    &named_numbers,
+                    &enumerations,
+                    &type_aliases,
+                    &assignments,
+                )?,
+            };
+            defaults.insert((type_name, field_name), value);
+        }
+
+        Ok(Self {
+            name,
+            tag_default,
+            assignments,
+            sequences,
+            choices,
+            type_aliases,
+            named_numbers,
+            enumerations,
+            values,
+            defaults,
+            tags,
+            docs,
+        })
+    }
+}
+
+/// The line of a part of the source, starting from 1.
+pub(crate) fn line(source: &str, part: &str) -> usize {
+    let offset = part.as_ptr() as usize - source.as_ptr() as usize;
+    source[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AsnConstraint, AsnEnumerated, AsnEnumerationItem, AsnModule, AsnNamedNumber, AsnPresence,
+        AsnTag, AsnType, AsnValue,
+    };
+    use crate::{TagClass, TagDefault, TagMode};
+    use std::collections::HashMap;
+
+    #[test]
+    fn split_keep_separator() {
+        let input = ",";
+        assert_eq!(vec![","], super::split_keep_separator(input));
+
+        let input = "test,";
+        assert_eq!(vec!["test", ","], super::split_keep_separator(input));
+
+        let input = "test,test2";
+        assert_eq!(
+            vec!["test", ",", "test2"],
+            super::split_keep_separator(input)
+        );
+
+        let input = "(0..maxItems)";
+        assert_eq!(
+            vec!["(", "0", "..", "maxItems", ")"],
+            super::split_keep_separator(input)
+        );
+
+        let input = "{2";
+        assert_eq!(vec!["{", "2"], super::split_keep_separator(input));
+    }
+
+    #[test]
+    fn tokenizer() {
+        let input = "my fake, input";
+        assert_eq!(
+            vec!["my", "fake", ",", "input"],
+            super::tokenizer(input).unwrap().tokens
+        );
+
+        let input = "id-ce OBJECT IDENTIFIER ::= {2 5 29}";
+        assert_eq!(
+            vec![
+                "id-ce",
+                "OBJECT",
+                "IDENTIFIER",
+                "::=",
+                "{",
+                "2",
+                "5",
+                "29",
+                "}"
+            ],
+            super::tokenizer(input).unwrap().tokens
+        );
+    }
+
+    #[test]
+    fn tokenizer_comments() {
+        let input = "-- leading\nx INTEGER, -- trailing -- y /* block\n * comment */ z --";
+        let tokens = super::tokenizer(input).unwrap();
+
+        assert_eq!(vec!["x", "INTEGER", ",", "y", "z"], tokens.tokens);
+        assert_eq!(
+            vec![(0, vec!["leading"])]
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+            tokens.leading_comments
+        );
+        assert_eq!(
+            vec![(2, vec!["trailing"]), (3, vec!["block", "comment"])]
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+            tokens.trailing_comments
+        );
+    }
+
+    #[test]
+    fn asn_parse_geo() {
+        let asn1_string = include_str!("../../test-asn/geo.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        assert_eq!("Geometry", asn_module.name);
+        assert_eq!(
+            vec![
+                "Unsigned16",
+                "Point",
+                "Line",
+                "Rectangle",
+                "TinyRectangle",
+                "SmallRectangle"
+            ],
+            asn_module.assignments
+        );
+        assert_eq!(5, asn_module.sequences.len());
+
+        let point = asn_module.sequences.get("Point").unwrap();
+        assert_eq!(2, point.fields.len());
+        assert_eq!("x", point.fields[0].name);
+        assert_eq!(AsnType::Integer, point.fields[0].field_type);
+        assert_eq!("y", point.fields[1].name);
+        assert_eq!(AsnType::Integer, point.fields[1].field_type);
+
+        let line = asn_module.sequences.get("Line").unwrap();
+        assert_eq!(2, line.fields.len());
+        assert_eq!("p1", line.fields[0].name);
+        assert_eq!(AsnType::Custom("Point"), line.fields[0].field_type);
+        assert_eq!("p2", line.fields[1].name);
+        assert_eq!(AsnType::Custom("Point"), line.fields[1].field_type);
+
+        let rectangle = asn_module.sequences.get("Rectangle").unwrap();
+        assert_eq!(2, rectangle.fields.len());
+        assert_eq!("width", rectangle.fields[0].name);
+        assert_eq!(
+            AsnType::BoundedInteger {
+                min: Some(0),
+                max: Some(18_446_744_073_709_551_615),
+                extensible: false
+            },
+            rectangle.fields[0].field_type
+        );
+        assert_eq!("height", rectangle.fields[1].name);
+        assert_eq!(
+            AsnType::BoundedInteger {
+                min: Some(0),
+                max: Some(18_446_744_073_709_551_615),
+                extensible: false
+            },
+            rectangle.fields[1].field_type
+        );
+
+        let tiny_rectangle = asn_module.sequences.get("TinyRectangle").unwrap();
+        assert_eq!(2, tiny_rectangle.fields.len());
+        assert_eq!("width", tiny_rectangle.fields[0].name);
+        assert_eq!(
+            AsnType::BoundedInteger {
+                min: Some(0),
+                max: Some(255),
+                extensible: false
+            },
+            tiny_rectangle.fields[0].field_type
+        );
+        assert_eq!("height", tiny_rectangle.fields[1].name);
+        assert_eq!(
+            AsnType::BoundedInteger {
+                min: Some(0),
+                max: Some(255),
+                extensible: false
+            },
+            tiny_rectangle.fields[1].field_type
+        );
+
+        assert_eq!(1, asn_module.type_aliases.len());
+        assert_eq!(
+            &AsnType::BoundedInteger {
+                min: Some(0),
+                max: Some(65535),
+                extensible: false
+            },
+            asn_module.type_aliases.get("Unsigned16").unwrap()
+        );
+
+        assert_eq!(0, asn_module.values.len());
+    }
+
+    #[test]
+    fn asn_parse_values() {
+        let asn1_string = include_str!("../../test-asn/values.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        assert_eq!("Values", asn_module.name);
+        assert_eq!(8, asn_module.values.len());
+
+        let max_items = asn_module.values.get("maxItems").unwrap();
+        assert_eq!(AsnType::Integer, max_items.value_type);
+        assert_eq!(AsnValue::Integer(64), max_items.value);
+
+        let min_temperature = asn_module.values.get("minTemperature").unwrap();
+        assert_eq!(AsnValue::Integer(-40), min_temperature.value);
+
+        let default_count = asn_module.values.get("defaultCount").unwrap();
+        assert_eq!(AsnType::Custom("Unsigned8"), default_count.value_type);
+        assert_eq!(AsnValue::Integer(3), default_count.value);
+
+        // refers to a value which is defined later in the module
+        let heaviest = asn_module.values.get("heaviest").unwrap();
+        assert_eq!(AsnValue::Integer(1000), heaviest.value);
+
+        let id_ce = asn_module.values.get("id-ce").unwrap();
+        assert_eq!(AsnType::ObjectIdentifier, id_ce.value_type);
+        assert_eq!(AsnValue::ObjectIdentifier(vec![2, 5, 29]), id_ce.value);
+
+        assert_eq!(
+            AsnValue::ObjectIdentifier(vec![2, 5, 29, 14]),
+            asn_module
+                .values
+                .get("id-ce-subjectKeyIdentifier")
+                .unwrap()
+                .value
+        );
+        assert_eq!(
+            AsnValue::ObjectIdentifier(vec![1, 3, 6, 1, 5, 5, 7]),
+            asn_module.values.get("id-pkix").unwrap().value
+        );
+
+        let basket = asn_module.sequences.get("Basket").unwrap();
+        assert_eq!(
+            AsnType::BoundedInteger {
+                min: Some(0),
+                max: Some(64),
+                extensible: false
+            },
+            basket.fields[0].field_type
+        );
+        assert_eq!(
+            AsnType::BoundedInteger {
+                min: Some(0),
+                max: Some(1000),
+                extensible: false
+            },
+            basket.fields[1].field_type
+        );
+
+        // the type of a value may be constrained, or defined later on
+        let asn_module = AsnModule::parse(
+            "Forward DEFINITIONS ::= BEGIN
+            Ref ::= INTEGER (0..x)
+            x INTEGER (0..10) ::= 5
+            enabled Flag ::= TRUE
+            Flag ::= BOOLEAN
+            END",
+        )
+        .unwrap();
+        assert_eq!(
+            AsnType::BoundedInteger {
+                min: Some(0),
+                max: Some(5),
+                extensible: false
+            },
+            asn_module.type_aliases["Ref"]
+        );
+        assert_eq!(AsnValue::Boolean(true), asn_module.values["enabled"].value);
+    }
+
+    #[test]
+    fn asn_parse_named_numbers() {
+        let asn1_string = include_str!("../../test-asn/responses.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        assert_eq!(
+            &AsnType::BoundedInteger {
+                min: Some(0),
+                max: Some(255),
+                extensible: false
+            },
+            asn_module.type_aliases.get("Status").unwrap()
+        );
+        assert_eq!(
+            &vec![
+                AsnNamedNumber {
+                    name: "ok",
+                    value: 0
+                },
+                AsnNamedNumber {
+                    name: "busy",
+                    value: 1
+                },
+                AsnNamedNumber {
+                    name: "error",
+                    value: 2
+                },
+            ],
+            asn_module.named_numbers.get("Status").unwrap()
+        );
+
+        assert_eq!(
+            &AsnType::Integer,
+            asn_module.type_aliases.get("ErrorCode").unwrap()
+        );
+        assert_eq!(
+            &vec![
+                AsnNamedNumber {
+                    name: "noError",
+                    value: 0
+                },
+                AsnNamedNumber {
+                    name: "timeout",
+                    value: -1
+                },
+                AsnNamedNumber {
+                    name: "unknown",
+                    value: 1000
+                },
+            ],
+            asn_module.named_numbers.get("ErrorCode").unwrap()
+        );
+
+        // named numbers of sequence fields are kept with the field
+        let response = asn_module.sequences.get("Response").unwrap();
+        assert_eq!(
+            AsnType::BoundedInteger {
+                min: Some(0),
+                max: Some(10),
+                extensible: false
+            },
+            response.fields[2].field_type
+        );
+        assert_eq!(
+            vec![
+                AsnNamedNumber {
+                    name: "none",
+                    value: 0
+                },
+                AsnNamedNumber {
+                    name: "many",
+                    value: 10
+                },
+            ],
+            response.fields[2].named_numbers
+        );
+        assert!(response.fields[0].named_numbers.is_empty());
+        assert_eq!(2, asn_module.named_numbers.len());
+        assert_eq!(
+            AsnValue::Integer(10),
+            asn_module.defaults[&("Retry", "attempts")]
+        );
+
+        assert_eq!(
+            AsnValue::Integer(1),
+            asn_module.values.get("defaultStatus").unwrap().value
+        );
+    }
+
+    #[test]
+    fn asn_parse_recursive() {
+        let asn1_string = include_str!("../../test-asn/recursive.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        let node = asn_module.sequences.get("Node").unwrap();
+        assert_eq!("children", node.fields[1].name);
+        assert_eq!(
+            AsnType::SequenceOf(Box::new(AsnType::Custom("Node"))),
+            node.fields[1].field_type
+        );
+
+        let expr = asn_module.choices.get("Expr").unwrap();
+        assert_eq!(3, expr.alternatives.len());
+        assert_eq!("lit", expr.alternatives[0].name);
+        assert_eq!(AsnType::Integer, expr.alternatives[0].field_type);
+        assert_eq!("neg", expr.alternatives[1].name);
+        assert_eq!(AsnType::Custom("Expr"), expr.alternatives[1].field_type);
+        assert_eq!("sum", expr.alternatives[2].name);
+        assert_eq!(AsnType::Custom("Sum"), expr.alternatives[2].field_type);
+
+        assert_eq!(
+            &AsnType::SequenceOf(Box::new(AsnType::Custom("Expr"))),
+            asn_module.type_aliases.get("Exprs").unwrap()
+        );
+    }
+
+    #[test]
+    fn asn_parse_sets() {
+        let asn1_string = include_str!("../../test-asn/sets.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        assert!(!asn_module.sequences["Attribute"].set);
+        let entry = &asn_module.sequences["Entry"];
+        assert!(entry.set);
+        assert_eq!(
+            AsnType::SetOf(Box::new(AsnType::CharacterString("IA5String"))),
+            entry.fields[3].field_type
+        );
+        assert_eq!(
+            vec!["a relative distinguished name"],
+            asn_module.docs["Name"].comments
+        );
+        assert_eq!(
+            &AsnType::Constrained(
+                Box::new(AsnType::SetOf(Box::new(AsnType::Custom("Attribute")))),
+                AsnConstraint {
+                    size: Some((1, None)),
+                    alphabet: None,
+                    extensible: false,
+                }
+            ),
+            asn_module.type_aliases.get("Name").unwrap()
+        );
+    }
+
+    #[test]
+    fn asn_parse_extensions() {
+        let asn1_string = include_str!("../../test-asn/extensions.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        assert_eq!(
+            AsnType::BoundedInteger {
+                min: Some(0),
+                max: Some(7),
+                extensible: true,
+            },
+            asn_module.type_aliases["Level"]
+        );
+        let setup = &asn_module.sequences["Setup"];
+        assert!(setup.extensible);
+        let extensions: Vec<bool> = setup.fields.iter().map(|field| field.extension).collect();
+        assert_eq!(vec![false, false, true, true], extensions);
+        assert_eq!(
+            AsnType::Constrained(
+                Box::new(AsnType::CharacterString("PrintableString")),
+                AsnConstraint {
+                    size: Some((1, Some(16))),
+                    alphabet: None,
+                    extensible: true,
+                }
+            ),
+            setup.fields[1].field_type
+        );
+        assert_eq!("level\tLevel OPTIONAL", setup.fields[2].docs.definition);
+
+        let procedure = &asn_module.choices["Procedure"];
+        assert!(procedure.extensible);
+        assert!(procedure.alternatives[2].extension);
+
+        // the extension marker may come last
+        let asn_module = AsnModule::parse(
+            "Marker DEFINITIONS ::= BEGIN\nA ::= SEQUENCE { a INTEGER, ... }\nEND",
+        )
+        .unwrap();
+        assert!(asn_module.sequences["A"].extensible);
+        assert!(!asn_module.sequences["A"].fields[0].extension);
+
+        let error = AsnModule::parse(
+            "Broken DEFINITIONS ::= BEGIN\nA ::= SEQUENCE { a INTEGER, ..., b BOOLEAN, ... }\nEND",
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            "line 2: a second extension marker is not supported",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn asn_parse_comments() {
+        let asn1_string = include_str!("../../test-asn/documented.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        let speed = &asn_module.docs["Speed"];
+        assert_eq!(
+            vec!["The speed of a vehicle,", "in kilometers per hour."],
+            speed.comments
+        );
+        assert_eq!("Speed ::= INTEGER (0..255)", speed.definition);
+
+        let reading = &asn_module.docs["Reading"];
+        assert_eq!(
+            vec!["A reading of a vehicle's sensors.", "sent once a second"],
+            reading.comments
+        );
+        assert!(reading.definition.starts_with("Reading ::= SEQUENCE {"));
+        assert!(reading.definition.ends_with("-- degrees from north\n}"));
+
+        let fields = &asn_module.sequences["Reading"].fields;
+        assert_eq!(vec!["The measured speed"], fields[0].docs.comments);
+        assert_eq!("speed\tSpeed", fields[0].docs.definition);
+        assert_eq!(vec!["degrees from north"], fields[1].docs.comments);
+        assert_eq!("heading\tINTEGER (0..359)", fields[1].docs.definition);
+
+        let alternatives = &asn_module.choices["Update"].alternatives;
+        assert_eq!(vec!["a new reading"], alternatives[0].docs.comments);
+        assert_eq!(
+            vec!["the vehicle stopped reporting"],
+            alternatives[1].docs.comments
+        );
+
+        assert_eq!(
+            vec!["the speed limit"],
+            asn_module.docs["maxSpeed"].comments
+        );
+    }
+
+    #[test]
+    fn asn_parse_aliases() {
+        let asn1_string = include_str!("../../test-asn/aliases.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        assert_eq!(
+            AsnType::ConstrainedReference {
+                name: "Velocity",
+                min: Some(0),
+                max: Some(100),
+                extensible: false
+            },
+            asn_module.type_aliases["Speed"]
+        );
+        assert_eq!(
+            AsnType::CharacterString("UTF8String"),
+            asn_module.type_aliases["Name"]
+        );
+        assert_eq!(AsnType::Boolean, asn_module.type_aliases["Flag"]);
+        assert_eq!(
+            AsnType::SequenceOf(Box::new(AsnType::Custom("Waypoint"))),
+            asn_module.type_aliases["Waypoints"]
+        );
+
+        // chains of aliases are resolved
+        assert_eq!(
+            &asn_module.type_aliases["Speed"],
+            asn_module.resolve(&AsnType::Custom("MaxSpeed"))
+        );
+        assert_eq!(
+            &asn_module.type_aliases["Waypoints"],
+            asn_module.resolve(&AsnType::Custom("Route"))
+        );
+        assert_eq!(
+            &AsnType::Custom("Waypoint"),
+            asn_module.resolve(&AsnType::Custom("Waypoint"))
+        );
+
+        assert_eq!(
+            AsnType::ConstrainedReference {
+                name: "Velocity",
+                min: Some(0),
+                max: Some(200),
+                extensible: false
+            },
+            asn_module.sequences["Vehicle"].fields[2].field_type
+        );
+    }
+
+    #[test]
+    fn asn_parse_constraints() {
+        let asn1_string = include_str!("../../test-asn/constraints.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        assert_eq!(
+            AsnType::Constrained(
+                Box::new(AsnType::CharacterString("IA5String")),
+                AsnConstraint {
+                    size: Some((2, Some(4))),
+                    alphabet: Some(vec![('A', 'Z'), ('0', '9')]),
+                    extensible: false,
+                }
+            ),
+            asn_module.type_aliases["Code"]
+        );
+        assert_eq!(
+            AsnType::Constrained(
+                Box::new(AsnType::CharacterString("PrintableString")),
+                AsnConstraint {
+                    size: Some((1, None)),
+                    alphabet: None,
+                    extensible: false,
+                }
+            ),
+            asn_module.type_aliases["Label"]
+        );
+        assert_eq!(
+            AsnType::Constrained(
+                Box::new(AsnType::SequenceOf(Box::new(AsnType::Custom("Reading")))),
+                AsnConstraint {
+                    size: Some((1, Some(3))),
+                    alphabet: None,
+                    extensible: false,
+                }
+            ),
+            asn_module.type_aliases["Readings"]
+        );
+        assert_eq!(
+            AsnType::Constrained(
+                Box::new(AsnType::CharacterString("NumericString")),
+                AsnConstraint {
+                    size: Some((4, Some(4))),
+                    alphabet: None,
+                    extensible: false,
+                }
+            ),
+            asn_module.sequences["Report"].fields[0].field_type
+        );
+
+        // MIN and MAX leave an end of a range unbounded
+        assert_eq!(
+            AsnType::BoundedInteger {
+                min: Some(0),
+                max: None,
+                extensible: false,
+            },
+            asn_module.type_aliases["Count"]
+        );
+        assert_eq!(
+            AsnType::BoundedInteger {
+                min: None,
+                max: Some(0),
+                extensible: false,
+            },
+            asn_module.sequences["Tally"].fields[1].field_type
+        );
+    }
+
+    #[test]
+    fn asn_parse_constraint_forms() {
+        let asn_module = AsnModule::parse(
+            r#"Forms DEFINITIONS ::= BEGIN
+            Quoted ::= UTF8String (FROM("a""b" | "c".."e" UNION "-"))
+            Separate ::= VisibleString (SIZE(8))(FROM("0".."9"))
+            Lists ::= SEQUENCE (SIZE(0..MAX)) OF Quoted
+            END"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            AsnType::Constrained(
+                Box::new(AsnType::CharacterString("UTF8String")),
+                AsnConstraint {
+                    size: None,
+                    alphabet: Some(vec![
+                        ('a', 'a'),
+                        ('"', '"'),
+                        ('b', 'b'),
+                        ('c', 'e'),
+                        ('-', '-')
+                    ]),
+                    extensible: false,
+                }
+            ),
+            asn_module.type_aliases["Quoted"]
+        );
+        assert_eq!(
+            AsnType::Constrained(
+                Box::new(AsnType::CharacterString("VisibleString")),
+                AsnConstraint {
+                    size: Some((8, Some(8))),
+                    alphabet: Some(vec![('0', '9')]),
+                    extensible: false,
+                }
+            ),
+            asn_module.type_aliases["Separate"]
+        );
+        assert_eq!(
+            AsnType::Constrained(
+                Box::new(AsnType::SequenceOf(Box::new(AsnType::Custom("Quoted")))),
+                AsnConstraint {
+                    size: Some((0, None)),
+                    alphabet: None,
+                    extensible: false,
+                }
+            ),
+            asn_module.type_aliases["Lists"]
+        );
+    }
+
+    #[test]
+    fn asn_parse_defaults() {
+        let asn1_string = include_str!("../../test-asn/defaults.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        let presences: Vec<AsnPresence> = asn_module.sequences["Job"]
+            .fields
+            .iter()
+            .map(|field| field.presence)
+            .collect();
+        assert_eq!(
+            vec![
+                AsnPresence::Required,
+                AsnPresence::Required,
+                AsnPresence::Optional,
+                AsnPresence::Default,
+                AsnPresence::Optional,
+            ],
+            presences
+        );
+
+        // values may refer to named numbers and values defined later on
+        let mut expected = HashMap::new();
+        expected.insert(("Settings", "name"), AsnValue::String("default".into()));
+        expected.insert(("Settings", "port"), AsnValue::Integer(8080));
+        expected.insert(("Settings", "priority"), AsnValue::Integer(1));
+        expected.insert(("Settings", "verbose"), AsnValue::Boolean(false));
+        expected.insert(("Job", "retries"), AsnValue::Integer(3));
+        assert_eq!(expected, asn_module.defaults);
+
+        assert_eq!(AsnValue::Boolean(true), asn_module.values["enabled"].value);
+        assert_eq!(
+            AsnValue::String("hello \"world\"".into()),
+            asn_module.values["greeting"].value
+        );
+    }
+
+    #[test]
+    fn asn_parse_files() {
+        let asn1_string = include_str!("../../test-asn/files.asn");
+        let asn_module = AsnModule::parse(asn1_string).unwrap();
+
+        // device takes the smallest number after the previous addition
+        // which no root item has
+        let item = |name, value, extension| AsnEnumerationItem {
+            name,
+            value,
+            extension,
+        };
+        assert_eq!(
+            AsnEnumerated {
+                items: vec![
+                    item("file", 0, false),
+                    item("directory", 1, false),
+                    item("link", 5, false),
+                    item("device", 2, true),
+                ],
+                extensible: true,
+            },
+            asn_module.enumerations["Kind"]
+        );
+        assert_eq!(
+            AsnType::Constrained(
+                Box::new(AsnType::OctetString),
+                AsnConstraint {
+                    size: Some((4, Some(4))),
+                    alphabet: None,
+                    extensible: false,
+                }
+            ),
+            asn_module.type_aliases["Digest"]
+        );
+
+        assert_eq!(
+            AsnValue::OctetString(vec![0; 4]),
+            asn_module.values["emptyDigest"].value
+        );
+        assert_eq!(
+            AsnValue::Enumerated("directory".into()),
+            asn_module.values["defaultKind"].value
+        );
+        assert_eq!(
+            AsnValue::Enumerated("file".into()),
+            asn_module.defaults[&("File", "kind")]
+        );
+    }
+
+    #[test]
+    fn asn_parse_enumerated_errors() {
+        let parse = |types: &str| {
+            AsnModule::parse(&format!("Broken DEFINITIONS ::= BEGIN\n{}\nEND", types))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            "line 2: 1 is enumerated more than once",
+            parse("A ::= ENUMERATED { a(1), b, c(1) }")
+        );
+        assert_eq!(
+            "line 2: `b` has to be numbered above the previous extension additions",
+            parse("A ::= ENUMERATED { a, ..., c(3), b(2) }")
+        );
+        assert_eq!(
+            "line 2: ENUMERATED is only supported in type assignments",
+            parse("A ::= SEQUENCE { a ENUMERATED { b } }")
+        );
+        assert_eq!(
+            "line 3: `c` is not a value of A",
+            parse("A ::= ENUMERATED { a, b }\nc A ::= c")
+        );
+        assert_eq!(
+            "line 2: `'0G'H` is not an OCTET STRING value",
+            parse("a OCTET STRING ::= '0G'H")
+        );
+    }
+
+    #[test]
+    fn asn_parse_optional_alternative() {
+        let error = AsnModule::parse(
+            "Choices DEFINITIONS ::= BEGIN\nA ::= CHOICE {\n b BOOLEAN OPTIONAL\n}\nEND",
+        )
+        .err()
+        .unwrap();
+        assert_eq!(3, error.line);
+        assert!(error.message.contains("can't be OPTIONAL"));
+    }
+
+    #[test]
+    fn asn_parse_alias_cycle() {
+        let error = AsnModule::parse("Cycle DEFINITIONS ::= BEGIN\nA ::= B\nB ::= A\nEND")
+            .err()
+            .unwrap();
+        assert_eq!("line 2: type alias `A` refers to itself", error.to_string());
+    }
+
+    #[test]
+    fn asn_parse_errors() {
+        let error = AsnModule::parse("Broken DEFINITIONS ::= BEGIN\nA ::= B\nA ::= INTEGER\nEND")
+            .err()
+            .unwrap();
+        assert_eq!("line 3: `A` is assigned more than once", error.to_string());
+
+        let error =
+            AsnModule::parse("Broken DEFINITIONS ::= BEGIN\nA ::= SEQUENCE {\n a INTEGER\n")
+                .err()
+                .unwrap();
+        assert_eq!("line 3: unexpected end of ASN.1 input", error.to_string());
+
+        let error = AsnModule::parse("Broken DEFINITIONS ::= BEGIN\ns UTF8String ::= \"a\nEND")
+            .err()
+            .unwrap();
+        assert_eq!(
+            "line 2: unterminated string in ASN.1 input",
+            error.to_string()
+        );
+        let parse = |assignments: &str| {
+            AsnModule::parse(&format!(
+                "Broken DEFINITIONS ::= BEGIN\n{}\nEND",
+                assignments
+            ))
+            .err()
+            .unwrap()
+            .to_string()
+        };
+        assert_eq!(
+            "line 2: `max` is not an integer value",
+            parse("A ::= INTEGER (0..max)")
+        );
+        assert_eq!(
+            "line 3: values of `A` are not supported",
+            parse("A ::= SEQUENCE { b BOOLEAN }\na A ::= 5")
+        );
+        assert_eq!("line 2: type `B` is not defined", parse("b B ::= 5"));
+    }
+
+    #[test]
+    fn asn_parse_tags() {
+        let asn_module = AsnModule::parse(
+            "Tags DEFINITIONS AUTOMATIC TAGS ::= BEGIN
+            A ::= [APPLICATION 3] IMPLICIT INTEGER
+            B ::= SEQUENCE { a [0] EXPLICIT A, b [PRIVATE 1] BOOLEAN, c A }
+            END",
+        )
+        .unwrap();
+
+        assert_eq!(TagDefault::Automatic, asn_module.tag_default);
+        assert_eq!(
+            AsnTag {
+                class: TagClass::Application,
+                number: 3,
+                mode: Some(TagMode::Implicit),
+            },
+            asn_module.tags["A"]
+        );
+        let tags: Vec<Option<AsnTag>> = asn_module.sequences["B"]
+            .fields
+            .iter()
+            .map(|field| field.tag)
+            .collect();
+        assert_eq!(
+            vec![
+                Some(AsnTag {
+                    class: TagClass::ContextSpecific,
+                    number: 0,
+                    mode: Some(TagMode::Explicit),
+                }),
+                Some(AsnTag {
+                    class: TagClass::Private,
+                    number: 1,
+                    mode: None,
+                }),
+                None,
+            ],
+            tags
+        );
+    }
+}
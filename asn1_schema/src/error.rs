@@ -0,0 +1,17 @@
+use std::fmt::{self, Display};
+
+/// An ASN.1 module which couldn't be parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    /// The line of the module the error was found on, starting from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,34 @@
+//! Parses ASN.1 modules into a typed schema.
+//!
+//! `Module` is the resolved view of a module, which owns its data. It gives
+//! the types of the module along with their constraints and tags, with the
+//! tag default of the module and automatic tagging applied, for tools and
+//! codecs working with ASN.1 types at runtime.
+//!
+//! ```
+//! let module = asn1_schema::Module::parse(
+//!     "Geometry DEFINITIONS AUTOMATIC TAGS ::= BEGIN
+//!     Point ::= SEQUENCE { x INTEGER (0..65535), y INTEGER (0..65535) }
+//!     END",
+//! )
+//! .unwrap();
+//!
+//! let point = module.get_type("Point").unwrap();
+//! if let asn1_schema::TypeKind::Sequence(components) = &point.ty.kind {
+//!     assert_eq!(Some((Some(0), Some(65535))), components[0].ty.constraint.range);
+//! }
+//! ```
+//!
+//! The types of the schema are `#[non_exhaustive]`, so that later versions
+//! can describe more of ASN.1 without breaking code matching on them.
+
+/// The syntax tree the schema is built from, which borrows from the ASN.1
+/// source. It is used by `asn1_build`, and isn't a stable API: it changes
+/// along with the parser.
+#[doc(hidden)]
+pub mod ast;
+mod error;
+mod schema;
+
+pub use error::Error;
+pub use schema::*;
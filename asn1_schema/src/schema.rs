@@ -0,0 +1,983 @@
+//! The resolved view of a module, which owns its data.
+
+use crate::ast::{line, AsnModule, AsnPresence, AsnTag, AsnType, AsnValue};
+use crate::Error;
+
+/// Rec.ITU-T X.680 13
+/// A parsed ASN.1 module. Parsing fails on references to types which aren't
+/// defined in the module, so every type of a parsed module can be resolved.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Module {
+    pub name: String,
+    pub tag_default: TagDefault,
+    /// The type assignments, in the order they appear in the module.
+    pub types: Vec<TypeAssignment>,
+    /// The value assignments, in the order they appear in the module.
+    pub values: Vec<ValueAssignment>,
+}
+
+/// A type assignment, such as `Point ::= SEQUENCE { ... }`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeAssignment {
+    pub name: String,
+    pub ty: Type,
+    /// The lines of the comments documenting the type.
+    pub comments: Vec<String>,
+}
+
+/// A value assignment, such as `maxItems INTEGER ::= 64`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValueAssignment {
+    pub name: String,
+    pub ty: Type,
+    pub value: Value,
+    /// The lines of the comments documenting the value.
+    pub comments: Vec<String>,
+}
+
+/// A type, along with its tag and constraints.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Type {
+    pub kind: TypeKind,
+    /// The tag written before the type, if any. The tag of the type itself
+    /// is given by `Module::tag_of`.
+    pub tag: Option<Tag>,
+    pub constraint: Constraint,
+    /// Rec.ITU-T X.680 25.1
+    /// Whether the components of a SEQUENCE or SET, the alternatives of a
+    /// CHOICE, or the items of an ENUMERATED type, have an extension marker,
+    /// after which come the extension additions.
+    pub extensible: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum TypeKind {
+    Boolean,
+    Integer {
+        /// The named numbers, such as `busy(1)` in `INTEGER { busy(1) }`.
+        named_numbers: Vec<(String, i128)>,
+    },
+    ObjectIdentifier,
+    CharacterString(StringKind),
+    OctetString,
+    /// An ENUMERATED type, with its items in the order they are written in.
+    Enumerated(Vec<EnumerationItem>),
+    Sequence(Vec<Component>),
+    /// A SET, whose components may be encoded in any order.
+    Set(Vec<Component>),
+    Choice(Vec<Component>),
+    SequenceOf(Box<Type>),
+    /// A SET OF, whose elements are unordered.
+    SetOf(Box<Type>),
+    /// A reference to the type assigned the given name.
+    Reference(String),
+}
+
+impl TypeKind {
+    /// Rec.ITU-T X.680 8.6
+    /// The universal tag of the kind of type. This is `None` for a CHOICE,
+    /// which has no tag of its own, and for a reference, whose tag is that
+    /// of the type it refers to.
+    pub fn universal_tag(&self) -> Option<Tag> {
+        match self {
+            TypeKind::Boolean => Some(Tag::universal(1)),
+            TypeKind::Integer { .. } => Some(Tag::universal(2)),
+            TypeKind::OctetString => Some(Tag::universal(4)),
+            TypeKind::ObjectIdentifier => Some(Tag::universal(6)),
+            TypeKind::Enumerated(_) => Some(Tag::universal(10)),
+            TypeKind::CharacterString(kind) => Some(Tag::universal(match kind {
+                StringKind::Utf8 => 12,
+                StringKind::Numeric => 18,
+                StringKind::Printable => 19,
+                StringKind::Ia5 => 22,
+                StringKind::Visible => 26,
+            })),
+            TypeKind::Sequence(_) | TypeKind::SequenceOf(_) => Some(Tag::universal(16)),
+            TypeKind::Set(_) | TypeKind::SetOf(_) => Some(Tag::universal(17)),
+            TypeKind::Choice(_) | TypeKind::Reference(_) => None,
+        }
+    }
+}
+
+/// Rec.ITU-T X.680 20
+/// An item of an ENUMERATED type, such as `green(5)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumerationItem {
+    pub name: String,
+    /// The number of the item, which is assigned to items without one.
+    pub number: i128,
+    /// Whether the item is an extension addition.
+    pub extension: bool,
+}
+
+/// Rec.ITU-T X.680 41
+/// The restricted character string types which are supported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StringKind {
+    Utf8,
+    Ia5,
+    Printable,
+    Visible,
+    Numeric,
+}
+
+impl StringKind {
+    /// The ASN.1 name of the type, such as `IA5String`.
+    pub fn name(self) -> &'static str {
+        match self {
+            StringKind::Utf8 => "UTF8String",
+            StringKind::Ia5 => "IA5String",
+            StringKind::Printable => "PrintableString",
+            StringKind::Visible => "VisibleString",
+            StringKind::Numeric => "NumericString",
+        }
+    }
+
+    /// The type with the given ASN.1 name, if it is supported.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            StringKind::Utf8,
+            StringKind::Ia5,
+            StringKind::Printable,
+            StringKind::Visible,
+            StringKind::Numeric,
+        ]
+        .iter()
+        .copied()
+        .find(|kind| kind.name() == name)
+    }
+
+    /// Rec.ITU-T X.680 41.2 and 41.4
+    /// Ranges of the characters the type permits, both ends included, or
+    /// `None` for UTF8String, which permits every character.
+    pub fn alphabet(self) -> Option<&'static [(char, char)]> {
+        match self {
+            StringKind::Utf8 => None,
+            StringKind::Ia5 => Some(&[('\0', '\x7f')]),
+            StringKind::Visible => Some(&[(' ', '~')]),
+            StringKind::Numeric => Some(&[('0', '9'), (' ', ' ')]),
+            StringKind::Printable => Some(&[
+                ('A', 'Z'),
+                ('a', 'z'),
+                ('0', '9'),
+                (' ', ' '),
+                ('\'', ')'),
+                ('+', '/'),
+                (':', ':'),
+                ('=', '='),
+                ('?', '?'),
+            ]),
+        }
+    }
+}
+
+/// Rec.ITU-T X.680 51
+/// The constraints of a type. Each is `None` if the type doesn't have it.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Constraint {
+    /// The minimum and maximum value of an INTEGER, where `None` is MIN or
+    /// MAX.
+    pub range: Option<(Option<i128>, Option<i128>)>,
+    /// The minimum and, unless it is MAX, the maximum size of a string,
+    /// OCTET STRING or SEQUENCE OF.
+    pub size: Option<(u64, Option<u64>)>,
+    /// Ranges of the characters permitted in a string, both ends included.
+    pub alphabet: Option<Vec<(char, char)>>,
+    /// Whether the range or size constraint has an extension marker, as in
+    /// `(0..7, ...)`, so that values outside of it are valid too.
+    pub extensible: bool,
+}
+
+/// A component of a SEQUENCE, or an alternative of a CHOICE.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Component {
+    pub name: String,
+    pub ty: Type,
+    pub presence: Presence,
+    /// Whether the component is an extension addition, which may be absent
+    /// from the encodings of senders which don't know of it.
+    pub extension: bool,
+    /// The tag of the component, with automatic tagging and the tag
+    /// default of the module applied. `None` if the component takes the
+    /// tag of its type.
+    pub tag: Option<Tag>,
+    /// The lines of the comments documenting the component.
+    pub comments: Vec<String>,
+}
+
+/// Rec.ITU-T X.680 25.1
+#[derive(Clone, Debug, PartialEq)]
+pub enum Presence {
+    Required,
+    Optional,
+    Default(Value),
+}
+
+/// Rec.ITU-T X.680 13.1
+/// How the tags in a module are applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TagDefault {
+    Explicit,
+    Implicit,
+    /// Like IMPLICIT, and the components of SEQUENCEs and CHOICEs without
+    /// any tags are given context specific tags numbered from 0.
+    Automatic,
+}
+
+/// Rec.ITU-T X.680 8.1
+/// The classes are ordered as in the canonical order of tags.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum TagClass {
+    Universal,
+    Application,
+    ContextSpecific,
+    Private,
+}
+
+/// Rec.ITU-T X.680 31.2
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TagMode {
+    /// The tag replaces the tag of the type it is applied to.
+    Implicit,
+    /// The tag is added around the tag of the type it is applied to.
+    Explicit,
+}
+
+/// A tag, with its mode resolved from the tag default of the module.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tag {
+    pub class: TagClass,
+    pub number: u32,
+    pub mode: TagMode,
+}
+
+impl Tag {
+    /// The universal tag with the given number. Universal tags are reported
+    /// as implicit, since there is no other tag inside of them.
+    pub fn universal(number: u32) -> Self {
+        Self {
+            class: TagClass::Universal,
+            number,
+            mode: TagMode::Implicit,
+        }
+    }
+}
+
+/// A value, such as a DEFAULT value or the value of a value assignment.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Boolean(bool),
+    Integer(i128),
+    /// The arcs of the object identifier, starting from the root.
+    ObjectIdentifier(Vec<u64>),
+    String(String),
+    OctetString(Vec<u8>),
+    /// The name of an item of an ENUMERATED type.
+    Enumerated(String),
+}
+
+impl Module {
+    /// Parses the first module in the given string, and resolves its tags.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let ast = AsnModule::parse(s)?;
+        Self::from_ast(s, &ast)
+    }
+
+    /// The type assigned the given name.
+    pub fn get_type(&self, name: &str) -> Option<&TypeAssignment> {
+        self.types.iter().find(|assignment| assignment.name == name)
+    }
+
+    /// The value assigned the given name.
+    pub fn get_value(&self, name: &str) -> Option<&ValueAssignment> {
+        self.values
+            .iter()
+            .find(|assignment| assignment.name == name)
+    }
+
+    /// Follows references to other types, including chains of them, to a
+    /// type which isn't a reference. The constraints and tags of the
+    /// references are not merged into the returned type. `None` if one of
+    /// the references is to a type which isn't defined, or if they form a
+    /// cycle, which parsing rules out but changes to the module may not.
+    pub fn resolve<'a>(&'a self, mut ty: &'a Type) -> Option<&'a Type> {
+        // a chain of references without a cycle refers to each type once
+        for _ in 0..=self.types.len() {
+            match &ty.kind {
+                TypeKind::Reference(name) => ty = &self.get_type(name)?.ty,
+                _ => return Some(ty),
+            }
+        }
+
+        None
+    }
+
+    /// The constraints which apply to a type, including those of the types
+    /// it refers to. Where a reference and the type it refers to both have
+    /// a constraint of the same kind, the one on the reference applies.
+    /// `None` if the type can't be resolved.
+    pub fn constraint_of<'a>(&'a self, mut ty: &'a Type) -> Option<Constraint> {
+        let mut constraint = ty.constraint.clone();
+        for _ in 0..=self.types.len() {
+            ty = match &ty.kind {
+                TypeKind::Reference(name) => &self.get_type(name)?.ty,
+                _ => return Some(constraint),
+            };
+            if constraint.range.is_none() && constraint.size.is_none() {
+                constraint.extensible = ty.constraint.extensible;
+            }
+            constraint.range = constraint.range.or(ty.constraint.range);
+            constraint.size = constraint.size.or(ty.constraint.size);
+            if constraint.alphabet.is_none() {
+                constraint.alphabet = ty.constraint.alphabet.clone();
+            }
+        }
+
+        None
+    }
+
+    /// Rec.ITU-T X.680 8.6
+    /// The outermost tag of a type. This is `None` for an untagged CHOICE,
+    /// whose values take the tag of the chosen alternative.
+    pub fn tag_of(&self, ty: &Type) -> Option<Tag> {
+        if let Some(tag) = ty.tag {
+            return Some(tag);
+        }
+
+        match &ty.kind {
+            TypeKind::Reference(name) => self.get_type(name).and_then(|t| self.tag_of(&t.ty)),
+            kind => kind.universal_tag(),
+        }
+    }
+
+    /// Rec.ITU-T X.680 8.6
+    /// The components of a SET in the canonical order of their tags, which
+    /// orders them by class, and then by number. An untagged CHOICE is
+    /// ordered by the least tag of its alternatives.
+    pub fn canonical_order<'a>(&self, components: &'a [Component]) -> Vec<&'a Component> {
+        let mut ordered: Vec<&Component> = components.iter().collect();
+        ordered.sort_by_key(|component| self.least_tag(component.tag, &component.ty));
+        ordered
+    }
+
+    /// The class and number of the least tag a value of the type may have,
+    /// as a component with the given tag.
+    fn least_tag(&self, tag: Option<Tag>, ty: &Type) -> Option<(TagClass, u32)> {
+        if let Some(tag) = tag.or_else(|| self.tag_of(ty)) {
+            return Some((tag.class, tag.number));
+        }
+
+        match self.resolve(ty).map(|ty| &ty.kind) {
+            Some(TypeKind::Choice(alternatives)) => alternatives
+                .iter()
+                .filter_map(|alternative| self.least_tag(alternative.tag, &alternative.ty))
+                .min(),
+            _ => None,
+        }
+    }
+
+    /// Converts the syntax tree of the module parsed from `source`.
+    fn from_ast(source: &str, ast: &AsnModule) -> Result<Self, Error> {
+        let converter = Converter { ast };
+
+        let mut types = vec![];
+        let mut values = vec![];
+        for &name in &ast.assignments {
+            let comments = ast.docs[name]
+                .comments
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+
+            if let Some(value) = ast.values.get(name) {
+                values.push(ValueAssignment {
+                    name: name.to_string(),
+                    ty: converter.convert_type(&value.value_type),
+                    value: convert_value(&value.value),
+                    comments,
+                });
+                continue;
+            }
+
+            let mut ty = if let Some(sequence) = ast.sequences.get(name) {
+                let components = converter.components(name, &sequence.fields);
+                Type {
+                    extensible: sequence.extensible,
+                    ..converter.plain(if sequence.set {
+                        TypeKind::Set(components)
+                    } else {
+                        TypeKind::Sequence(components)
+                    })
+                }
+            } else if let Some(choice) = ast.choices.get(name) {
+                Type {
+                    extensible: choice.extensible,
+                    ..converter.plain(TypeKind::Choice(
+                        converter.components(name, &choice.alternatives),
+                    ))
+                }
+            } else if let Some(enumerated) = ast.enumerations.get(name) {
+                let items = enumerated
+                    .items
+                    .iter()
+                    .map(|item| EnumerationItem {
+                        name: item.name.to_string(),
+                        number: item.value,
+                        extension: item.extension,
+                    })
+                    .collect();
+                Type {
+                    extensible: enumerated.extensible,
+                    ..converter.plain(TypeKind::Enumerated(items))
+                }
+            } else {
+                let mut ty = converter.convert_type(&ast.type_aliases[name]);
+                if let Some(numbers) = ast.named_numbers.get(name) {
+                    ty.kind = TypeKind::Integer {
+                        named_numbers: numbers
+                            .iter()
+                            .map(|number| (number.name.to_string(), number.value))
+                            .collect(),
+                    };
+                }
+                ty
+            };
+            if let Some(tag) = ast.tags.get(name) {
+                let aliased = ast.type_aliases.get(name);
+                ty.tag = Some(converter.tag(tag, aliased));
+            }
+
+            types.push(TypeAssignment {
+                name: name.to_string(),
+                ty,
+                comments,
+            });
+        }
+
+        let module = Self {
+            name: ast.name.to_string(),
+            tag_default: ast.tag_default,
+            types,
+            values,
+        };
+
+        // every reference has to be to a type of the module, so that
+        // `resolve` and `constraint_of` can't fail
+        for &name in &ast.assignments {
+            let ty = match module.get_value(name) {
+                Some(value) => &value.ty,
+                None => &module.get_type(name).expect("all types are converted").ty,
+            };
+            let fields = match (ast.sequences.get(name), ast.choices.get(name)) {
+                (Some(sequence), _) => &sequence.fields,
+                (_, Some(choice)) => &choice.alternatives,
+                _ => {
+                    module.check_references(ty, line(source, ast.docs[name].definition))?;
+                    continue;
+                }
+            };
+            // components are reported on their own line
+            if let TypeKind::Sequence(components)
+            | TypeKind::Set(components)
+            | TypeKind::Choice(components) = &ty.kind
+            {
+                for (field, component) in fields.iter().zip(components) {
+                    module.check_references(&component.ty, line(source, field.docs.definition))?;
+                }
+            }
+        }
+
+        Ok(module)
+    }
+
+    /// Fails if the type, or a type it is made of, refers to a type which
+    /// isn't defined in the module.
+    fn check_references(&self, ty: &Type, line: usize) -> Result<(), Error> {
+        match &ty.kind {
+            TypeKind::Reference(name) if self.get_type(name).is_none() => Err(Error {
+                line,
+                message: format!("type `{}` is not defined", name),
+            }),
+            TypeKind::SequenceOf(element) | TypeKind::SetOf(element) => {
+                self.check_references(element, line)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Converts the syntax tree of a module into the owned types.
+struct Converter<'a, 'b> {
+    ast: &'b AsnModule<'a>,
+}
+
+impl<'a, 'b> Converter<'a, 'b> {
+    fn plain(&self, kind: TypeKind) -> Type {
+        Type {
+            kind,
+            tag: None,
+            constraint: Constraint::default(),
+            extensible: false,
+        }
+    }
+
+    fn convert_type(&self, asn_type: &AsnType) -> Type {
+        match asn_type {
+            AsnType::Integer => self.plain(TypeKind::Integer {
+                named_numbers: vec![],
+            }),
+            AsnType::BoundedInteger {
+                min,
+                max,
+                extensible,
+            } => Type {
+                constraint: Constraint {
+                    range: Some((*min, *max)),
+                    extensible: *extensible,
+                    ..Constraint::default()
+                },
+                ..self.plain(TypeKind::Integer {
+                    named_numbers: vec![],
+                })
+            },
+            AsnType::ObjectIdentifier => self.plain(TypeKind::ObjectIdentifier),
+            AsnType::Boolean => self.plain(TypeKind::Boolean),
+            AsnType::OctetString => self.plain(TypeKind::OctetString),
+            AsnType::CharacterString(name) => {
+                let kind = StringKind::from_name(name)
+                    .unwrap_or_else(|| panic!("unsupported character string type `{}`", name));
+                self.plain(TypeKind::CharacterString(kind))
+            }
+            AsnType::SequenceOf(element) => {
+                self.plain(TypeKind::SequenceOf(Box::new(self.convert_type(element))))
+            }
+            AsnType::SetOf(element) => {
+                self.plain(TypeKind::SetOf(Box::new(self.convert_type(element))))
+            }
+            AsnType::Custom(name) => self.plain(TypeKind::Reference(name.to_string())),
+            AsnType::ConstrainedReference {
+                name,
+                min,
+                max,
+                extensible,
+            } => Type {
+                constraint: Constraint {
+                    range: Some((*min, *max)),
+                    extensible: *extensible,
+                    ..Constraint::default()
+                },
+                ..self.plain(TypeKind::Reference(name.to_string()))
+            },
+            AsnType::Constrained(inner, constraint) => {
+                let mut ty = self.convert_type(inner);
+                ty.constraint.size = constraint.size;
+                ty.constraint.alphabet = constraint.alphabet.clone();
+                ty.constraint.extensible = constraint.extensible;
+                ty
+            }
+        }
+    }
+
+    /// Converts the components of the SEQUENCE or CHOICE with the given
+    /// name, applying automatic tagging.
+    fn components(&self, type_name: &str, fields: &[crate::ast::AsnField]) -> Vec<Component> {
+        // Rec.ITU-T X.680 25.3
+        let automatic = self.ast.tag_default == TagDefault::Automatic
+            && fields.iter().all(|field| field.tag.is_none());
+
+        fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let tag = match field.tag {
+                    Some(tag) => Some(self.tag(&tag, Some(&field.field_type))),
+                    None if automatic => Some(Tag {
+                        class: TagClass::ContextSpecific,
+                        number: index as u32,
+                        mode: self.mode(None, Some(&field.field_type)),
+                    }),
+                    None => None,
+                };
+                let presence = match field.presence {
+                    AsnPresence::Required => Presence::Required,
+                    AsnPresence::Optional => Presence::Optional,
+                    AsnPresence::Default => Presence::Default(convert_value(
+                        &self.ast.defaults[&(type_name, field.name)],
+                    )),
+                };
+
+                let mut ty = self.convert_type(&field.field_type);
+                if !field.named_numbers.is_empty() {
+                    ty.kind = TypeKind::Integer {
+                        named_numbers: field
+                            .named_numbers
+                            .iter()
+                            .map(|number| (number.name.to_string(), number.value))
+                            .collect(),
+                    };
+                }
+
+                Component {
+                    name: field.name.to_string(),
+                    ty,
+                    presence,
+                    extension: field.extension,
+                    tag,
+                    comments: field
+                        .docs
+                        .comments
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// Resolves the mode of a tag applied to the given type.
+    fn tag(&self, tag: &AsnTag, tagged: Option<&AsnType>) -> Tag {
+        Tag {
+            class: tag.class,
+            number: tag.number,
+            mode: self.mode(tag.mode, tagged),
+        }
+    }
+
+    /// Rec.ITU-T X.680 31.2.7
+    /// Tags are explicit when they are written so, when the module's tag
+    /// default is EXPLICIT, and when they are applied to an untagged CHOICE,
+    /// which has no tag of its own to replace.
+    fn mode(&self, mode: Option<TagMode>, tagged: Option<&AsnType>) -> TagMode {
+        if let Some(mode) = mode {
+            return mode;
+        }
+        if self.ast.tag_default == TagDefault::Explicit
+            || tagged.is_some_and(|tagged| self.is_untagged_choice(tagged))
+        {
+            TagMode::Explicit
+        } else {
+            TagMode::Implicit
+        }
+    }
+
+    fn is_untagged_choice(&self, asn_type: &AsnType) -> bool {
+        let mut name = match asn_type {
+            AsnType::Custom(name) => *name,
+            _ => return false,
+        };
+        loop {
+            if self.ast.tags.contains_key(name) {
+                return false;
+            }
+            match self.ast.type_aliases.get(name) {
+                Some(AsnType::Custom(aliased)) => name = aliased,
+                Some(_) => return false,
+                None => return self.ast.choices.contains_key(name),
+            }
+        }
+    }
+}
+
+fn convert_value(value: &AsnValue) -> Value {
+    match value {
+        AsnValue::Integer(value) => Value::Integer(*value),
+        AsnValue::ObjectIdentifier(arcs) => Value::ObjectIdentifier(arcs.clone()),
+        AsnValue::Boolean(value) => Value::Boolean(*value),
+        AsnValue::String(value) => Value::String(value.clone()),
+        AsnValue::OctetString(octets) => Value::OctetString(octets.clone()),
+        AsnValue::Enumerated(name) => Value::Enumerated(name.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAGS: &str = "Tags DEFINITIONS AUTOMATIC TAGS ::= BEGIN
+        -- a flag
+        Flag ::= [APPLICATION 1] BOOLEAN
+        Shape ::= CHOICE { circle INTEGER, square INTEGER }
+        Drawing ::= SEQUENCE {
+            shape Shape,
+            flag Flag OPTIONAL,
+            size INTEGER (0..10) DEFAULT 5
+        }
+        Labels ::= SEQUENCE {
+            first [1] IA5String (SIZE(1..4)),
+            second [2] EXPLICIT Shape,
+            third Shape
+        }
+        END";
+
+    #[test]
+    fn tags() {
+        let module = Module::parse(TAGS).unwrap();
+
+        assert_eq!(TagDefault::Automatic, module.tag_default);
+        let flag = module.get_type("Flag").unwrap();
+        assert_eq!(vec!["a flag"], flag.comments);
+        assert_eq!(
+            Some(Tag {
+                class: TagClass::Application,
+                number: 1,
+                mode: TagMode::Implicit,
+            }),
+            module.tag_of(&flag.ty)
+        );
+        assert_eq!(None, module.tag_of(&module.get_type("Shape").unwrap().ty));
+
+        // components are tagged automatically, and tags on a CHOICE are
+        // explicit
+        let drawing = &module.get_type("Drawing").unwrap().ty;
+        let tags: Vec<Option<Tag>> = match &drawing.kind {
+            TypeKind::Sequence(components) => components.iter().map(|c| c.tag).collect(),
+            _ => panic!("Drawing should be a SEQUENCE"),
+        };
+        let context = |number, mode| Tag {
+            class: TagClass::ContextSpecific,
+            number,
+            mode,
+        };
+        assert_eq!(
+            vec![
+                Some(context(0, TagMode::Explicit)),
+                Some(context(1, TagMode::Implicit)),
+                Some(context(2, TagMode::Implicit)),
+            ],
+            tags
+        );
+        assert_eq!(Some(Tag::universal(16)), module.tag_of(drawing));
+
+        // automatic tagging doesn't apply once a component is tagged
+        let labels = &module.get_type("Labels").unwrap().ty;
+        let tags: Vec<Option<Tag>> = match &labels.kind {
+            TypeKind::Sequence(components) => components.iter().map(|c| c.tag).collect(),
+            _ => panic!("Labels should be a SEQUENCE"),
+        };
+        assert_eq!(
+            vec![
+                Some(context(1, TagMode::Implicit)),
+                Some(context(2, TagMode::Explicit)),
+                None,
+            ],
+            tags
+        );
+    }
+
+    #[test]
+    fn extensions() {
+        let module = Module::parse(include_str!("../../test-asn/extensions.asn")).unwrap();
+
+        let setup = &module.get_type("Setup").unwrap().ty;
+        assert!(setup.extensible);
+        let components = match &setup.kind {
+            TypeKind::Sequence(components) => components,
+            kind => panic!("Setup should be a SEQUENCE, not {:?}", kind),
+        };
+        let extensions: Vec<bool> = components
+            .iter()
+            .map(|component| component.extension)
+            .collect();
+        assert_eq!(vec![false, false, true, true], extensions);
+        // automatic tags continue into the extension additions
+        assert_eq!(Some(2), components[2].tag.map(|tag| tag.number));
+
+        let level = module.constraint_of(&components[2].ty).unwrap();
+        assert_eq!(Some((Some(0), Some(7))), level.range);
+        assert!(level.extensible);
+        assert!(!module.constraint_of(&components[3].ty).unwrap().extensible);
+        assert!(module.get_type("Procedure").unwrap().ty.extensible);
+    }
+
+    #[test]
+    fn sets() {
+        let module = Module::parse(include_str!("../../test-asn/sets.asn")).unwrap();
+
+        let entry = &module.get_type("Entry").unwrap().ty;
+        assert_eq!(Some(Tag::universal(17)), module.tag_of(entry));
+        let components = match &entry.kind {
+            TypeKind::Set(components) => components,
+            kind => panic!("Entry should be a SET, not {:?}", kind),
+        };
+        let names: Vec<&str> = module
+            .canonical_order(components)
+            .iter()
+            .map(|component| component.name.as_str())
+            .collect();
+        assert_eq!(vec!["active", "serial", "labels", "name"], names);
+
+        assert!(matches!(
+            module.resolve(&components[0].ty).unwrap().kind,
+            TypeKind::SetOf(_)
+        ));
+        assert_eq!(
+            Some((1, None)),
+            module.constraint_of(&components[0].ty).unwrap().size
+        );
+    }
+
+    #[test]
+    fn constraints_and_defaults() {
+        let module = Module::parse(TAGS).unwrap();
+
+        let components = match &module.get_type("Drawing").unwrap().ty.kind {
+            TypeKind::Sequence(components) => components.clone(),
+            _ => panic!("Drawing should be a SEQUENCE"),
+        };
+        assert_eq!(Presence::Required, components[0].presence);
+        assert_eq!(Presence::Optional, components[1].presence);
+        assert_eq!(Presence::Default(Value::Integer(5)), components[2].presence);
+        assert_eq!(Some((Some(0), Some(10))), components[2].ty.constraint.range);
+        assert!(matches!(
+            module.resolve(&components[0].ty).unwrap().kind,
+            TypeKind::Choice(_)
+        ));
+
+        let labels = match &module.get_type("Labels").unwrap().ty.kind {
+            TypeKind::Sequence(components) => components.clone(),
+            _ => panic!("Labels should be a SEQUENCE"),
+        };
+        assert_eq!(
+            TypeKind::CharacterString(StringKind::Ia5),
+            labels[0].ty.kind
+        );
+        assert_eq!(Some((1, Some(4))), labels[0].ty.constraint.size);
+    }
+
+    #[test]
+    fn constraint_of() {
+        let module = Module::parse(
+            "Constraints DEFINITIONS ::= BEGIN
+            Code ::= IA5String (SIZE(2..4, ...)) (FROM(\"A\"..\"Z\"))
+            ShortCode ::= Code (SIZE(2))
+            Letters ::= Code (FROM(\"a\"..\"z\"))
+            END",
+        )
+        .unwrap();
+
+        let short_code = &module.get_type("ShortCode").unwrap().ty;
+        assert_eq!(
+            Some(Constraint {
+                range: None,
+                size: Some((2, Some(2))),
+                alphabet: Some(vec![('A', 'Z')]),
+                extensible: false,
+            }),
+            module.constraint_of(short_code)
+        );
+        // the extension marker goes along with the size it is on
+        let letters = &module.get_type("Letters").unwrap().ty;
+        assert_eq!(
+            Some(Constraint {
+                range: None,
+                size: Some((2, Some(4))),
+                alphabet: Some(vec![('a', 'z')]),
+                extensible: true,
+            }),
+            module.constraint_of(letters)
+        );
+    }
+
+    #[test]
+    fn unresolved() {
+        let mut module =
+            Module::parse("Unresolved DEFINITIONS ::= BEGIN\nA ::= B\nB ::= INTEGER\nEND").unwrap();
+        let a = module.get_type("A").unwrap().ty.clone();
+        assert_eq!(
+            TypeKind::Integer {
+                named_numbers: vec![]
+            },
+            module.resolve(&a).unwrap().kind
+        );
+
+        // a reference to a type which isn't defined
+        module.types[1].name = "C".into();
+        assert_eq!(None, module.resolve(&a));
+        assert_eq!(None, module.constraint_of(&a));
+
+        // a cycle of references
+        module.types[1].name = "B".into();
+        module.types[1].ty.kind = TypeKind::Reference("A".into());
+        assert_eq!(None, module.resolve(&a));
+        assert_eq!(None, module.constraint_of(&a));
+    }
+
+    #[test]
+    fn geo() {
+        let module = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+
+        assert_eq!("Geometry", module.name);
+        assert_eq!(TagDefault::Explicit, module.tag_default);
+        assert_eq!(6, module.types.len());
+        let rectangle = module.get_type("SmallRectangle").unwrap();
+        match &rectangle.ty.kind {
+            TypeKind::Sequence(components) => {
+                assert_eq!("width", components[0].name);
+                assert_eq!(None, components[0].tag);
+                assert_eq!(
+                    TypeKind::Reference("Unsigned16".into()),
+                    components[0].ty.kind
+                );
+                assert_eq!(
+                    Some((Some(0), Some(65535))),
+                    module.resolve(&components[0].ty).unwrap().constraint.range
+                );
+            }
+            _ => panic!("SmallRectangle should be a SEQUENCE"),
+        }
+    }
+
+    #[test]
+    fn values() {
+        let module = Module::parse(include_str!("../../test-asn/defaults.asn")).unwrap();
+
+        assert_eq!(
+            Value::String("hello \"world\"".into()),
+            module.get_value("greeting").unwrap().value
+        );
+        assert!(module.get_value("Job").is_none());
+    }
+
+    #[test]
+    fn errors() {
+        let error = Module::parse("Broken DEFINITIONS ::= BEGIN\nA ::= SEQUENCE {\n a B\n").err();
+        assert_eq!(
+            Some(Error {
+                line: 3,
+                message: "unexpected end of ASN.1 input".into(),
+            }),
+            error
+        );
+
+        let error = Module::parse(
+            "Undefined DEFINITIONS ::= BEGIN\nA ::= SEQUENCE {\n y INTEGER,\n x Foo\n}\nEND",
+        )
+        .err();
+        assert_eq!(
+            Some(Error {
+                line: 4,
+                message: "type `Foo` is not defined".into(),
+            }),
+            error
+        );
+        for (line, module) in [
+            (2, "Undefined DEFINITIONS ::= BEGIN\nA ::= Foo\nEND"),
+            (
+                2,
+                "Undefined DEFINITIONS ::= BEGIN\nA ::= SEQUENCE OF Foo (SIZE(1..4))\nEND",
+            ),
+            (
+                3,
+                "Undefined DEFINITIONS ::= BEGIN\nA ::= CHOICE {\n x Foo (0..7)\n}\nEND",
+            ),
+            (2, "Undefined DEFINITIONS ::= BEGIN\nfoo Foo ::= 1\nEND"),
+        ] {
+            assert_eq!(line, Module::parse(module).unwrap_err().line, "{}", module);
+        }
+    }
+}
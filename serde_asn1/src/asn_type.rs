@@ -0,0 +1,24 @@
+use asn1_schema::{Module, Type};
+
+/// A rust type generated from an ASN.1 type, which knows the ASN.1 type it
+/// stands for. The serde data model loses the constraints, tags and
+/// OPTIONAL components of ASN.1 types, so the codecs of this crate follow
+/// the ASN.1 type instead.
+///
+/// `asn1_codegen::from!` implements this for the types it generates, other
+/// than rust type aliases.
+pub trait AsnType {
+    /// The name of the ASN.1 type in its module.
+    const NAME: &'static str;
+
+    /// The module the ASN.1 type is defined in.
+    fn module() -> &'static Module;
+
+    /// The ASN.1 type, with its constraints, tags and components.
+    fn asn_type() -> &'static Type {
+        match Self::module().get_type(Self::NAME) {
+            Some(assignment) => &assignment.ty,
+            None => panic!("type `{}` is not defined in its module", Self::NAME),
+        }
+    }
+}
@@ -0,0 +1,1848 @@
+//! Rec.ITU-T X.690
+//! The Basic Encoding Rules (BER), driven by the ASN.1 types of a schema.
+//! Each value is encoded as a tag, a length and the contents, which for
+//! constructed values are themselves encoded values. The Distinguished
+//! Encoding Rules (DER) and the Canonical Encoding Rules (CER) share the
+//! encoding, and each allow a single encoding of each value.
+
+use asn1_schema::{Component, Module, Presence, Tag, TagClass, TagMode, Type, TypeKind};
+use std::cmp::Ordering;
+use std::io;
+
+use crate::constraints::{component_path, element_path};
+use crate::error::{Error, Result};
+use crate::oer::{
+    base128_octets, object_identifier_arcs, object_identifier_octets, signed_octets,
+    tag_class_bits, unsigned_octets,
+};
+use crate::value::{
+    check_constraint, component_values, enumerated_value, enumeration_item, invalid_value,
+    kind_name, lookup, octets, resolve, unsupported_type,
+};
+use crate::{Value, Violation, DEFAULT_RECURSION_LIMIT};
+
+/// How values are encoded with BER, which leaves some choices to the
+/// sender.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BerOptions {
+    /// Whether constructed values use the indefinite length form, which
+    /// lets them be written before their length is known, rather than the
+    /// definite form.
+    pub indefinite_length: bool,
+}
+
+/// The encoding rules which share the BER encoding.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Rules {
+    Basic,
+    /// Rec.ITU-T X.690 10, 11
+    /// Definite lengths, with the choices BER leaves to the sender made
+    /// canonically. Decoding fails on any other encoding.
+    Distinguished,
+    /// Rec.ITU-T X.690 9, 11
+    /// Like DER, but with the indefinite length form for constructed values,
+    /// so that they can be written as they are encoded, and strings of over
+    /// 1000 octets split into segments of 1000 octets.
+    Canonical,
+}
+
+/// Rec.ITU-T X.690 9.2
+/// The number of octets of each segment of a string in CER, and the most a
+/// string in the primitive form may have.
+const CER_SEGMENT_LEN: usize = 1000;
+
+/// Decodes a value of the type with the given name in `module` from BER
+/// bytes.
+pub fn value_from_ber_bytes(module: &Module, type_name: &str, input: &[u8]) -> Result<Value> {
+    let ty = lookup(module, type_name)?;
+
+    decode(module, ty, input, DEFAULT_RECURSION_LIMIT, Rules::Basic)
+}
+
+/// Encodes a value of the type with the given name in `module` as BER
+/// bytes, with definite lengths. Components with a DEFAULT value are left
+/// out when they have it.
+pub fn value_to_ber_bytes(module: &Module, type_name: &str, value: &Value) -> Result<Vec<u8>> {
+    let ty = lookup(module, type_name)?;
+
+    let mut output = vec![];
+    encode(
+        module,
+        ty,
+        value,
+        &mut output,
+        Rules::Basic,
+        BerOptions::default(),
+    )?;
+    Ok(output)
+}
+
+/// Decodes a value of the type with the given name in `module` from DER
+/// bytes, failing with `Error::NonCanonical` on encodings which BER allows
+/// but DER doesn't.
+pub fn value_from_der_bytes(module: &Module, type_name: &str, input: &[u8]) -> Result<Value> {
+    let ty = lookup(module, type_name)?;
+
+    decode(
+        module,
+        ty,
+        input,
+        DEFAULT_RECURSION_LIMIT,
+        Rules::Distinguished,
+    )
+}
+
+/// Encodes a value of the type with the given name in `module` as DER
+/// bytes.
+pub fn value_to_der_bytes(module: &Module, type_name: &str, value: &Value) -> Result<Vec<u8>> {
+    let ty = lookup(module, type_name)?;
+
+    let mut output = vec![];
+    encode(
+        module,
+        ty,
+        value,
+        &mut output,
+        Rules::Distinguished,
+        BerOptions::default(),
+    )?;
+    Ok(output)
+}
+
+/// Decodes a value of the type with the given name in `module` from CER
+/// bytes, failing with `Error::NonCanonical` on encodings which BER allows
+/// but CER doesn't.
+pub fn value_from_cer_bytes(module: &Module, type_name: &str, input: &[u8]) -> Result<Value> {
+    let ty = lookup(module, type_name)?;
+
+    decode(module, ty, input, DEFAULT_RECURSION_LIMIT, Rules::Canonical)
+}
+
+/// Encodes a value of the type with the given name in `module` as CER
+/// bytes.
+pub fn value_to_cer_bytes(module: &Module, type_name: &str, value: &Value) -> Result<Vec<u8>> {
+    let ty = lookup(module, type_name)?;
+
+    let mut output = vec![];
+    encode(
+        module,
+        ty,
+        value,
+        &mut output,
+        Rules::Canonical,
+        BerOptions::default(),
+    )?;
+    Ok(output)
+}
+
+/// Decodes a value of the given type, which may nest constructed values up
+/// to `recursion_limit` deep.
+pub(crate) fn decode(
+    module: &Module,
+    ty: &Type,
+    input: &[u8],
+    recursion_limit: usize,
+    rules: Rules,
+) -> Result<Value> {
+    let mut decoder = BerDecoder::new(input, recursion_limit, rules);
+    let value = decoder.read_value(module, None, ty, "")?;
+    if decoder.input.is_empty() {
+        Ok(value)
+    } else {
+        Err(Error::TrailingCharacters)
+    }
+}
+
+/// Writes a value of the given type to `writer`. With the indefinite length
+/// form, which CER always uses, values are written as they are encoded, so
+/// part of a value may have been written when encoding fails.
+pub(crate) fn encode<W: io::Write>(
+    module: &Module,
+    ty: &Type,
+    value: &Value,
+    writer: W,
+    rules: Rules,
+    options: BerOptions,
+) -> Result<()> {
+    let mut encoder = BerEncoder {
+        module,
+        rules,
+        options,
+        writer,
+        buffers: vec![],
+    };
+    encoder.write_value(None, ty, value, "")
+}
+
+/// Rec.ITU-T X.690 8.14
+/// The tags of the values a value of a type is encoded as, outermost first,
+/// given the tag of the component it is a value of. Each of them is the tag
+/// of a constructed value holding the next. An implicit tag replaces the
+/// tag after it, so only the first tag and those after an explicit tag are
+/// encoded. Untagged CHOICEs have none, since their values are encoded as
+/// the chosen alternative.
+fn encoded_tags<'a>(module: &'a Module, tag: Option<Tag>, mut ty: &'a Type) -> Vec<Tag> {
+    let mut tags: Vec<Tag> = tag.into_iter().collect();
+    loop {
+        tags.extend(ty.tag);
+        match &ty.kind {
+            TypeKind::Reference(name) => {
+                ty = match module.get_type(name) {
+                    Some(assignment) => &assignment.ty,
+                    None => unreachable!("references are checked by `lookup`"),
+                }
+            }
+            kind => {
+                tags.extend(kind.universal_tag());
+                break;
+            }
+        }
+    }
+
+    tags.iter()
+        .enumerate()
+        .filter(|(index, _)| *index == 0 || tags[index - 1].mode == TagMode::Explicit)
+        .map(|(_, tag)| *tag)
+        .collect()
+}
+
+/// Whether a value of the type, as a component with the given tag, may
+/// start with the given tag.
+fn starts_with(module: &Module, tag: Option<Tag>, ty: &Type, class: TagClass, number: u32) -> bool {
+    match encoded_tags(module, tag, ty).first() {
+        Some(tag) => tag.class == class && tag.number == number,
+        None => match module.resolve(ty).map(|ty| &ty.kind) {
+            Some(TypeKind::Choice(alternatives)) => alternatives.iter().any(|alternative| {
+                starts_with(module, alternative.tag, &alternative.ty, class, number)
+            }),
+            _ => unreachable!("only CHOICEs are untagged"),
+        },
+    }
+}
+
+/// Rec.ITU-T X.690 8.1.2.2
+fn tag_class(bits: u8) -> TagClass {
+    match bits {
+        0b00 => TagClass::Universal,
+        0b01 => TagClass::Application,
+        0b10 => TagClass::ContextSpecific,
+        _ => TagClass::Private,
+    }
+}
+
+/// Rec.ITU-T X.690 11.6
+/// The order of the encodings of the elements of a SET OF, as octet strings
+/// where the shorter one is padded with trailing zero octets.
+pub(crate) fn set_of_order(a: &[u8], b: &[u8]) -> Ordering {
+    let padded = |bytes: &[u8], index| bytes.get(index).copied().unwrap_or(0);
+    (0..a.len().max(b.len()))
+        .map(|index| padded(a, index).cmp(&padded(b, index)))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+pub(crate) fn non_canonical(path: &str, message: &str) -> Error {
+    Error::NonCanonical(Violation {
+        path: path.to_string(),
+        message: message.to_string(),
+    })
+}
+
+/// The identifier and length octets of an encoded value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Header {
+    class: TagClass,
+    number: u32,
+    constructed: bool,
+    /// The length of the contents, or `None` for the indefinite form, where
+    /// the contents end with an end-of-contents marker.
+    length: Option<usize>,
+}
+
+/// Reads values from BER bytes.
+struct BerDecoder<'de> {
+    input: &'de [u8],
+    /// Whether the contents being read have the indefinite length form.
+    indefinite: bool,
+    depth: usize,
+    recursion_limit: usize,
+    rules: Rules,
+}
+
+impl<'de> BerDecoder<'de> {
+    fn new(input: &'de [u8], recursion_limit: usize, rules: Rules) -> Self {
+        Self {
+            input,
+            indefinite: false,
+            depth: 0,
+            recursion_limit,
+            rules,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'de [u8]> {
+        if self.input.len() < len {
+            return Err(Error::Eof);
+        }
+        let (bytes, rest) = self.input.split_at(len);
+        self.input = rest;
+
+        Ok(bytes)
+    }
+
+    fn take_byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Fails with `Error::NonCanonical` unless the encoding at `path` is
+    /// `canonical`, or the rules are BER, which allows any encoding.
+    fn require(&self, canonical: bool, path: &str, message: &str) -> Result<()> {
+        if canonical || self.rules == Rules::Basic {
+            Ok(())
+        } else {
+            Err(non_canonical(path, message))
+        }
+    }
+
+    /// Rec.ITU-T X.690 8.1.2
+    /// Returns the class, number and whether the value is constructed. Tag
+    /// numbers of 31 and above are written in subsequent octets, seven bits
+    /// at a time, with the high bit set on all but the last.
+    fn read_identifier(&mut self) -> Result<(TagClass, u32, bool)> {
+        let first = self.take_byte()?;
+        let mut number = u32::from(first & 0x1f);
+        if number == 0x1f {
+            number = 0;
+            loop {
+                let b = self.take_byte()?;
+                number = number
+                    .checked_mul(128)
+                    .map(|number| number | u32::from(b & 0x7f))
+                    .ok_or(Error::InvalidTag)?;
+                if b & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+
+        Ok((tag_class(first >> 6), number, first & 0x20 != 0))
+    }
+
+    /// Rec.ITU-T X.690 8.1.3
+    /// A length is either a single octet holding a length of up to 127, the
+    /// octet 0x80 for the indefinite form, or an octet with the high bit set
+    /// whose remaining bits give the number of subsequent octets holding
+    /// the length.
+    fn read_length(&mut self) -> Result<Option<usize>> {
+        let first = self.take_byte()?;
+        match first {
+            0x00..=0x7f => Ok(Some(usize::from(first))),
+            0x80 => Ok(None),
+            // Rec.ITU-T X.690 8.1.3.5 c), reserved
+            0xff => Err(Error::InvalidLength),
+            _ => {
+                let octets = self.take(usize::from(first & 0x7f))?;
+                octets
+                    .iter()
+                    .try_fold(0_usize, |len, &b| {
+                        len.checked_mul(256)
+                            .map(|len| len + usize::from(b))
+                            .ok_or(Error::LengthOverflow)
+                    })
+                    .map(Some)
+            }
+        }
+    }
+
+    /// Reads the header of the value at `path`.
+    fn read_header(&mut self, path: &str) -> Result<Header> {
+        let start = self.input;
+        let (class, number, constructed) = self.read_identifier()?;
+        let identifier = &start[..start.len() - self.input.len()];
+        // Rec.ITU-T X.690 8.1.2.4.2 c), 10.1
+        self.require(
+            identifier.len() == 1 || (number >= 0x1f && identifier[1] != 0x80),
+            path,
+            "tag is not encoded in the fewest octets",
+        )?;
+
+        let start = self.input;
+        let length = self.read_length()?;
+        let octets = &start[..start.len() - self.input.len()];
+        match length {
+            // Rec.ITU-T X.690 8.1.3.2 a), only constructed values may have
+            // the indefinite form
+            None if !constructed => return Err(Error::InvalidLength),
+            // Rec.ITU-T X.690 9.1, 10.1
+            None => self.require(
+                self.rules == Rules::Canonical,
+                path,
+                "length has the indefinite form",
+            )?,
+            Some(_) if constructed && self.rules == Rules::Canonical => {
+                self.require(false, path, "length has the definite form")?
+            }
+            Some(length) => self.require(
+                octets.len() == 1 || (length >= 0x80 && octets[1] != 0),
+                path,
+                "length is not encoded in the fewest octets",
+            )?,
+        }
+
+        Ok(Header {
+            class,
+            number,
+            constructed,
+            length,
+        })
+    }
+
+    /// Reads the header of the value at `path`, which must have the given
+    /// tag.
+    fn read_tagged_header(&mut self, tag: Tag, path: &str) -> Result<Header> {
+        let header = self.read_header(path)?;
+        if header.class == tag.class && header.number == tag.number {
+            Ok(header)
+        } else {
+            Err(Error::UnexpectedTag {
+                class: header.class,
+                number: header.number,
+            })
+        }
+    }
+
+    /// The tag of the next value, without reading it.
+    fn peek_tag(&self) -> Result<(TagClass, u32)> {
+        let (class, number, _) = Self::new(self.input, 0, self.rules).read_identifier()?;
+        Ok((class, number))
+    }
+
+    /// Whether the contents being read are at their end, which for the
+    /// indefinite form is the end-of-contents marker.
+    fn at_end(&self) -> bool {
+        if self.indefinite {
+            self.input.starts_with(&[0, 0])
+        } else {
+            self.input.is_empty()
+        }
+    }
+
+    /// Rec.ITU-T X.690 8.1.3, 8.1.5
+    /// Runs the given function on the contents of the constructed value with
+    /// the given header, one level deeper into nested values. The function
+    /// must read all of the contents.
+    fn constructed<F, T>(&mut self, header: Header, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        if !header.constructed {
+            return Err(Error::UnexpectedForm);
+        }
+        if self.depth >= self.recursion_limit {
+            return Err(Error::RecursionLimitExceeded);
+        }
+
+        let indefinite = self.indefinite;
+        let rest = match header.length {
+            Some(len) => {
+                let contents = self.take(len)?;
+                Some(std::mem::replace(&mut self.input, contents))
+            }
+            None => None,
+        };
+        self.indefinite = header.length.is_none();
+        self.depth += 1;
+
+        let result = f(self).and_then(|value| {
+            if !self.at_end() {
+                let (class, number) = self.peek_tag()?;
+                return Err(Error::UnexpectedTag { class, number });
+            }
+            if self.indefinite {
+                self.take(2)?;
+            }
+            Ok(value)
+        });
+
+        self.depth -= 1;
+        self.indefinite = indefinite;
+        if let Some(rest) = rest {
+            self.input = rest;
+        }
+
+        result
+    }
+
+    /// Reads the contents of the primitive value with the given header.
+    fn primitive(&mut self, header: Header) -> Result<&'de [u8]> {
+        match header.length {
+            Some(len) if !header.constructed => self.take(len),
+            _ => Err(Error::UnexpectedForm),
+        }
+    }
+
+    /// Reads the value at `path` of the given type, as a component with the
+    /// given tag.
+    fn read_value(
+        &mut self,
+        module: &Module,
+        tag: Option<Tag>,
+        ty: &Type,
+        path: &str,
+    ) -> Result<Value> {
+        let tags = encoded_tags(module, tag, ty);
+        let value = self.read_tagged(module, &tags, ty, path)?;
+        check_constraint(module, ty, &value, path)?;
+
+        Ok(value)
+    }
+
+    fn read_tagged(
+        &mut self,
+        module: &Module,
+        tags: &[Tag],
+        ty: &Type,
+        path: &str,
+    ) -> Result<Value> {
+        let ty = resolve(module, ty)?;
+        match tags.split_first() {
+            None => match &ty.kind {
+                TypeKind::Choice(alternatives) => self.read_choice(module, alternatives, path),
+                _ => unreachable!("only CHOICEs are untagged"),
+            },
+            Some((&tag, [])) => {
+                let header = self.read_tagged_header(tag, path)?;
+                self.read_contents(module, ty, header, path)
+            }
+            Some((&tag, tags)) => {
+                let header = self.read_tagged_header(tag, path)?;
+                self.constructed(header, |decoder| {
+                    decoder.read_tagged(module, tags, ty, path)
+                })
+            }
+        }
+    }
+
+    /// Reads the contents of a value of the given type, which isn't a
+    /// reference.
+    fn read_contents(
+        &mut self,
+        module: &Module,
+        ty: &Type,
+        header: Header,
+        path: &str,
+    ) -> Result<Value> {
+        match &ty.kind {
+            // Rec.ITU-T X.690 8.2, any octet other than 0 is true, while
+            // DER and CER require 0xFF
+            TypeKind::Boolean => match self.primitive(header)? {
+                [b] => {
+                    self.require(
+                        *b == 0 || *b == 0xff,
+                        path,
+                        "BOOLEAN true is not encoded as 0xFF",
+                    )?;
+                    Ok(Value::Bool(*b != 0))
+                }
+                _ => Err(Error::ExpectedBoolean),
+            },
+            TypeKind::Integer { .. } => self.read_integer(header, path).map(Value::Integer),
+            // Rec.ITU-T X.690 8.4, the number of the item as an INTEGER
+            TypeKind::Enumerated(items) => {
+                let number = self.read_integer(header, path)?;
+                enumerated_value(items, number, path)
+            }
+            // Rec.ITU-T X.690 8.19.2
+            TypeKind::ObjectIdentifier => {
+                let bytes = self.primitive(header)?;
+                let padded = bytes
+                    .iter()
+                    .enumerate()
+                    .any(|(index, &b)| b == 0x80 && (index == 0 || bytes[index - 1] & 0x80 == 0));
+                self.require(
+                    !padded,
+                    path,
+                    "object identifier arc is not encoded in the fewest octets",
+                )?;
+                object_identifier_arcs(bytes).map(Value::ObjectIdentifier)
+            }
+            // Rec.ITU-T X.690 8.7
+            TypeKind::OctetString => self.read_octets(header, path).map(Value::Bytes),
+            TypeKind::CharacterString(_) => String::from_utf8(self.read_octets(header, path)?)
+                .map(Value::Str)
+                .map_err(|_| Error::ExpectedString),
+            TypeKind::Sequence(components) => self.constructed(header, |decoder| {
+                decoder.read_sequence(module, components, ty.extensible, path)
+            }),
+            TypeKind::Set(components) => self.constructed(header, |decoder| {
+                decoder.read_set(module, components, ty.extensible, path)
+            }),
+            // the tag of a CHOICE is explicit, holding the chosen alternative
+            TypeKind::Choice(alternatives) => self.constructed(header, |decoder| {
+                decoder.read_choice(module, alternatives, path)
+            }),
+            // Rec.ITU-T X.690 8.10
+            TypeKind::SequenceOf(element) => self.constructed(header, |decoder| {
+                decoder.read_list(module, element, false, path)
+            }),
+            // Rec.ITU-T X.690 8.12, 11.6
+            TypeKind::SetOf(element) => self.constructed(header, |decoder| {
+                decoder.read_list(module, element, true, path)
+            }),
+            TypeKind::Reference(_) => unreachable!("references are resolved"),
+            _ => Err(unsupported_type(path)),
+        }
+    }
+
+    /// Rec.ITU-T X.690 8.3.2
+    fn read_integer(&mut self, header: Header, path: &str) -> Result<i128> {
+        let bytes = self.primitive(header)?;
+        self.require(
+            !matches!(bytes, [0, b, ..] if b & 0x80 == 0)
+                && !matches!(bytes, [0xff, b, ..] if b & 0x80 != 0),
+            path,
+            "INTEGER is not encoded in the fewest octets",
+        )?;
+        read_integer(bytes)
+    }
+
+    /// Rec.ITU-T X.690 9.2, 10.2
+    /// Reads the octets of an OCTET STRING or a character string, which DER
+    /// requires to have the primitive form and CER to be split into
+    /// segments of 1000 octets.
+    fn read_octets(&mut self, header: Header, path: &str) -> Result<Vec<u8>> {
+        if self.rules == Rules::Distinguished {
+            self.require(!header.constructed, path, "string has the constructed form")?;
+        }
+        let mut bytes = vec![];
+        let mut segments = vec![];
+        self.read_string(header, &mut bytes, &mut segments, path)?;
+        if self.rules == Rules::Canonical {
+            let (last, rest) = segments.split_last().unwrap_or((&0, &[]));
+            self.require(
+                *last <= CER_SEGMENT_LEN
+                    && header.constructed != rest.is_empty()
+                    && (rest.is_empty() || *last > 0)
+                    && rest.iter().all(|len| *len == CER_SEGMENT_LEN),
+                path,
+                "string is not split into segments of 1000 octets",
+            )?;
+        }
+
+        Ok(bytes)
+    }
+
+    /// Rec.ITU-T X.690 8.23.6
+    /// Appends the octets of a string to `bytes`, and the number
+    /// of octets in each primitive segment to `segments`. The constructed
+    /// form holds segments of the string, each of which is an OCTET STRING,
+    /// in either form, while CER only allows the primitive form.
+    fn read_string(
+        &mut self,
+        header: Header,
+        bytes: &mut Vec<u8>,
+        segments: &mut Vec<usize>,
+        path: &str,
+    ) -> Result<()> {
+        if !header.constructed {
+            let contents = self.primitive(header)?;
+            bytes.extend_from_slice(contents);
+            segments.push(contents.len());
+            return Ok(());
+        }
+
+        self.constructed(header, |decoder| {
+            while !decoder.at_end() {
+                let header = decoder.read_tagged_header(Tag::universal(4), path)?;
+                decoder.require(
+                    !header.constructed,
+                    path,
+                    "string segment has the constructed form",
+                )?;
+                decoder.read_string(header, bytes, segments, path)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Skips a value of a type this schema doesn't know of, such as an
+    /// extension addition from a later version of the type.
+    fn skip_value(&mut self, path: &str) -> Result<()> {
+        let header = self.read_header(path)?;
+        if header.constructed {
+            self.constructed(header, |decoder| {
+                while !decoder.at_end() {
+                    decoder.skip_value(path)?;
+                }
+                Ok(())
+            })
+        } else {
+            self.primitive(header).map(|_| ())
+        }
+    }
+
+    /// Reads the value of a component which is present. DER and CER require
+    /// components with their DEFAULT value to be left out.
+    fn read_component(
+        &mut self,
+        module: &Module,
+        component: &Component,
+        path: &str,
+    ) -> Result<Value> {
+        let path = component_path(path, &component.name);
+        let value = self.read_value(module, component.tag, &component.ty, &path)?;
+        if let Presence::Default(default) = &component.presence {
+            self.require(
+                value != Value::from(default),
+                &path,
+                "the DEFAULT value is encoded",
+            )?;
+        }
+
+        Ok(value)
+    }
+
+    /// Rec.ITU-T X.690 8.9
+    /// The components of a SEQUENCE are encoded in order. Absent components
+    /// are left out, so each OPTIONAL or DEFAULT component is present if the
+    /// next value has its tag. The components of an extensible SEQUENCE may
+    /// be followed by extension additions this schema doesn't know of, which
+    /// are skipped.
+    fn read_sequence(
+        &mut self,
+        module: &Module,
+        components: &[Component],
+        extensible: bool,
+        path: &str,
+    ) -> Result<Value> {
+        let mut values = vec![];
+        for component in components {
+            let present = !self.at_end() && {
+                let (class, number) = self.peek_tag()?;
+                starts_with(module, component.tag, &component.ty, class, number)
+            };
+
+            if present || component.presence == Presence::Required {
+                let value = self.read_component(module, component, path)?;
+                values.push((component.name.clone(), value));
+            } else if let Presence::Default(default) = &component.presence {
+                values.push((component.name.clone(), Value::from(default)));
+            }
+        }
+        while extensible && !self.at_end() {
+            self.skip_value(path)?;
+        }
+
+        Ok(Value::Seq(values))
+    }
+
+    /// Rec.ITU-T X.690 8.11, 10.3
+    /// The components of a SET may be encoded in any order, and are told
+    /// apart by their tags. DER and CER require the canonical order of the
+    /// tags. Values with tags this schema doesn't know of are skipped if the
+    /// SET is extensible.
+    fn read_set(
+        &mut self,
+        module: &Module,
+        components: &[Component],
+        extensible: bool,
+        path: &str,
+    ) -> Result<Value> {
+        let order = module.canonical_order(components);
+        let mut present: Vec<(&Component, Value)> = vec![];
+        while !self.at_end() {
+            let (class, number) = self.peek_tag()?;
+            let component = components
+                .iter()
+                .find(|component| starts_with(module, component.tag, &component.ty, class, number));
+            let component = match component {
+                None if extensible => {
+                    self.skip_value(path)?;
+                    continue;
+                }
+                component => component
+                    .filter(|component| {
+                        !present.iter().any(|(read, _)| read.name == component.name)
+                    })
+                    .ok_or(Error::UnexpectedTag { class, number })?,
+            };
+
+            let position = |component: &Component| {
+                order
+                    .iter()
+                    .position(|ordered| ordered.name == component.name)
+            };
+            self.require(
+                present
+                    .last()
+                    .is_none_or(|(last, _)| position(last) < position(component)),
+                path,
+                "SET components are not in canonical order",
+            )?;
+            let value = self.read_component(module, component, path)?;
+            present.push((component, value));
+        }
+
+        let mut values = vec![];
+        for component in components {
+            match present
+                .iter()
+                .position(|(read, _)| read.name == component.name)
+            {
+                Some(index) => values.push((component.name.clone(), present.remove(index).1)),
+                None => match &component.presence {
+                    Presence::Required => {
+                        return Err(invalid_value(
+                            path,
+                            format!("component `{}` is missing", component.name),
+                        ))
+                    }
+                    Presence::Optional => {}
+                    Presence::Default(default) => {
+                        values.push((component.name.clone(), Value::from(default)))
+                    }
+                },
+            }
+        }
+
+        Ok(Value::Seq(values))
+    }
+
+    /// Reads the elements of a SEQUENCE OF or, if `set`, a SET OF, whose
+    /// elements DER and CER require to be sorted by their encodings.
+    fn read_list(
+        &mut self,
+        module: &Module,
+        element: &Type,
+        set: bool,
+        path: &str,
+    ) -> Result<Value> {
+        let mut values = vec![];
+        let mut previous: Option<&[u8]> = None;
+        while !self.at_end() {
+            let start = self.input;
+            let value =
+                self.read_value(module, None, element, &element_path(path, values.len()))?;
+            values.push(value);
+
+            let encoding = &start[..start.len() - self.input.len()];
+            if set {
+                self.require(
+                    previous.is_none_or(|previous| {
+                        set_of_order(previous, encoding) != Ordering::Greater
+                    }),
+                    path,
+                    "SET OF elements are not sorted",
+                )?;
+                previous = Some(encoding);
+            }
+        }
+
+        Ok(Value::List(values))
+    }
+
+    /// Rec.ITU-T X.690 8.13
+    /// A CHOICE is encoded as its chosen alternative, which is told by its
+    /// tag.
+    fn read_choice(
+        &mut self,
+        module: &Module,
+        alternatives: &[Component],
+        path: &str,
+    ) -> Result<Value> {
+        let (class, number) = self.peek_tag()?;
+        let alternative = alternatives
+            .iter()
+            .find(|alternative| {
+                starts_with(module, alternative.tag, &alternative.ty, class, number)
+            })
+            .ok_or(Error::InvalidChoiceTag)?;
+
+        let path = component_path(path, &alternative.name);
+        let value = self.read_value(module, alternative.tag, &alternative.ty, &path)?;
+        Ok(Value::Choice(alternative.name.clone(), Box::new(value)))
+    }
+}
+
+/// Writes values as directed by a schema.
+struct BerEncoder<'a, W> {
+    module: &'a Module,
+    rules: Rules,
+    options: BerOptions,
+    writer: W,
+    /// The contents of the enclosing constructed values with the definite
+    /// length form, innermost last, which are written once their length is
+    /// known.
+    buffers: Vec<Vec<u8>>,
+}
+
+impl<'a, W: io::Write> BerEncoder<'a, W> {
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        match self.buffers.last_mut() {
+            Some(buffer) => {
+                buffer.extend_from_slice(bytes);
+                Ok(())
+            }
+            None => self
+                .writer
+                .write_all(bytes)
+                .map_err(|error| Error::Io(error.to_string())),
+        }
+    }
+
+    /// Returns what the given function writes, rather than writing it.
+    fn buffered<F>(&mut self, f: F) -> Result<Vec<u8>>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        self.buffers.push(vec![]);
+        let result = f(self);
+        let bytes = self.buffers.pop().unwrap_or_default();
+        result.map(|_| bytes)
+    }
+
+    /// Rec.ITU-T X.690 8.1.2
+    fn write_identifier(&mut self, tag: Tag, constructed: bool) -> Result<()> {
+        let first = tag_class_bits(tag.class) << 6 | if constructed { 0x20 } else { 0 };
+        if tag.number < 0x1f {
+            self.write(&[first | tag.number as u8])
+        } else {
+            self.write(&[first | 0x1f])?;
+            self.write(&base128_octets(u64::from(tag.number)))
+        }
+    }
+
+    /// Rec.ITU-T X.690 8.1.3, in the definite form with as few octets as
+    /// possible.
+    fn write_length(&mut self, len: usize) -> Result<()> {
+        if len < 0x80 {
+            self.write(&[len as u8])
+        } else {
+            let octets = unsigned_octets(len as u128);
+            self.write(&[0x80 | octets.len() as u8])?;
+            self.write(&octets)
+        }
+    }
+
+    fn write_primitive(&mut self, tag: Tag, contents: &[u8]) -> Result<()> {
+        self.write_identifier(tag, false)?;
+        self.write_length(contents.len())?;
+        self.write(contents)
+    }
+
+    /// Writes a constructed value with the given tag, whose contents are
+    /// written by the given function.
+    fn write_constructed<F>(&mut self, tag: Tag, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        self.write_identifier(tag, true)?;
+        if self.options.indefinite_length || self.rules == Rules::Canonical {
+            self.write(&[0x80])?;
+            f(self)?;
+            // the end-of-contents marker
+            return self.write(&[0, 0]);
+        }
+
+        let contents = self.buffered(f)?;
+        self.write_length(contents.len())?;
+        self.write(&contents)
+    }
+
+    /// Writes a value of the given type, as a component with the given tag.
+    fn write_value(
+        &mut self,
+        tag: Option<Tag>,
+        ty: &Type,
+        value: &Value,
+        path: &str,
+    ) -> Result<()> {
+        check_constraint(self.module, ty, value, path)?;
+        let tags = encoded_tags(self.module, tag, ty);
+        self.write_tagged(&tags, ty, value, path)
+    }
+
+    fn write_tagged(&mut self, tags: &[Tag], ty: &Type, value: &Value, path: &str) -> Result<()> {
+        let kind = &resolve(self.module, ty)?.kind;
+        match (tags.split_first(), kind, value) {
+            (None, TypeKind::Choice(alternatives), Value::Choice(name, value)) => {
+                self.write_choice(alternatives, name, value, path)
+            }
+            (Some((&tag, [])), _, _) => self.write_contents(tag, kind, value, path),
+            (Some((&tag, tags)), _, _) => {
+                self.write_constructed(tag, |encoder| encoder.write_tagged(tags, ty, value, path))
+            }
+            (None, kind, _) => Err(invalid_value(
+                path,
+                format!("expected a value of type {}", kind_name(kind)),
+            )),
+        }
+    }
+
+    /// Rec.ITU-T X.690 8.7, 9.2
+    /// Writes the octets of an OCTET STRING or a character string, which
+    /// CER splits into segments of 1000 octets.
+    fn write_octets(&mut self, tag: Tag, octets: &[u8]) -> Result<()> {
+        if self.rules == Rules::Canonical && octets.len() > CER_SEGMENT_LEN {
+            self.write_constructed(tag, |encoder| {
+                octets
+                    .chunks(CER_SEGMENT_LEN)
+                    .try_for_each(|segment| encoder.write_primitive(Tag::universal(4), segment))
+            })
+        } else {
+            self.write_primitive(tag, octets)
+        }
+    }
+
+    /// Writes a value of the given kind of type with the given tag.
+    fn write_contents(
+        &mut self,
+        tag: Tag,
+        kind: &TypeKind,
+        value: &Value,
+        path: &str,
+    ) -> Result<()> {
+        match (kind, value) {
+            // Rec.ITU-T X.690 8.2, 11.1
+            (TypeKind::Boolean, Value::Bool(value)) => {
+                self.write_primitive(tag, &[if *value { 0xff } else { 0 }])
+            }
+            (TypeKind::Integer { .. }, Value::Integer(value)) => {
+                self.write_primitive(tag, &signed_octets(*value))
+            }
+            (TypeKind::ObjectIdentifier, Value::ObjectIdentifier(arcs)) => {
+                let octets = object_identifier_octets(arcs)
+                    .ok_or_else(|| invalid_value(path, "invalid object identifier"))?;
+                self.write_primitive(tag, &octets)
+            }
+            // Rec.ITU-T X.690 8.4
+            (TypeKind::Enumerated(items), Value::Enum(name)) => {
+                let item = enumeration_item(items, name, path)?;
+                self.write_primitive(tag, &signed_octets(item.number))
+            }
+            (TypeKind::OctetString, value) if octets(value).is_some() => {
+                self.write_octets(tag, &octets(value).unwrap_or_default())
+            }
+            (TypeKind::CharacterString(_), Value::Str(value)) => {
+                self.write_octets(tag, value.as_bytes())
+            }
+            (TypeKind::Sequence(components), Value::Seq(values)) => {
+                let components: Vec<&Component> = components.iter().collect();
+                self.write_constructed(tag, |encoder| {
+                    encoder.write_components(&components, values, path)
+                })
+            }
+            // Rec.ITU-T X.690 10.3, the canonical order suits BER too
+            (TypeKind::Set(components), Value::Seq(values)) => {
+                let components = self.module.canonical_order(components);
+                self.write_constructed(tag, |encoder| {
+                    encoder.write_components(&components, values, path)
+                })
+            }
+            (TypeKind::Choice(alternatives), Value::Choice(name, value)) => self
+                .write_constructed(tag, |encoder| {
+                    encoder.write_choice(alternatives, name, value, path)
+                }),
+            (TypeKind::SequenceOf(element), Value::List(values)) => {
+                self.write_constructed(tag, |encoder| {
+                    for (index, value) in values.iter().enumerate() {
+                        encoder.write_value(None, element, value, &element_path(path, index))?;
+                    }
+                    Ok(())
+                })
+            }
+            // Rec.ITU-T X.690 11.6, DER and CER sort the elements by their
+            // encodings, while BER keeps them in order
+            (TypeKind::SetOf(element), Value::List(values)) if self.rules != Rules::Basic => {
+                let mut encodings = values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, value)| {
+                        self.buffered(|encoder| {
+                            encoder.write_value(None, element, value, &element_path(path, index))
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                encodings.sort_by(|a, b| set_of_order(a, b));
+
+                self.write_constructed(tag, |encoder| {
+                    encodings
+                        .iter()
+                        .try_for_each(|encoding| encoder.write(encoding))
+                })
+            }
+            (TypeKind::SetOf(element), Value::List(values)) => {
+                self.write_constructed(tag, |encoder| {
+                    for (index, value) in values.iter().enumerate() {
+                        encoder.write_value(None, element, value, &element_path(path, index))?;
+                    }
+                    Ok(())
+                })
+            }
+            (kind, _) => Err(invalid_value(
+                path,
+                format!("expected a value of type {}", kind_name(kind)),
+            )),
+        }
+    }
+
+    /// Writes the components of a SEQUENCE or SET in the given order,
+    /// leaving out those which are absent or have their DEFAULT value.
+    fn write_components(
+        &mut self,
+        components: &[&Component],
+        values: &[(String, Value)],
+        path: &str,
+    ) -> Result<()> {
+        for (component, value) in component_values(components, values, path)? {
+            if let Some(value) = value {
+                let path = component_path(path, &component.name);
+                self.write_value(component.tag, &component.ty, value, &path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_choice(
+        &mut self,
+        alternatives: &[Component],
+        name: &str,
+        value: &Value,
+        path: &str,
+    ) -> Result<()> {
+        let alternative = alternatives
+            .iter()
+            .find(|alternative| alternative.name == name)
+            .ok_or_else(|| invalid_value(path, format!("unknown alternative `{}`", name)))?;
+
+        self.write_value(
+            alternative.tag,
+            &alternative.ty,
+            value,
+            &component_path(path, name),
+        )
+    }
+}
+
+/// Rec.ITU-T X.690 8.3
+/// An integer is a two's complement number in one or more octets.
+pub(crate) fn read_integer(bytes: &[u8]) -> Result<i128> {
+    // the sign extends into the octets before the first one
+    let sign = match bytes.first() {
+        Some(&first) if first & 0x80 != 0 => -1,
+        Some(_) => 0,
+        None => return Err(Error::ExpectedInteger),
+    };
+
+    bytes.iter().try_fold(sign, |value: i128, &b| {
+        value
+            .checked_mul(256)
+            .and_then(|value| value.checked_add(i128::from(b)))
+            .ok_or(Error::IntegerOverflow)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        encode, non_canonical, value_from_ber_bytes, value_from_cer_bytes, value_from_der_bytes,
+        value_to_ber_bytes, value_to_cer_bytes, value_to_der_bytes, BerOptions, Rules,
+    };
+    use crate::value::helpers::{integer, seq, string};
+    use crate::{Error, Value, Violation};
+    use asn1_schema::{Module, TagClass};
+
+    #[test]
+    fn geo() {
+        let module = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+        let point = seq(vec![("x", integer(-2)), ("y", integer(300))]);
+
+        let definite = [0x30, 7, 0x02, 1, 0xfe, 0x02, 2, 0x01, 0x2c];
+        assert_eq!(
+            point,
+            value_from_ber_bytes(&module, "Point", &definite).unwrap()
+        );
+
+        // the indefinite form ends with an end-of-contents marker, and the
+        // long form of a length may have leading zeros
+        let indefinite = [0x30, 0x80, 0x02, 0x81, 1, 0xfe, 0x02, 2, 0x01, 0x2c, 0, 0];
+        assert_eq!(
+            point,
+            value_from_ber_bytes(&module, "Point", &indefinite).unwrap()
+        );
+
+        let line = [
+            0x30, 0x80, 0x30, 6, 0x02, 1, 1, 0x02, 1, 2, 0x30, 0x80, 0x02, 1, 3, 0x02, 1, 4, 0, 0,
+            0, 0,
+        ];
+        assert_eq!(
+            seq(vec![
+                ("p1", seq(vec![("x", integer(1)), ("y", integer(2))])),
+                ("p2", seq(vec![("x", integer(3)), ("y", integer(4))])),
+            ]),
+            value_from_ber_bytes(&module, "Line", &line).unwrap()
+        );
+    }
+
+    #[test]
+    fn strings() {
+        let module = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+
+        // the constructed form holds segments, which may be constructed too
+        let note = [
+            0x2c, 0x80, 0x04, 2, b'h', b'e', 0x24, 6, 0x04, 1, b'l', 0x04, 1, b'l', 0x04, 1, b'o',
+            0, 0,
+        ];
+        assert_eq!(
+            Value::Choice("note".into(), Box::new(string("hello"))),
+            value_from_ber_bytes(&module, "Message", &note).unwrap()
+        );
+        assert_eq!(
+            Err(Error::UnexpectedTag {
+                class: TagClass::Universal,
+                number: 12,
+            }),
+            value_from_ber_bytes(&module, "Message", &[0x2c, 3, 0x0c, 1, b'h'])
+        );
+    }
+
+    #[test]
+    fn tags() {
+        let module = Module::parse(
+            "Misc DEFINITIONS IMPLICIT TAGS ::= BEGIN
+            Id ::= [APPLICATION 40] OBJECT IDENTIFIER
+            Flag ::= [5] EXPLICIT BOOLEAN
+            Reading ::= CHOICE { id Id, flag [1] Flag, level [PRIVATE 2] INTEGER }
+            Record ::= SEQUENCE {
+                reading Reading,
+                note [0] UTF8String OPTIONAL,
+                count INTEGER DEFAULT 1,
+                flag Flag OPTIONAL
+            }
+            END",
+        )
+        .unwrap();
+
+        // high tag numbers are written in subsequent octets
+        assert_eq!(
+            Value::Choice(
+                "id".into(),
+                Box::new(Value::ObjectIdentifier(vec![1, 2, 840]))
+            ),
+            value_from_ber_bytes(&module, "Reading", &[0x5f, 40, 3, 42, 0x86, 0x48]).unwrap()
+        );
+        // the implicit [1] replaces the explicit [5], which holds the BOOLEAN
+        assert_eq!(
+            Value::Choice("flag".into(), Box::new(Value::Bool(true))),
+            value_from_ber_bytes(&module, "Reading", &[0xa1, 3, 0x01, 1, 0x01]).unwrap()
+        );
+
+        // the untagged CHOICE is encoded as its alternative, and the absent
+        // components are told apart by their tags
+        let record = [0x30, 8, 0xc2, 1, 0xfb, 0xa5, 3, 0x01, 1, 0];
+        assert_eq!(
+            seq(vec![
+                (
+                    "reading",
+                    Value::Choice("level".into(), Box::new(integer(-5)))
+                ),
+                ("count", integer(1)),
+                ("flag", Value::Bool(false)),
+            ]),
+            value_from_ber_bytes(&module, "Record", &record).unwrap()
+        );
+        assert_eq!(
+            Err(Error::InvalidChoiceTag),
+            value_from_ber_bytes(&module, "Record", &[0x30, 3, 0x80, 1, b'a'])
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let module = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+
+        assert_eq!(
+            Err(Error::UnexpectedTag {
+                class: TagClass::ContextSpecific,
+                number: 0,
+            }),
+            value_from_ber_bytes(&module, "Point", &[0xa0, 0])
+        );
+        // a component is missing
+        assert_eq!(
+            Err(Error::Eof),
+            value_from_ber_bytes(&module, "Point", &[0x30, 3, 0x02, 1, 1])
+        );
+        // an extra component
+        assert_eq!(
+            Err(Error::UnexpectedTag {
+                class: TagClass::Universal,
+                number: 1,
+            }),
+            value_from_ber_bytes(
+                &module,
+                "Point",
+                &[0x30, 9, 0x02, 1, 1, 0x02, 1, 2, 0x01, 1, 0]
+            )
+        );
+        assert_eq!(
+            Err(Error::UnexpectedForm),
+            value_from_ber_bytes(&module, "Point", &[0x10, 0])
+        );
+        assert_eq!(
+            Err(Error::InvalidLength),
+            value_from_ber_bytes(&module, "Point", &[0x30, 0x80, 0x02, 0x80, 1, 0, 0, 0, 0])
+        );
+        assert_eq!(
+            Err(Error::ExpectedInteger),
+            value_from_ber_bytes(&module, "Point", &[0x30, 4, 0x02, 0, 0x02, 0])
+        );
+        assert_eq!(
+            Err(Error::TrailingCharacters),
+            value_from_ber_bytes(&module, "Point", &[0x30, 6, 0x02, 1, 1, 0x02, 1, 2, 0])
+        );
+        assert_eq!(
+            Err(Error::Eof),
+            value_from_ber_bytes(&module, "Point", &[0x30, 0x80, 0x02, 1, 1, 0x02, 1, 2])
+        );
+    }
+
+    #[test]
+    fn files() {
+        let module = Module::parse(include_str!("../../test-asn/files.asn")).unwrap();
+
+        // an ENUMERATED is encoded like an INTEGER holding the number of the
+        // item, and the kind is left out as it has its DEFAULT value
+        let file = seq(vec![
+            ("name", string("a")),
+            ("kind", Value::Enum("file".into())),
+            ("digest", Value::Bytes(vec![1, 2, 3, 4])),
+            ("contents", Value::Bytes(vec![0xff])),
+        ]);
+        let bytes = [0x30, 12, 0x80, 1, b'a', 0x82, 4, 1, 2, 3, 4, 0x83, 1, 0xff];
+        assert_eq!(
+            bytes.to_vec(),
+            value_to_der_bytes(&module, "File", &file).unwrap()
+        );
+        assert_eq!(file, value_from_der_bytes(&module, "File", &bytes).unwrap());
+        assert_eq!(
+            Value::Enum("link".into()),
+            value_from_ber_bytes(&module, "Kind", &[0x0a, 1, 5]).unwrap()
+        );
+
+        // an OCTET STRING may be split into segments, except in DER
+        let digest = [0x24, 8, 0x04, 2, 1, 2, 0x04, 2, 3, 4];
+        assert_eq!(
+            Value::Bytes(vec![1, 2, 3, 4]),
+            value_from_ber_bytes(&module, "Digest", &digest).unwrap()
+        );
+        assert_eq!(
+            Err(non_canonical("", "string has the constructed form")),
+            value_from_der_bytes(&module, "Digest", &digest)
+        );
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: "unknown item number 3".into(),
+            })),
+            value_from_ber_bytes(&module, "Kind", &[0x0a, 1, 3])
+        );
+    }
+
+    #[test]
+    fn decode_errors() {
+        let module = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+        let invalid = |message: &str| {
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: message.into(),
+            }))
+        };
+
+        for decode in [
+            value_from_ber_bytes,
+            value_from_der_bytes,
+            value_from_cer_bytes,
+        ] {
+            assert_eq!(
+                invalid("200 is out of the range 0..100"),
+                decode(&module, "Percent", &[0x02, 2, 0, 200])
+            );
+            assert_eq!(
+                invalid("size 5 is out of the range 2..4"),
+                decode(&module, "Code", &[0x16, 5, b'A', b'B', b'C', b'D', b'E'])
+            );
+            // not in the alphabet of IA5String
+            assert_eq!(
+                invalid("character 'é' is not permitted"),
+                decode(&module, "Code", &[0x16, 3, b'A', 0xc3, 0xa9])
+            );
+        }
+        assert_eq!(
+            invalid("size 0 is out of the range 1..3"),
+            value_from_ber_bytes(&module, "Readings", &[0x30, 0])
+        );
+    }
+
+    #[test]
+    fn recursion_limit() {
+        let module = Module::parse(include_str!("../../test-asn/recursive.asn")).unwrap();
+
+        // neg(neg(neg(lit 1))), with each tag holding the next alternative
+        let mut expr = vec![0x80, 1, 1];
+        for _ in 0..3 {
+            let mut neg = vec![0xa1, expr.len() as u8];
+            neg.extend(expr);
+            expr = neg;
+        }
+        assert!(super::decode(
+            &module,
+            &module.get_type("Expr").unwrap().ty,
+            &expr,
+            3,
+            Rules::Basic
+        )
+        .is_ok());
+        assert_eq!(
+            Err(Error::RecursionLimitExceeded),
+            super::decode(
+                &module,
+                &module.get_type("Expr").unwrap().ty,
+                &expr,
+                2,
+                Rules::Basic
+            )
+        );
+
+        // nested string segments count too
+        let mut segments = vec![0x04, 0];
+        for _ in 0..4 {
+            let mut segment = vec![0x24, segments.len() as u8];
+            segment.extend(segments);
+            segments = segment;
+        }
+        segments[0] = 0x2c;
+        let note = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+        assert_eq!(
+            Err(Error::RecursionLimitExceeded),
+            super::decode(
+                &note,
+                &note.get_type("Message").unwrap().ty,
+                &segments,
+                3,
+                Rules::Basic
+            )
+        );
+    }
+    /// Checks that the value decodes from the bytes, and encodes to them.
+    fn round_trip(module: &Module, type_name: &str, bytes: &[u8]) {
+        let value = value_from_ber_bytes(module, type_name, bytes).unwrap();
+        assert_eq!(
+            bytes,
+            &value_to_ber_bytes(module, type_name, &value).unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn encode_definite() {
+        let geo = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+        round_trip(
+            &geo,
+            "Point",
+            &[0x30, 7, 0x02, 1, 0xfe, 0x02, 2, 0x01, 0x2c],
+        );
+
+        let misc = Module::parse(
+            "Misc DEFINITIONS IMPLICIT TAGS ::= BEGIN
+            Id ::= [APPLICATION 40] OBJECT IDENTIFIER
+            Flag ::= [5] EXPLICIT BOOLEAN
+            Text ::= UTF8String
+            Reading ::= CHOICE { id Id, flag [1] Flag, level [PRIVATE 2] INTEGER }
+            Record ::= SEQUENCE {
+                reading Reading,
+                readings SEQUENCE OF Reading,
+                count INTEGER DEFAULT 1
+            }
+            END",
+        )
+        .unwrap();
+        // lengths of 128 and above have the long form
+        let mut text = vec![0x0c, 0x81, 200];
+        text.extend(&[b'a'; 200]);
+        round_trip(&misc, "Text", &text);
+        round_trip(&misc, "Reading", &[0x5f, 40, 3, 42, 0x86, 0x48]);
+        round_trip(&misc, "Reading", &[0xa1, 3, 0x01, 1, 0xff]);
+        round_trip(
+            &misc,
+            "Record",
+            &[
+                0x30, 13, 0xc2, 1, 0xfb, 0x30, 5, 0xa1, 3, 0x01, 1, 0, 0x02, 1, 2,
+            ],
+        );
+    }
+
+    #[test]
+    fn encode_indefinite() {
+        let module = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+        let reading = Value::Seq(vec![
+            ("code".into(), string("AB")),
+            ("label".into(), string("X")),
+            ("level".into(), integer(50)),
+        ]);
+        let message = Value::Choice(
+            "report".into(),
+            Box::new(seq(vec![
+                ("station", string("1234")),
+                ("readings", Value::List(vec![reading])),
+            ])),
+        );
+
+        let mut bytes = vec![];
+        let options = BerOptions {
+            indefinite_length: true,
+        };
+        let ty = &module.get_type("Message").unwrap().ty;
+        encode(&module, ty, &message, &mut bytes, Rules::Basic, options).unwrap();
+        assert_eq!(
+            vec![
+                0x30, 0x80, 0x12, 4, b'1', b'2', b'3', b'4', 0x30, 0x80, 0x30, 0x80, 0x16, 2, b'A',
+                b'B', 0x13, 1, b'X', 0x02, 1, 50, 0, 0, 0, 0, 0, 0,
+            ],
+            bytes
+        );
+        assert_eq!(
+            message,
+            value_from_ber_bytes(&module, "Message", &bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn encode_errors() {
+        let module = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "p2.y".into(),
+                message: "expected a value of type INTEGER".into(),
+            })),
+            value_to_ber_bytes(
+                &module,
+                "Line",
+                &seq(vec![
+                    ("p1", seq(vec![("x", integer(1)), ("y", integer(2))])),
+                    ("p2", seq(vec![("x", integer(1)), ("y", Value::Bool(true))])),
+                ])
+            )
+        );
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: "component `y` is missing".into(),
+            })),
+            value_to_ber_bytes(&module, "Point", &seq(vec![("x", integer(1))]))
+        );
+    }
+
+    fn attribute(ty: i128, value: &str) -> Value {
+        seq(vec![("type", integer(ty)), ("value", string(value))])
+    }
+
+    #[test]
+    fn extensions() {
+        let module = Module::parse(include_str!("../../test-asn/extensions.asn")).unwrap();
+
+        let setup = seq(vec![("id", integer(1)), ("level", integer(300))]);
+        let bytes = [0x30, 7, 0x80, 1, 1, 0x82, 2, 1, 0x2c];
+        round_trip(&module, "Setup", &bytes);
+        assert_eq!(
+            setup,
+            value_from_ber_bytes(&module, "Setup", &bytes).unwrap()
+        );
+
+        // additions from a later version are skipped
+        let later = [
+            0x30, 12, 0x80, 1, 1, 0x82, 2, 1, 0x2c, 0xa5, 3, 0x04, 1, 0xff,
+        ];
+        assert_eq!(
+            setup,
+            value_from_ber_bytes(&module, "Setup", &later).unwrap()
+        );
+
+        round_trip(&module, "Procedure", &[0x82, 1, 5]);
+        assert_eq!(
+            Err(Error::InvalidChoiceTag),
+            value_from_ber_bytes(&module, "Procedure", &[0x83, 1, 5])
+        );
+    }
+
+    #[test]
+    fn sets() {
+        let module = Module::parse(include_str!("../../test-asn/sets.asn")).unwrap();
+        let entry = |name, labels| {
+            seq(vec![
+                ("name", Value::List(name)),
+                ("serial", integer(5)),
+                ("active", Value::Bool(true)),
+                ("labels", Value::List(labels)),
+            ])
+        };
+
+        // DER puts the components in the canonical order of their tags, and
+        // the elements of each SET OF in the order of their encodings
+        let der = [
+            0x31, 29, 0x02, 1, 5, 0xa0, 6, 0x16, 1, b'x', 0x16, 1, b'y', 0xa2, 16, 0x30, 6, 0x02,
+            1, 1, 0x0c, 1, b'a', 0x30, 6, 0x02, 1, 2, 0x0c, 1, b'b',
+        ];
+        let sorted = entry(
+            vec![attribute(1, "a"), attribute(2, "b")],
+            vec![string("x"), string("y")],
+        );
+        let unsorted = entry(
+            vec![attribute(2, "b"), attribute(1, "a")],
+            vec![string("y"), string("x")],
+        );
+        assert_eq!(
+            sorted,
+            value_from_der_bytes(&module, "Entry", &der).unwrap()
+        );
+        assert_eq!(
+            sorted,
+            value_from_ber_bytes(&module, "Entry", &der).unwrap()
+        );
+        assert_eq!(
+            der,
+            &value_to_der_bytes(&module, "Entry", &unsorted).unwrap()[..]
+        );
+
+        // BER keeps the elements of a SET OF in order
+        let ber = value_to_ber_bytes(&module, "Entry", &unsorted).unwrap();
+        assert_eq!(&der[..5], &ber[..5]);
+        assert_eq!([0x16, 1, b'y', 0x16, 1, b'x'], ber[7..13]);
+        assert_eq!(
+            unsorted,
+            value_from_ber_bytes(&module, "Entry", &ber).unwrap()
+        );
+
+        // while BER allows the components in any order
+        let shuffled = [
+            0x31, 16, 0xa2, 8, 0x30, 6, 0x02, 1, 1, 0x0c, 1, b'a', 0x01, 1, 0, 0x02, 1, 5,
+        ];
+        assert_eq!(
+            seq(vec![
+                ("name", Value::List(vec![attribute(1, "a")])),
+                ("serial", integer(5)),
+                ("active", Value::Bool(false)),
+            ]),
+            value_from_ber_bytes(&module, "Entry", &shuffled).unwrap()
+        );
+        assert_eq!(
+            Err(non_canonical(
+                "",
+                "SET components are not in canonical order"
+            )),
+            value_from_der_bytes(&module, "Entry", &shuffled)
+        );
+
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: "component `name` is missing".into(),
+            })),
+            value_from_ber_bytes(&module, "Entry", &[0x31, 3, 0x02, 1, 5])
+        );
+        assert_eq!(
+            Err(Error::UnexpectedTag {
+                class: TagClass::Universal,
+                number: 2,
+            }),
+            value_from_ber_bytes(&module, "Entry", &[0x31, 6, 0x02, 1, 5, 0x02, 1, 6])
+        );
+    }
+
+    #[test]
+    fn der() {
+        let geo = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+        let point = [0x30, 6, 0x02, 1, 1, 0x02, 1, 2];
+        assert!(value_from_der_bytes(&geo, "Point", &point).is_ok());
+
+        let rejected: &[(&[u8], Error)] = &[
+            (
+                &[0x3f, 0x10, 6, 0x02, 1, 1, 0x02, 1, 2],
+                non_canonical("", "tag is not encoded in the fewest octets"),
+            ),
+            (
+                &[0x30, 0x80, 0x02, 1, 1, 0x02, 1, 2, 0, 0],
+                non_canonical("", "length has the indefinite form"),
+            ),
+            (
+                &[0x30, 0x81, 6, 0x02, 1, 1, 0x02, 1, 2],
+                non_canonical("", "length is not encoded in the fewest octets"),
+            ),
+            (
+                &[0x30, 7, 0x02, 2, 0, 1, 0x02, 1, 2],
+                non_canonical("x", "INTEGER is not encoded in the fewest octets"),
+            ),
+            (
+                &[0x30, 7, 0x02, 1, 1, 0x02, 2, 0xff, 0x80],
+                non_canonical("y", "INTEGER is not encoded in the fewest octets"),
+            ),
+        ];
+        for (bytes, error) in rejected {
+            assert!(value_from_ber_bytes(&geo, "Point", bytes).is_ok());
+            assert_eq!(
+                Err(error.clone()),
+                value_from_der_bytes(&geo, "Point", bytes)
+            );
+        }
+
+        let misc = Module::parse(
+            "Misc DEFINITIONS IMPLICIT TAGS ::= BEGIN
+            Id ::= [APPLICATION 40] OBJECT IDENTIFIER
+            Text ::= UTF8String
+            END",
+        )
+        .unwrap();
+        assert_eq!(
+            Err(non_canonical(
+                "",
+                "object identifier arc is not encoded in the fewest octets"
+            )),
+            value_from_der_bytes(&misc, "Id", &[0x5f, 40, 3, 42, 0x80, 1])
+        );
+        assert_eq!(
+            Err(non_canonical("", "string has the constructed form")),
+            value_from_der_bytes(&misc, "Text", &[0x2c, 3, 0x04, 1, b'a'])
+        );
+
+        let sets = Module::parse(include_str!("../../test-asn/sets.asn")).unwrap();
+        let entry = |active: u8| {
+            vec![
+                0x31, 16, 0x01, 1, active, 0x02, 1, 5, 0xa2, 8, 0x30, 6, 0x02, 1, 1, 0x0c, 1, b'a',
+            ]
+        };
+        assert_eq!(
+            Err(non_canonical(
+                "active",
+                "BOOLEAN true is not encoded as 0xFF"
+            )),
+            value_from_der_bytes(&sets, "Entry", &entry(1))
+        );
+        assert_eq!(
+            Err(non_canonical("active", "the DEFAULT value is encoded")),
+            value_from_der_bytes(&sets, "Entry", &entry(0xff))
+        );
+        assert!(value_from_der_bytes(&sets, "Entry", &entry(0)).is_ok());
+        assert_eq!(
+            Err(non_canonical("labels", "SET OF elements are not sorted")),
+            value_from_der_bytes(
+                &sets,
+                "Entry",
+                &[
+                    0x31, 21, 0x02, 1, 5, 0xa0, 6, 0x16, 1, b'y', 0x16, 1, b'x', 0xa2, 8, 0x30, 6,
+                    0x02, 1, 1, 0x0c, 1, b'a',
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn cer() {
+        let geo = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+        let point = [0x30, 0x80, 0x02, 1, 1, 0x02, 1, 2, 0, 0];
+        let value = value_from_cer_bytes(&geo, "Point", &point).unwrap();
+        assert_eq!(
+            point,
+            &value_to_cer_bytes(&geo, "Point", &value).unwrap()[..]
+        );
+        assert_eq!(
+            Err(non_canonical("", "length has the definite form")),
+            value_from_cer_bytes(&geo, "Point", &[0x30, 6, 0x02, 1, 1, 0x02, 1, 2])
+        );
+        assert_eq!(
+            Err(non_canonical(
+                "x",
+                "length is not encoded in the fewest octets"
+            )),
+            value_from_cer_bytes(&geo, "Point", &[0x30, 0x80, 0x02, 0x81, 1, 1, 0, 0])
+        );
+
+        let misc = Module::parse(
+            "Misc DEFINITIONS IMPLICIT TAGS ::= BEGIN
+            Text ::= UTF8String
+            END",
+        )
+        .unwrap();
+        // strings of up to 1000 octets have the primitive form
+        let mut short = vec![0x0c, 0x82, 0x03, 0xe8];
+        short.extend(&[b'a'; 1000]);
+        let value = value_from_cer_bytes(&misc, "Text", &short).unwrap();
+        assert_eq!(short, value_to_cer_bytes(&misc, "Text", &value).unwrap());
+
+        // and longer ones are split into segments of 1000 octets
+        let mut long = vec![0x2c, 0x80, 0x04, 0x82, 0x03, 0xe8];
+        long.extend(&[b'a'; 1000]);
+        long.extend(&[0x04, 1, b'b', 0, 0]);
+        let value = value_from_cer_bytes(&misc, "Text", &long).unwrap();
+        assert_eq!(long, value_to_cer_bytes(&misc, "Text", &value).unwrap());
+        // which BER leaves in the primitive form
+        assert_eq!(
+            [0x0c, 0x82, 0x03, 0xe9],
+            value_to_ber_bytes(&misc, "Text", &value).unwrap()[..4]
+        );
+
+        let mut unsplit = vec![0x0c, 0x82, 0x03, 0xe9];
+        unsplit.extend(&[b'a'; 1001]);
+        let rejected: &[(&[u8], Error)] = &[
+            (
+                &unsplit,
+                non_canonical("", "string is not split into segments of 1000 octets"),
+            ),
+            (
+                &[0x2c, 0x80, 0x04, 1, b'a', 0, 0],
+                non_canonical("", "string is not split into segments of 1000 octets"),
+            ),
+            (
+                &[0x2c, 0x80, 0x24, 0x80, 0x04, 1, b'a', 0, 0, 0, 0],
+                non_canonical("", "string segment has the constructed form"),
+            ),
+        ];
+        for (bytes, error) in rejected {
+            assert!(value_from_ber_bytes(&misc, "Text", bytes).is_ok());
+            assert_eq!(
+                Err(error.clone()),
+                value_from_cer_bytes(&misc, "Text", bytes)
+            );
+        }
+
+        // SETs and SET OFs are in the same order as in DER
+        let sets = Module::parse(include_str!("../../test-asn/sets.asn")).unwrap();
+        let entry = seq(vec![
+            (
+                "name",
+                Value::List(vec![attribute(2, "b"), attribute(1, "a")]),
+            ),
+            ("serial", integer(5)),
+            ("active", Value::Bool(true)),
+            ("labels", Value::List(vec![string("y"), string("x")])),
+        ]);
+        assert_eq!(
+            vec![
+                0x31, 0x80, 0x02, 1, 5, 0xa0, 0x80, 0x16, 1, b'x', 0x16, 1, b'y', 0, 0, 0xa2, 0x80,
+                0x30, 0x80, 0x02, 1, 1, 0x0c, 1, b'a', 0, 0, 0x30, 0x80, 0x02, 1, 2, 0x0c, 1, b'b',
+                0, 0, 0, 0, 0, 0,
+            ],
+            value_to_cer_bytes(&sets, "Entry", &entry).unwrap()
+        );
+    }
+
+    #[test]
+    fn set_of_order() {
+        use std::cmp::Ordering;
+
+        // the shorter encoding is padded with zeros
+        assert_eq!(Ordering::Less, super::set_of_order(&[1, 2], &[1, 2, 1]));
+        assert_eq!(Ordering::Equal, super::set_of_order(&[1, 2], &[1, 2, 0]));
+        assert_eq!(Ordering::Greater, super::set_of_order(&[2], &[1, 0xff]));
+    }
+}
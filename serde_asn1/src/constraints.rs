@@ -0,0 +1,182 @@
+//! Checking the constraints of ASN.1 types. The functions in this module are
+//! used by the `Validate` impls generated by `asn1_codegen`.
+
+use std::fmt::{self, Display};
+
+/// The error returned when converting an integer into a type whose
+/// constraint the integer doesn't satisfy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RangeError {
+    pub value: i128,
+    pub min: i128,
+    pub max: i128,
+}
+
+impl Display for RangeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} is out of the range {}..{}",
+            self.value, self.min, self.max
+        )
+    }
+}
+
+impl std::error::Error for RangeError {}
+
+/// A value which doesn't satisfy the constraint of its type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// The path of the value within the validated value, such as
+    /// `route[0].speed`. Empty for the validated value itself.
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            formatter.write_str(&self.message)
+        } else {
+            write!(formatter, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Types with ASN.1 constraints which their rust type doesn't enforce, such
+/// as the SIZE of a string, or the range of an integer which doesn't fall on
+/// the bounds of a rust integer type.
+pub trait Validate {
+    /// Adds the violations of the constraints of this value, and of the
+    /// values it contains, to `violations`. The value is at `path` within
+    /// the value being validated.
+    fn validate_at(&self, path: &str, violations: &mut Vec<Violation>);
+
+    /// Checks the constraints of this value, and of the values it contains,
+    /// returning all the violations found.
+    fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = vec![];
+        self.validate_at("", &mut violations);
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+impl<T: Validate + ?Sized> Validate for Box<T> {
+    fn validate_at(&self, path: &str, violations: &mut Vec<Violation>) {
+        (**self).validate_at(path, violations)
+    }
+}
+
+/// The path of the component with the given name of the value at `path`.
+pub fn component_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+/// The path of the element at `index` of the list at `path`.
+pub fn element_path(path: &str, index: usize) -> String {
+    format!("{}[{}]", path, index)
+}
+
+/// Checks a range constraint, where a `min` or `max` of `None` is MIN or
+/// MAX.
+pub fn check_range(
+    path: &str,
+    value: i128,
+    min: Option<i128>,
+    max: Option<i128>,
+    violations: &mut Vec<Violation>,
+) {
+    if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+        let min = min.map_or_else(|| "MIN".to_string(), |min| min.to_string());
+        let max = max.map_or_else(|| "MAX".to_string(), |max| max.to_string());
+        violations.push(Violation {
+            path: path.to_string(),
+            message: format!("{} is out of the range {}..{}", value, min, max),
+        });
+    }
+}
+
+/// Checks a SIZE constraint, where a `max` of `None` is unbounded.
+pub fn check_size(
+    path: &str,
+    size: usize,
+    min: u64,
+    max: Option<u64>,
+    violations: &mut Vec<Violation>,
+) {
+    let size = size as u64;
+    if size < min || max.is_some_and(|max| size > max) {
+        let max = max.map_or_else(|| "MAX".to_string(), |max| max.to_string());
+        violations.push(Violation {
+            path: path.to_string(),
+            message: format!("size {} is out of the range {}..{}", size, min, max),
+        });
+    }
+}
+
+/// Checks a permitted alphabet, given as ranges of characters with both
+/// ends included.
+pub fn check_alphabet(
+    path: &str,
+    value: &str,
+    alphabet: &[(char, char)],
+    violations: &mut Vec<Violation>,
+) {
+    let not_permitted = value
+        .chars()
+        .find(|c| !alphabet.iter().any(|(from, to)| (from..=to).contains(&c)));
+    if let Some(c) = not_permitted {
+        violations.push(Violation {
+            path: path.to_string(),
+            message: format!("character {:?} is not permitted", c),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks() {
+        let mut violations = vec![];
+        check_range("a", 5, Some(0), Some(10), &mut violations);
+        check_range("a", -5, None, Some(0), &mut violations);
+        check_size("b", 3, 1, None, &mut violations);
+        check_alphabet("c", "AB1", &[('A', 'Z'), ('0', '9')], &mut violations);
+        assert!(violations.is_empty());
+
+        check_range("a", 11, Some(0), Some(10), &mut violations);
+        check_range("a", -1, Some(0), None, &mut violations);
+        check_size("b", 3, 4, Some(8), &mut violations);
+        check_alphabet("c", "Ab", &[('A', 'Z')], &mut violations);
+        assert_eq!(
+            vec![
+                "a: 11 is out of the range 0..10",
+                "a: -1 is out of the range 0..MAX",
+                "b: size 3 is out of the range 4..8",
+                "c: character 'b' is not permitted",
+            ],
+            violations
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn paths() {
+        let path = component_path("", "route");
+        let path = element_path(&path, 2);
+        assert_eq!("route[2].speed", component_path(&path, "speed"));
+    }
+}
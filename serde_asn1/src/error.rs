@@ -1,8 +1,10 @@
-use std;
 use std::fmt::{self, Display};
 
+use asn1_schema::TagClass;
 use serde::{de, ser};
 
+use crate::constraints::Violation;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 // This is a bare-bones implementation. A real library would provide additional
@@ -36,6 +38,34 @@ pub enum Error {
     ExpectedMapEnd,
     ExpectedEnum,
     TrailingCharacters,
+
+    // ASN.1 specific errors.
+    LengthOverflow,
+    InvalidChoiceTag,
+    RecursionLimitExceeded,
+    IntegerOverflow,
+    InvalidObjectIdentifier,
+    UnknownType(String),
+    /// A value whose tag doesn't match the schema.
+    UnexpectedTag {
+        class: TagClass,
+        number: u32,
+    },
+    InvalidTag,
+    InvalidLength,
+    /// An error writing the encoded value.
+    Io(String),
+    /// A primitive value where a constructed one is required, or the other
+    /// way around.
+    UnexpectedForm,
+    /// A value which can't be encoded as its type, or a decoded SET missing
+    /// a required component.
+    InvalidValue(Violation),
+    /// An encoding which the basic rules allow but the canonical ones, such
+    /// as DER or canonical PER, don't, as each canonical value has a single
+    /// encoding.
+    NonCanonical(Violation),
+    ConstraintViolations(Vec<Violation>),
 }
 
 impl ser::Error for Error {
@@ -52,16 +82,50 @@ impl de::Error for Error {
 
 impl Display for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str(std::error::Error::description(self))
-    }
-}
-
-impl std::error::Error for Error {
-    fn description(&self) -> &str {
         match *self {
-            Error::Message(ref msg) => msg,
-            Error::Eof => "unexpected end of input",
-            _ => "replace me",
+            Error::Message(ref msg) => formatter.write_str(msg),
+            Error::Eof => formatter.write_str("unexpected end of input"),
+            Error::ExpectedBoolean => formatter.write_str("invalid boolean"),
+            Error::ExpectedInteger => formatter.write_str("invalid integer"),
+            Error::ExpectedString => formatter.write_str("string is not valid UTF-8"),
+            Error::LengthOverflow => formatter.write_str("length does not fit in a usize"),
+            Error::InvalidChoiceTag => formatter.write_str("tag does not match any alternative"),
+            Error::RecursionLimitExceeded => formatter.write_str("recursion limit exceeded"),
+            Error::IntegerOverflow => formatter.write_str("integer is too large"),
+            Error::InvalidObjectIdentifier => formatter.write_str("invalid object identifier"),
+            Error::UnknownType(ref name) => write!(formatter, "unknown type `{}`", name),
+            Error::UnexpectedTag { class, number } => {
+                let class = match class {
+                    TagClass::Universal => "UNIVERSAL ",
+                    TagClass::Application => "APPLICATION ",
+                    TagClass::ContextSpecific => "",
+                    TagClass::Private => "PRIVATE ",
+                };
+                write!(formatter, "unexpected tag [{}{}]", class, number)
+            }
+            Error::Io(ref message) => write!(formatter, "I/O error: {}", message),
+            Error::InvalidTag => formatter.write_str("tag number is too large"),
+            Error::InvalidLength => formatter.write_str("invalid length"),
+            Error::UnexpectedForm => formatter.write_str(
+                "value is primitive where it should be constructed, or the other way around",
+            ),
+            Error::InvalidValue(ref violation) => write!(formatter, "invalid value: {}", violation),
+            Error::NonCanonical(ref violation) => {
+                write!(formatter, "non-canonical encoding: {}", violation)
+            }
+            Error::ConstraintViolations(ref violations) => {
+                formatter.write_str("constraints violated: ")?;
+                for (index, violation) in violations.iter().enumerate() {
+                    if index > 0 {
+                        formatter.write_str(", ")?;
+                    }
+                    write!(formatter, "{}", violation)?;
+                }
+                Ok(())
+            }
+            _ => formatter.write_str("replace me"),
         }
     }
 }
+
+impl std::error::Error for Error {}
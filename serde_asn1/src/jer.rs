@@ -0,0 +1,273 @@
+//! Rec.ITU-T X.697
+//! Conversion of JSON in the shape of the JSON Encoding Rules (JER) into
+//! values, which can then be encoded with another set of encoding rules.
+//! Generated types serialized with `serde_json` have this shape.
+
+use asn1_schema::{Module, Presence, Type, TypeKind};
+use std::convert::TryFrom;
+
+use crate::constraints::{component_path, element_path};
+use crate::error::Result;
+use crate::value::{enumeration_item, invalid_value, kind_name, lookup, resolve, unsupported_type};
+use crate::Value;
+
+/// Converts a JER value of the type with the given name in `module`.
+///
+/// SEQUENCE components which are `null` are taken to be absent, since that
+/// is how `serde_json` writes OPTIONAL components of generated types.
+pub fn value_from_jer(module: &Module, type_name: &str, json: &serde_json::Value) -> Result<Value> {
+    let ty = lookup(module, type_name)?;
+
+    convert(module, ty, json, "")
+}
+
+fn convert(module: &Module, ty: &Type, json: &serde_json::Value, path: &str) -> Result<Value> {
+    let kind = &resolve(module, ty)?.kind;
+    let unexpected = || {
+        invalid_value(
+            path,
+            format!("expected a value of type {}", kind_name(kind)),
+        )
+    };
+
+    Ok(match kind {
+        TypeKind::Boolean => Value::Bool(json.as_bool().ok_or_else(unexpected)?),
+        TypeKind::Integer { .. } => Value::Integer(
+            json.as_i64()
+                .map(i128::from)
+                .or_else(|| json.as_u64().map(i128::from))
+                .ok_or_else(unexpected)?,
+        ),
+        // Rec.ITU-T X.697 31, the arcs separated by dots
+        TypeKind::ObjectIdentifier => {
+            let arcs = json
+                .as_str()
+                .ok_or_else(unexpected)?
+                .split('.')
+                .map(str::parse)
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|_| invalid_value(path, "invalid object identifier"))?;
+            Value::ObjectIdentifier(arcs)
+        }
+        TypeKind::CharacterString(_) => Value::Str(json.as_str().ok_or_else(unexpected)?.into()),
+        // Rec.ITU-T X.697 23, the octets in hexadecimal, or an array of
+        // them, which is how `serde_json` writes a `Vec<u8>`
+        TypeKind::OctetString => match json {
+            serde_json::Value::String(hex) => Value::Bytes(
+                hex_octets(hex).ok_or_else(|| invalid_value(path, "invalid hexadecimal string"))?,
+            ),
+            serde_json::Value::Array(octets) => Value::Bytes(
+                octets
+                    .iter()
+                    .map(|octet| octet.as_u64().and_then(|octet| u8::try_from(octet).ok()))
+                    .collect::<Option<_>>()
+                    .ok_or_else(unexpected)?,
+            ),
+            _ => return Err(unexpected()),
+        },
+        // Rec.ITU-T X.697 22, the identifier of the item
+        TypeKind::Enumerated(items) => {
+            let name = json.as_str().ok_or_else(unexpected)?;
+            Value::Enum(enumeration_item(items, name, path)?.name.clone())
+        }
+        TypeKind::Sequence(components) | TypeKind::Set(components) => {
+            let object = json.as_object().ok_or_else(unexpected)?;
+            if let Some(name) = object
+                .keys()
+                .find(|name| !components.iter().any(|component| component.name == **name))
+            {
+                return Err(invalid_value(path, format!("unknown component `{}`", name)));
+            }
+
+            let mut values = vec![];
+            for component in components {
+                match object.get(&component.name) {
+                    Some(serde_json::Value::Null) | None
+                        if component.presence != Presence::Required => {}
+                    Some(json) => values.push((
+                        component.name.clone(),
+                        convert(
+                            module,
+                            &component.ty,
+                            json,
+                            &component_path(path, &component.name),
+                        )?,
+                    )),
+                    None => {
+                        return Err(invalid_value(
+                            path,
+                            format!("component `{}` is missing", component.name),
+                        ))
+                    }
+                }
+            }
+            Value::Seq(values)
+        }
+        // Rec.ITU-T X.697 27, an object with the chosen alternative as its
+        // only member
+        TypeKind::Choice(alternatives) => {
+            let object = json.as_object().filter(|object| object.len() == 1);
+            let (name, json) = object
+                .and_then(|object| object.iter().next())
+                .ok_or_else(unexpected)?;
+            let alternative = alternatives
+                .iter()
+                .find(|alternative| alternative.name == *name)
+                .ok_or_else(|| invalid_value(path, format!("unknown alternative `{}`", name)))?;
+            let value = convert(module, &alternative.ty, json, &component_path(path, name))?;
+            Value::Choice(name.clone(), Box::new(value))
+        }
+        TypeKind::SequenceOf(element) | TypeKind::SetOf(element) => Value::List(
+            json.as_array()
+                .ok_or_else(unexpected)?
+                .iter()
+                .enumerate()
+                .map(|(index, json)| convert(module, element, json, &element_path(path, index)))
+                .collect::<Result<_>>()?,
+        ),
+        TypeKind::Reference(_) => unreachable!("references are resolved"),
+        _ => return Err(unsupported_type(path)),
+    })
+}
+
+/// The octets of a string of hexadecimal digits, two to an octet.
+fn hex_octets(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    hex.as_bytes()
+        .chunks(2)
+        .map(|digits| {
+            let digits = std::str::from_utf8(digits).ok()?;
+            u8::from_str_radix(digits, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::value_from_jer;
+    use crate::{Error, Value, Violation};
+    use asn1_schema::Module;
+    use serde_json::json;
+
+    #[test]
+    fn defaults() {
+        let module = Module::parse(include_str!("../../test-asn/defaults.asn")).unwrap();
+
+        let job = json!({
+            "id": 7,
+            "command": "ls",
+            "settings": { "port": 443, "limit": null },
+            "next": null,
+        });
+        assert_eq!(
+            Value::Seq(vec![
+                ("id".into(), Value::Integer(7)),
+                ("command".into(), Value::Str("ls".into())),
+                (
+                    "settings".into(),
+                    Value::Seq(vec![("port".into(), Value::Integer(443))])
+                ),
+            ]),
+            value_from_jer(&module, "Job", &job).unwrap()
+        );
+
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "settings".into(),
+                message: "unknown component `host`".into(),
+            })),
+            value_from_jer(
+                &module,
+                "Job",
+                &json!({ "id": 7, "command": "ls", "settings": { "host": "a" } })
+            )
+        );
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: "component `command` is missing".into(),
+            })),
+            value_from_jer(&module, "Job", &json!({ "id": 7 }))
+        );
+    }
+
+    #[test]
+    fn choices() {
+        let module = Module::parse(
+            "Misc DEFINITIONS AUTOMATIC TAGS ::= BEGIN
+            Reading ::= CHOICE { id OBJECT IDENTIFIER, flags SEQUENCE OF BOOLEAN }
+            END",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Value::Choice(
+                "id".into(),
+                Box::new(Value::ObjectIdentifier(vec![1, 2, 840]))
+            ),
+            value_from_jer(&module, "Reading", &json!({ "id": "1.2.840" })).unwrap()
+        );
+        assert_eq!(
+            Value::Choice(
+                "flags".into(),
+                Box::new(Value::List(vec![Value::Bool(true), Value::Bool(false)]))
+            ),
+            value_from_jer(&module, "Reading", &json!({ "flags": [true, false] })).unwrap()
+        );
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "flags[1]".into(),
+                message: "expected a value of type BOOLEAN".into(),
+            })),
+            value_from_jer(&module, "Reading", &json!({ "flags": [true, 1] }))
+        );
+    }
+
+    #[test]
+    fn files() {
+        let module = Module::parse(include_str!("../../test-asn/files.asn")).unwrap();
+
+        // the digest is in hexadecimal, the contents as `serde_json` writes
+        // a `Vec<u8>`
+        let file = json!({
+            "name": "a",
+            "kind": "link",
+            "digest": "0a0B0c0D",
+            "contents": [255],
+        });
+        assert_eq!(
+            Value::Seq(vec![
+                ("name".into(), Value::Str("a".into())),
+                ("kind".into(), Value::Enum("link".into())),
+                ("digest".into(), Value::Bytes(vec![10, 11, 12, 13])),
+                ("contents".into(), Value::Bytes(vec![255])),
+            ]),
+            value_from_jer(&module, "File", &file).unwrap()
+        );
+
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: "invalid hexadecimal string".into(),
+            })),
+            value_from_jer(&module, "Digest", &json!("+1"))
+        );
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: "expected a value of type OCTET STRING".into(),
+            })),
+            value_from_jer(&module, "Digest", &json!([256]))
+        );
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: "unknown item `socket`".into(),
+            })),
+            value_from_jer(&module, "Kind", &json!("socket"))
+        );
+    }
+}
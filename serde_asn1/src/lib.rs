@@ -1,355 +1,365 @@
+// lets generated code used in the tests refer to this crate by name
+#[cfg(test)]
+extern crate self as serde_asn1;
+
+mod asn_type;
+mod ber;
+pub mod constraints;
 mod error;
-use error::{Error, Result};
+#[cfg(feature = "jer")]
+mod jer;
+mod oer;
+mod per;
+mod value;
+pub use asn_type::AsnType;
+use ber::Rules;
+pub use ber::{
+    value_from_ber_bytes, value_from_cer_bytes, value_from_der_bytes, value_to_ber_bytes,
+    value_to_cer_bytes, value_to_der_bytes, BerOptions,
+};
+pub use constraints::{RangeError, Validate, Violation};
+pub use error::Error;
+use error::Result;
+#[cfg(feature = "jer")]
+pub use jer::value_from_jer;
+pub use oer::{value_from_oer_bytes, value_to_oer_bytes};
+use per::Variant;
+pub use per::{
+    value_from_aper_bytes, value_from_canonical_aper_bytes, value_from_canonical_uper_bytes,
+    value_from_uper_bytes, value_to_aper_bytes, value_to_canonical_aper_bytes,
+    value_to_canonical_uper_bytes, value_to_uper_bytes,
+};
+pub use value::{from_value, to_value, Value};
 
-use serde::{de::Visitor, Deserialize};
+/// The schema types, which generated code refers to through this crate.
+pub use asn1_schema;
 
-struct OerDeserializer<'de> {
-    input: &'de [u8],
-}
+use serde::{Deserialize, Serialize};
+use std::io;
 
-impl<'de> OerDeserializer<'de> {
-    fn from_oer_bytes(input: &'de [u8]) -> Self {
-        Self { input }
-    }
-}
+/// The maximum nesting depth of SEQUENCE, SEQUENCE OF and CHOICE
+/// values accepted by `from_oer_bytes` and the PER functions, such as
+/// `from_aper_bytes`, and of
+/// constructed values accepted by `from_ber_bytes`, `from_der_bytes` and
+/// `from_cer_bytes`.
+pub const DEFAULT_RECURSION_LIMIT: usize = 128;
 
 pub fn from_oer_bytes<'a, T>(input: &'a [u8]) -> Result<T>
 where
-    T: Deserialize<'a>,
+    T: Deserialize<'a> + AsnType,
 {
-    let mut deserializer = OerDeserializer::from_oer_bytes(input);
-    let t = T::deserialize(&mut deserializer)?;
-    if deserializer.input.is_empty() {
-        Ok(t)
-    } else {
-        Err(Error::TrailingCharacters)
-    }
+    from_oer_bytes_with_recursion_limit(input, DEFAULT_RECURSION_LIMIT)
 }
 
-impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut OerDeserializer<'de> {
-    type Error = Error;
-
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        Err(Error::Message(String::from("no any support")))
-    }
-
-    fn deserialize_bool<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
-
-    fn deserialize_i8<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
-
-    fn deserialize_i16<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
-
-    fn deserialize_i32<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
-
-    fn deserialize_i64<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
-
-    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        let (&field_size, rest) = self.input.split_first().ok_or(Error::Eof)?;
-        self.input = rest;
-
-        assert_eq!(1, field_size, "This method is currently not implemented for field size > 1");
-
-        let (&value, rest) = self.input.split_first().ok_or(Error::Eof)?;
-        self.input = rest;
-        visitor.visit_i64(i64::from(value as i8))
-    }
-
-    /// Rec.ITU-T X.696 10.3 a
-    /// For lower bound greater than or equal to zero.
-    /// If the upper bound is less than or equal to 2^8 - 1, then
-    /// every value of the integer type shall be encoded as a
-    /// fixed-size unsigned number in a one-octet word.
-    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        let (value, rest) = self.input.split_first().ok_or(Error::Eof)?;
-        self.input = rest;
-        visitor.visit_u8(*value)
-    }
-
-    /// Rec.ITU-T X.696 10.3 b
-    /// For lower bound greater than or equal to zero.
-    /// If the upper bound is less than or equal to 2^16 - 1, then
-    /// every value of the integer type shall be encoded as a
-    /// fixed-size unsigned number in a one-octet word.
-    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        let (value_bytes, rest) = self.input.split_at(2);
-        self.input = rest;
-
-        // interpret value_bytes as big endian
-        let val1 = value_bytes[0];
-        let val2 = value_bytes[1];
-        let value = u16::from(val1) << 8 | u16::from(val2);
-       
-        visitor.visit_u16(value)
-    }
-
-    fn deserialize_u32<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
-
-    fn deserialize_u64<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
-
-    fn deserialize_f32<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
-
-    fn deserialize_f64<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Like `from_oer_bytes`, but with a custom limit on how deeply SEQUENCE,
+/// SEQUENCE OF and CHOICE values may be nested. Recursive types can otherwise
+/// be used to exhaust the stack with a small input.
+pub fn from_oer_bytes_with_recursion_limit<'a, T>(
+    input: &'a [u8],
+    recursion_limit: usize,
+) -> Result<T>
+where
+    T: Deserialize<'a> + AsnType,
+{
+    let value = oer::decode(T::module(), T::asn_type(), input, recursion_limit, true)?;
+    from_value(value)
+}
 
-    fn deserialize_char<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Like `from_oer_bytes`, but rather than failing on the first value which
+/// violates a constraint, fails with `Error::ConstraintViolations` holding
+/// all of them.
+pub fn from_oer_bytes_validated<'a, T>(input: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a> + AsnType + Validate,
+{
+    let value = oer::decode(
+        T::module(),
+        T::asn_type(),
+        input,
+        DEFAULT_RECURSION_LIMIT,
+        false,
+    )?;
+    let t: T = from_value(value)?;
+    t.validate().map_err(Error::ConstraintViolations)?;
+    Ok(t)
+}
 
-    fn deserialize_str<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+pub fn to_oer_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + AsnType,
+{
+    oer::encode(T::module(), T::asn_type(), &to_value(value)?)
+}
 
-    fn deserialize_string<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Decodes a value from the Basic Encoding Rules (BER), in which lengths may
+/// have the indefinite form and strings may be split into segments.
+pub fn from_ber_bytes<'a, T>(input: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a> + AsnType,
+{
+    let value = ber::decode(
+        T::module(),
+        T::asn_type(),
+        input,
+        DEFAULT_RECURSION_LIMIT,
+        Rules::Basic,
+    )?;
+    from_value(value)
+}
 
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Encodes a value with the Basic Encoding Rules (BER), with definite
+/// lengths.
+pub fn to_ber_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + AsnType,
+{
+    let mut output = vec![];
+    to_ber_writer(&mut output, value)?;
+    Ok(output)
+}
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Like `to_ber_bytes`, but writes the encoding to `writer`.
+pub fn to_ber_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: Serialize + AsnType,
+{
+    to_ber_writer_with_options(writer, value, BerOptions::default())
+}
 
-    fn deserialize_option<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Like `to_ber_writer`, but with the given choices of how to encode the
+/// value. With indefinite lengths, the encoding is written as it is
+/// produced rather than once it is complete.
+pub fn to_ber_writer_with_options<W, T>(writer: W, value: &T, options: BerOptions) -> Result<()>
+where
+    W: io::Write,
+    T: Serialize + AsnType,
+{
+    ber::encode(
+        T::module(),
+        T::asn_type(),
+        &to_value(value)?,
+        writer,
+        Rules::Basic,
+        options,
+    )
+}
 
-    fn deserialize_unit<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Decodes a value from the Distinguished Encoding Rules (DER), failing
+/// with `Error::NonCanonical` on encodings which BER allows but DER
+/// doesn't, such as indefinite lengths or unsorted SET OF elements.
+pub fn from_der_bytes<'a, T>(input: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a> + AsnType,
+{
+    let value = ber::decode(
+        T::module(),
+        T::asn_type(),
+        input,
+        DEFAULT_RECURSION_LIMIT,
+        Rules::Distinguished,
+    )?;
+    from_value(value)
+}
 
-    fn deserialize_unit_struct<V>(self, _name: &'static str, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Encodes a value with the Distinguished Encoding Rules (DER), which give
+/// each value a single encoding.
+pub fn to_der_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + AsnType,
+{
+    let mut output = vec![];
+    ber::encode(
+        T::module(),
+        T::asn_type(),
+        &to_value(value)?,
+        &mut output,
+        Rules::Distinguished,
+        BerOptions::default(),
+    )?;
+    Ok(output)
+}
 
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Decodes a value from the Canonical Encoding Rules (CER), failing with
+/// `Error::NonCanonical` on encodings which BER allows but CER doesn't,
+/// such as definite lengths of constructed values or strings of over 1000
+/// octets which aren't split into segments of 1000 octets.
+pub fn from_cer_bytes<'a, T>(input: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a> + AsnType,
+{
+    let value = ber::decode(
+        T::module(),
+        T::asn_type(),
+        input,
+        DEFAULT_RECURSION_LIMIT,
+        Rules::Canonical,
+    )?;
+    from_value(value)
+}
 
-    fn deserialize_seq<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Encodes a value with the Canonical Encoding Rules (CER), which give each
+/// value a single encoding, like DER, but with the indefinite length form.
+pub fn to_cer_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + AsnType,
+{
+    let mut output = vec![];
+    to_cer_writer(&mut output, value)?;
+    Ok(output)
+}
 
-    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        struct Access<'a, 'de> {
-            deserializer: &'a mut OerDeserializer<'de>,
-            len: usize,
-        }
-
-        impl<'a, 'de> serde::de::SeqAccess<'de> for Access<'a, 'de> {
-            type Error = Error;
-
-            fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
-            where
-                T: serde::de::DeserializeSeed<'de>,
-            {
-                if self.len > 0 {
-                    self.len -= 1;
-                    let value =
-                        serde::de::DeserializeSeed::deserialize(seed, &mut *self.deserializer)?;
-                    Ok(Some(value))
-                } else {
-                    Ok(None)
-                }
-            }
+/// Like `to_cer_bytes`, but writes the encoding to `writer` as it is
+/// produced, except for the elements of SET OFs, which are sorted by their
+/// encodings first.
+pub fn to_cer_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: Serialize + AsnType,
+{
+    ber::encode(
+        T::module(),
+        T::asn_type(),
+        &to_value(value)?,
+        writer,
+        Rules::Canonical,
+        BerOptions::default(),
+    )
+}
 
-            fn size_hint(&self) -> Option<usize> {
-                Some(self.len)
-            }
-        }
+/// Decodes a value from the aligned variant of the Packed Encoding Rules
+/// (APER), which packs values into as few bits as their PER-visible
+/// constraints allow, with some fields aligned to octets.
+pub fn from_aper_bytes<'a, T>(input: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a> + AsnType,
+{
+    let value = per::decode(
+        T::module(),
+        T::asn_type(),
+        input,
+        DEFAULT_RECURSION_LIMIT,
+        Variant::ALIGNED,
+    )?;
+    from_value(value)
+}
 
-        visitor.visit_seq(Access {
-            deserializer: self,
-            len,
-        })
-    }
+/// Encodes a value with the aligned variant of the Packed Encoding Rules
+/// (APER).
+pub fn to_aper_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + AsnType,
+{
+    per::encode(
+        T::module(),
+        T::asn_type(),
+        &to_value(value)?,
+        Variant::ALIGNED,
+    )
+}
 
-    fn deserialize_tuple_struct<V>(
-        self,
-        _name: &'static str,
-        _len: usize,
-        _visitor: V,
-    ) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Decodes a value from the unaligned variant of the Packed Encoding Rules
+/// (UPER), which packs values into as few bits as their PER-visible
+/// constraints allow, without aligning any field to octets. Types generated
+/// once decode values from either variant.
+pub fn from_uper_bytes<'a, T>(input: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a> + AsnType,
+{
+    let value = per::decode(
+        T::module(),
+        T::asn_type(),
+        input,
+        DEFAULT_RECURSION_LIMIT,
+        Variant::UNALIGNED,
+    )?;
+    from_value(value)
+}
 
-    fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Encodes a value with the unaligned variant of the Packed Encoding Rules
+/// (UPER).
+pub fn to_uper_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + AsnType,
+{
+    per::encode(
+        T::module(),
+        T::asn_type(),
+        &to_value(value)?,
+        Variant::UNALIGNED,
+    )
+}
 
-    fn deserialize_struct<V>(
-        self,
-        _name: &'static str,
-        fields: &'static [&'static str],
-        visitor: V,
-    ) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        self.deserialize_tuple(fields.len(), visitor)
-    }
+/// Decodes a value from the canonical aligned variant of the Packed Encoding
+/// Rules, in which each value has a single encoding. Decoding fails with
+/// `Error::NonCanonical` on encodings which APER allows but the canonical
+/// variant doesn't, such as unsorted SET OF elements or components with
+/// their DEFAULT value.
+pub fn from_canonical_aper_bytes<'a, T>(input: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a> + AsnType,
+{
+    let value = per::decode(
+        T::module(),
+        T::asn_type(),
+        input,
+        DEFAULT_RECURSION_LIMIT,
+        Variant::CANONICAL_ALIGNED,
+    )?;
+    from_value(value)
+}
 
-    fn deserialize_enum<V>(
-        self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
-        _visitor: V,
-    ) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Encodes a value with the canonical aligned variant of the Packed Encoding
+/// Rules, which sorts the elements of SET OF values by their encodings.
+pub fn to_canonical_aper_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + AsnType,
+{
+    per::encode(
+        T::module(),
+        T::asn_type(),
+        &to_value(value)?,
+        Variant::CANONICAL_ALIGNED,
+    )
+}
 
-    fn deserialize_identifier<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Decodes a value from the canonical unaligned variant of the Packed
+/// Encoding Rules, failing with `Error::NonCanonical` on encodings which
+/// UPER allows but the canonical variant doesn't.
+pub fn from_canonical_uper_bytes<'a, T>(input: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a> + AsnType,
+{
+    let value = per::decode(
+        T::module(),
+        T::asn_type(),
+        input,
+        DEFAULT_RECURSION_LIMIT,
+        Variant::CANONICAL_UNALIGNED,
+    )?;
+    from_value(value)
+}
 
-    fn deserialize_ignored_any<V>(self, _visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        unimplemented!()
-    }
+/// Encodes a value with the canonical unaligned variant of the Packed
+/// Encoding Rules.
+pub fn to_canonical_uper_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + AsnType,
+{
+    per::encode(
+        T::module(),
+        T::asn_type(),
+        &to_value(value)?,
+        Variant::CANONICAL_UNALIGNED,
+    )
 }
 
 #[cfg(test)]
 mod tests {
-    use super::from_oer_bytes;
+    use super::{from_oer_bytes, to_oer_bytes};
 
     asn1_codegen::from!("../test-asn/geo.asn");
 
-    /// Subprocess call to asn1tools to serialize the given struct using
-    /// OER encoding.
-    fn serialize_with_asn1tools<'a, T>(
-        file_path: &str,
-        struct_name: &str,
-        struct_under_test: &'a T,
-    ) -> Vec<u8>
-    where
-        T: serde::Serialize,
-    {
-        let std_out_bytes = std::process::Command::new("asn1tools")
-            .args(&[
-                "convert",
-                file_path,
-                struct_name,
-                "-o",
-                "oer", // output OER format
-                "-i",
-                "jer", // input JSON format
-                // asn1tools expects the input in hex
-                &hex::encode(serde_json::to_string(struct_under_test).unwrap().as_bytes()),
-            ])
-            .current_dir(std::env::var("CARGO_MANIFEST_DIR").unwrap())
-            .output()
-            .unwrap()
-            .stdout;
-        let std_out_hex_string = String::from_utf8_lossy(&std_out_bytes);
-        let std_out_hex_string_without_newline = std_out_hex_string.trim_end();
-
-        hex::decode(std_out_hex_string_without_newline).unwrap()
-    }
-
     #[test]
     fn point() {
         // explicitly specify the types of these fields to verify the code generation
@@ -358,11 +368,11 @@ mod tests {
             y: 2_i128,
         };
 
-        let oer_bytes = serialize_with_asn1tools("../test-asn/geo.asn", "Point", &point);
+        let oer_bytes = to_oer_bytes(&point).unwrap();
 
-        // This assertion is checking the output of asn1tools, as well as our processing
-        // and interpretation of the returned bytes. It is left here mostly because it is
-        // helpful to see the input bytes to the deserialization code under test.
+        // This assertion is checking the output of the encoder. It is left here mostly
+        // because it is helpful to see the input bytes to the deserialization code
+        // under test.
         assert_eq!(oer_bytes, [1, 254, 1, 2]);
 
         assert_eq!(from_oer_bytes::<Point>(&oer_bytes).unwrap(), point);
@@ -374,9 +384,9 @@ mod tests {
         let p2 = Point { x: 15, y: 25 };
         let line = Line { p1, p2 };
 
-        let oer_bytes = serialize_with_asn1tools("../test-asn/geo.asn", "Line", &line);
+        let oer_bytes = to_oer_bytes(&line).unwrap();
 
-        // Sanity check the asn1tools output
+        // Sanity check the encoder output
         assert_eq!(oer_bytes, [1, 5, 1, 10, 1, 15, 1, 25]);
 
         assert_eq!(from_oer_bytes::<Line>(&oer_bytes).unwrap(), line);
@@ -390,10 +400,9 @@ mod tests {
             height: 5_u8,
         };
 
-        let oer_bytes =
-            serialize_with_asn1tools("../test-asn/geo.asn", "TinyRectangle", &tiny_rectangle);
+        let oer_bytes = to_oer_bytes(&tiny_rectangle).unwrap();
 
-        // Sanity check the asn1tools output
+        // Sanity check the encoder output
         assert_eq!(oer_bytes, [10, 5]);
 
         assert_eq!(
@@ -410,10 +419,9 @@ mod tests {
             height: 6_u16,
         };
 
-        let oer_bytes =
-            serialize_with_asn1tools("../test-asn/geo.asn", "SmallRectangle", &small_rectangle);
+        let oer_bytes = to_oer_bytes(&small_rectangle).unwrap();
 
-        // Sanity check the asn1tools output
+        // Sanity check the encoder output
         assert_eq!(oer_bytes, [0, 11, 0, 6]);
 
         assert_eq!(
@@ -421,4 +429,975 @@ mod tests {
             small_rectangle
         );
     }
+
+    #[test]
+    fn ber() {
+        use crate::{from_ber_bytes, to_ber_bytes, to_ber_writer_with_options, BerOptions};
+
+        let line = [
+            0x30, 0x80, 0x30, 6, 0x02, 1, 5, 0x02, 1, 10, 0x30, 6, 0x02, 1, 15, 0x02, 1, 25, 0, 0,
+        ];
+        let decoded = from_ber_bytes::<Line>(&line).unwrap();
+        assert_eq!(
+            decoded,
+            Line {
+                p1: Point { x: 5, y: 10 },
+                p2: Point { x: 15, y: 25 },
+            }
+        );
+
+        let options = BerOptions {
+            indefinite_length: true,
+        };
+        let mut output = vec![];
+        to_ber_writer_with_options(&mut output, &decoded, options).unwrap();
+        assert_eq!(
+            output,
+            [
+                0x30, 0x80, 0x30, 0x80, 0x02, 1, 5, 0x02, 1, 10, 0, 0, 0x30, 0x80, 0x02, 1, 15,
+                0x02, 1, 25, 0, 0, 0, 0
+            ]
+        );
+        assert_eq!(
+            to_ber_bytes(&decoded).unwrap(),
+            [0x30, 16, 0x30, 6, 0x02, 1, 5, 0x02, 1, 10, 0x30, 6, 0x02, 1, 15, 0x02, 1, 25]
+        );
+    }
+
+    #[test]
+    fn cer() {
+        use crate::{from_cer_bytes, to_cer_bytes};
+
+        let line = Line {
+            p1: Point { x: 5, y: 10 },
+            p2: Point { x: 15, y: 25 },
+        };
+        let cer_bytes = to_cer_bytes(&line).unwrap();
+        assert_eq!(
+            cer_bytes,
+            [
+                0x30, 0x80, 0x30, 0x80, 0x02, 1, 5, 0x02, 1, 10, 0, 0, 0x30, 0x80, 0x02, 1, 15,
+                0x02, 1, 25, 0, 0, 0, 0
+            ]
+        );
+        assert_eq!(from_cer_bytes::<Line>(&cer_bytes).unwrap(), line);
+    }
+
+    #[test]
+    fn asn_type() {
+        use crate::AsnType;
+        use asn1_schema::TypeKind;
+
+        assert_eq!("SmallRectangle", SmallRectangle::NAME);
+        let components = match &SmallRectangle::asn_type().kind {
+            TypeKind::Sequence(components) => components,
+            kind => panic!("unexpected kind {:?}", kind),
+        };
+        let constraint = SmallRectangle::module()
+            .constraint_of(&components[0].ty)
+            .unwrap();
+        assert_eq!(Some((Some(0), Some(65535))), constraint.range);
+        assert_eq!(None, constraint.size);
+        // all the types share one parsed module
+        assert!(std::ptr::eq(Point::module(), Line::module()));
+    }
+
+    mod sets {
+        use crate::{
+            from_canonical_aper_bytes, from_canonical_uper_bytes, from_der_bytes, from_oer_bytes,
+            to_aper_bytes, to_canonical_aper_bytes, to_canonical_uper_bytes, to_der_bytes,
+            to_oer_bytes, to_uper_bytes, Error,
+        };
+
+        asn1_codegen::from!("../test-asn/sets.asn");
+
+        #[test]
+        fn entry() {
+            let entry = Entry {
+                name: vec![
+                    Attribute {
+                        r#type: 2,
+                        value: "b".into(),
+                    },
+                    Attribute {
+                        r#type: 1,
+                        value: "a".into(),
+                    },
+                ],
+                serial: 5,
+                active: true,
+                labels: None,
+            };
+
+            // DER sorts the attributes by their encodings
+            let der_bytes = to_der_bytes(&entry).unwrap();
+            assert_eq!(
+                der_bytes,
+                [
+                    0x31, 21, 0x02, 1, 5, 0xa2, 16, 0x30, 6, 0x02, 1, 1, 0x0c, 1, b'a', 0x30, 6,
+                    0x02, 1, 2, 0x0c, 1, b'b'
+                ]
+            );
+            let sorted = from_der_bytes::<Entry>(&der_bytes).unwrap();
+            assert_eq!(sorted.name[0].value, "a");
+            assert_eq!(sorted.name[1].value, "b");
+
+            // OER encodes the components in the canonical order of their tags,
+            // active and labels being absent from the preamble
+            let oer_bytes = to_oer_bytes(&entry).unwrap();
+            assert_eq!(oer_bytes, [0, 1, 5, 1, 2, 1, 2, 1, b'b', 1, 1, 1, b'a']);
+            assert_eq!(from_oer_bytes::<Entry>(&oer_bytes).unwrap(), entry);
+        }
+
+        #[test]
+        fn canonical_per() {
+            let entry = Entry {
+                name: vec![
+                    Attribute {
+                        r#type: 2,
+                        value: "b".into(),
+                    },
+                    Attribute {
+                        r#type: 1,
+                        value: "a".into(),
+                    },
+                ],
+                serial: 5,
+                active: true,
+                labels: None,
+            };
+
+            // canonical PER sorts the attributes by their encodings, and
+            // rejects the unsorted encoding of basic PER
+            let aper_bytes = to_canonical_aper_bytes(&entry).unwrap();
+            assert_eq!(aper_bytes, [0, 1, 5, 2, 1, 1, 1, b'a', 1, 2, 1, b'b']);
+            let sorted = from_canonical_aper_bytes::<Entry>(&aper_bytes).unwrap();
+            assert_eq!(sorted.name[0].value, "a");
+            assert!(matches!(
+                from_canonical_aper_bytes::<Entry>(&to_aper_bytes(&entry).unwrap()),
+                Err(Error::NonCanonical(_))
+            ));
+
+            let uper_bytes = to_canonical_uper_bytes(&entry).unwrap();
+            assert_eq!(
+                from_canonical_uper_bytes::<Entry>(&uper_bytes).unwrap(),
+                sorted
+            );
+            assert!(matches!(
+                from_canonical_uper_bytes::<Entry>(&to_uper_bytes(&entry).unwrap()),
+                Err(Error::NonCanonical(_))
+            ));
+        }
+    }
+
+    mod values {
+        use crate::from_oer_bytes;
+
+        asn1_codegen::from!("../test-asn/values.asn");
+
+        #[test]
+        fn constants() {
+            // explicitly specify the types of these constants to verify the code generation
+            assert_eq!(64_i128, MAX_ITEMS);
+            assert_eq!(-40_i128, MIN_TEMPERATURE);
+            assert_eq!(3_u8, DEFAULT_COUNT);
+            assert_eq!(1000_i128, HEAVIEST);
+            assert_eq!(1000_i128, MAX_WEIGHT);
+
+            assert_eq!(&[2_u64, 5, 29], ID_CE);
+            assert_eq!(&[2_u64, 5, 29, 14], ID_CE_SUBJECT_KEY_IDENTIFIER);
+            assert_eq!(&[1_u64, 3, 6, 1, 5, 5, 7], ID_PKIX);
+        }
+
+        #[test]
+        fn basket() {
+            // the bounds of both fields are given by value references
+            let basket = Basket {
+                count: 3_u8,
+                weight: 500_u16,
+            };
+            let oer_bytes = [3, 1, 244];
+
+            assert_eq!(from_oer_bytes::<Basket>(&oer_bytes).unwrap(), basket);
+        }
+    }
+
+    mod integers {
+        use crate::{from_oer_bytes, to_oer_bytes, Validate, Violation};
+
+        asn1_codegen::from!("../test-asn/integers.asn", validate);
+
+        #[test]
+        fn types() {
+            // explicitly specify the types to verify the code generation
+            let coldest: i8 = COLDEST;
+            assert_eq!(-40, coldest);
+            let _: Altitude = -500_i16;
+            let _: Offset = -100000_i32;
+            let _: Timestamp = 4294967296000_i64;
+            let _: Counter = 18446744073709551616_i128;
+            let _: Delta = -128_i8;
+        }
+
+        #[test]
+        fn sample() {
+            let mut sample = Sample {
+                temperature: -40,
+                altitude: -500,
+                offset: -100000,
+                delta: -128,
+            };
+            let oer_bytes = [0xd8, 0xfe, 0x0c, 0xff, 0xfe, 0x79, 0x60, 0x80];
+
+            assert_eq!(to_oer_bytes(&sample).unwrap(), oer_bytes);
+            assert_eq!(from_oer_bytes::<Sample>(&oer_bytes).unwrap(), sample);
+
+            // the delta falls on the bounds of `i8`, so it can't be violated
+            sample.temperature = 100;
+            assert_eq!(
+                sample.validate(),
+                Err(vec![Violation {
+                    path: "temperature".into(),
+                    message: "100 is out of the range -40..85".into(),
+                }])
+            );
+        }
+    }
+
+    mod responses {
+        use crate::from_oer_bytes;
+
+        asn1_codegen::from!("../test-asn/responses.asn");
+
+        #[test]
+        fn named_numbers() {
+            assert_eq!(Status(0), Status::OK);
+            assert_eq!(Status(1), Status::BUSY);
+            assert_eq!(Status(2), Status::ERROR);
+            assert_eq!(Status::BUSY, DEFAULT_STATUS);
+
+            assert_eq!(ErrorCode(0), ErrorCode::NO_ERROR);
+            assert_eq!(ErrorCode(-1), ErrorCode::TIMEOUT);
+            assert_eq!(ErrorCode(1000), ErrorCode::UNKNOWN);
+
+            // the named numbers of components are kept in a newtype of their own
+            assert_eq!(ResponseRetries(0), ResponseRetries::NONE);
+            assert_eq!(ResponseRetries(10), ResponseRetries::MANY);
+            assert_eq!(RetryAttempts(1), RetryAttempts::ONCE);
+        }
+
+        #[test]
+        fn response() {
+            // explicitly specify the types of these fields to verify the code generation
+            let response = Response {
+                status: Status::BUSY,
+                code: ErrorCode::TIMEOUT,
+                retries: ResponseRetries(3_u8),
+            };
+
+            // named numbers don't change the encoding of the integer
+            assert_eq!(
+                serde_json::to_string(&response).unwrap(),
+                r#"{"status":1,"code":-1,"retries":3}"#
+            );
+
+            let oer_bytes = [1, 1, 255, 3];
+            assert_eq!(from_oer_bytes::<Response>(&oer_bytes).unwrap(), response);
+        }
+
+        #[test]
+        fn retry() {
+            // the DEFAULT value is given by one of the named numbers
+            let retry = Retry {
+                attempts: RetryAttempts::MANY,
+            };
+            assert_eq!(from_oer_bytes::<Retry>(&[0]).unwrap(), retry);
+        }
+    }
+
+    mod recursive {
+        use crate::error::Error;
+        use crate::{from_oer_bytes, from_oer_bytes_with_recursion_limit, to_oer_bytes};
+
+        asn1_codegen::from!("../test-asn/recursive.asn");
+
+        #[test]
+        fn node() {
+            let node = Node {
+                value: 1,
+                children: vec![
+                    Node {
+                        value: 2,
+                        children: vec![],
+                    },
+                    Node {
+                        value: 3,
+                        children: vec![],
+                    },
+                ],
+            };
+
+            // the quantity field of each SEQUENCE OF is a one octet length determinant,
+            // followed by the number of children in a single octet
+            let oer_bytes = [1, 1, 1, 2, 1, 2, 1, 0, 1, 3, 1, 0];
+            assert_eq!(from_oer_bytes::<Node>(&oer_bytes).unwrap(), node);
+        }
+
+        #[test]
+        fn expr() {
+            // explicitly specify the types of these alternatives to verify the code generation
+            let expr = Expr::Sum(Box::new(Sum {
+                left: Expr::Lit(1_i128),
+                right: Expr::Neg(Box::new(Expr::Lit(2_i128))),
+            }));
+
+            // the alternatives are tagged [0], [1] and [2]
+            let oer_bytes = [0x82, 0x80, 1, 1, 0x81, 0x80, 1, 2];
+            assert_eq!(from_oer_bytes::<Expr>(&oer_bytes).unwrap(), expr);
+            assert_eq!(to_oer_bytes(&expr).unwrap(), oer_bytes);
+        }
+
+        #[test]
+        fn program() {
+            let program = Program {
+                statements: vec![Expr::Lit(7), Expr::Neg(Box::new(Expr::Lit(7)))],
+            };
+
+            let oer_bytes = [1, 2, 0x80, 1, 7, 0x81, 0x80, 1, 7];
+            assert_eq!(from_oer_bytes::<Program>(&oer_bytes).unwrap(), program);
+            assert_eq!(to_oer_bytes(&program).unwrap(), oer_bytes);
+        }
+
+        #[test]
+        fn invalid_choice_tag() {
+            assert_eq!(
+                from_oer_bytes::<Expr>(&[0x83, 1, 1]),
+                Err(Error::InvalidChoiceTag)
+            );
+        }
+
+        #[test]
+        fn recursion_limit() {
+            // neg(neg(neg(lit 1)))
+            let oer_bytes = [0x81, 0x81, 0x81, 0x80, 1, 1];
+
+            assert!(from_oer_bytes_with_recursion_limit::<Expr>(&oer_bytes, 4).is_ok());
+            assert_eq!(
+                from_oer_bytes_with_recursion_limit::<Expr>(&oer_bytes, 3),
+                Err(Error::RecursionLimitExceeded)
+            );
+        }
+    }
+
+    mod names {
+        use crate::from_oer_bytes;
+
+        asn1_codegen::from!("../test-asn/names.asn");
+
+        #[test]
+        fn message_header() {
+            let header = MessageHeader {
+                max_speed: 1,
+                r#type: 2,
+                r#ref: 3,
+                min_speed: 4,
+                self_: 5,
+            };
+
+            // the ASN.1 names are kept when serializing
+            assert_eq!(
+                serde_json::to_string(&header).unwrap(),
+                r#"{"max-speed":1,"type":2,"ref":3,"minSpeed":4,"self":5}"#
+            );
+
+            let oer_bytes = [1, 2, 3, 4, 5];
+            assert_eq!(from_oer_bytes::<MessageHeader>(&oer_bytes).unwrap(), header);
+        }
+
+        #[test]
+        fn message() {
+            let message = Message::Match(5);
+
+            assert_eq!(serde_json::to_string(&message).unwrap(), r#"{"match":5}"#);
+
+            let oer_bytes = [0x81, 5];
+            assert_eq!(from_oer_bytes::<Message>(&oer_bytes).unwrap(), message);
+        }
+
+        #[test]
+        fn constants() {
+            assert_eq!(100, MAX_SPEED_LIMIT);
+        }
+    }
+
+    mod prelude {
+        use crate::{from_oer_bytes, to_oer_bytes, Validate};
+        use std::convert::TryFrom;
+
+        asn1_codegen::from!(
+            "../test-asn/prelude.asn",
+            validate,
+            defaults,
+            builders,
+            checked_newtypes,
+        );
+
+        #[test]
+        fn shadowed_names() {
+            // the generated code doesn't refer to the types it shadows
+            let string = String::builder().build();
+            assert_eq!(string.value, "none");
+            assert_eq!(Default::default().string, std::option::Option::None);
+
+            let option = Option::Vec(Vec {
+                items: std::vec![Some::try_from(1).unwrap()],
+                string: std::option::Option::Some(string),
+            });
+            assert_eq!(option.validate(), std::result::Result::Ok(()));
+
+            let oer_bytes = to_oer_bytes(&option).unwrap();
+            assert_eq!(from_oer_bytes::<Option>(&oer_bytes).unwrap(), option);
+            assert_eq!(Box::default().ok, Ok::ZERO);
+        }
+    }
+
+    mod config {
+        use crate::from_oer_bytes;
+        use std::collections::HashSet;
+
+        asn1_codegen::from!(
+            "../test-asn/geo.asn",
+            visibility = pub(crate),
+            derive(Clone, Eq, Hash),
+            serde = serde,
+            attributes("Point", #[derive(Default)]),
+        );
+
+        #[test]
+        fn derives() {
+            let point = Point::default();
+            let line = Line {
+                p1: point.clone(),
+                p2: Point { x: 1, y: 1 },
+            };
+
+            let mut lines = HashSet::new();
+            lines.insert(line.clone());
+            assert!(lines.contains(&line));
+
+            let oer_bytes = [1, 0, 1, 0, 1, 1, 1, 1];
+            assert_eq!(from_oer_bytes::<Line>(&oer_bytes).unwrap(), line);
+        }
+
+        mod without_serde {
+            asn1_codegen::from!("../test-asn/geo.asn", serde = none, derive(Copy, Clone));
+
+            #[test]
+            fn derives() {
+                let point = Point { x: 1, y: 2 };
+                let copy = point;
+                assert_eq!(point, copy);
+            }
+        }
+    }
+
+    mod type_overrides {
+        use crate::from_oer_bytes;
+        use serde_derive::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Port(u16);
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Pixel {
+            x: i128,
+            y: i128,
+        }
+
+        asn1_codegen::from!(
+            "../test-asn/geo.asn",
+            types("Unsigned16" = Port, "Line.p2" = Pixel),
+        );
+
+        #[test]
+        fn overridden_types() {
+            let oer_bytes = [0, 1, 0, 2];
+            assert_eq!(
+                from_oer_bytes::<SmallRectangle>(&oer_bytes).unwrap(),
+                SmallRectangle {
+                    width: Port(1),
+                    height: Port(2)
+                }
+            );
+
+            let oer_bytes = [1, 1, 1, 2, 1, 3, 1, 4];
+            assert_eq!(
+                from_oer_bytes::<Line>(&oer_bytes).unwrap(),
+                Line {
+                    p1: Point { x: 1, y: 2 },
+                    p2: Pixel { x: 3, y: 4 }
+                }
+            );
+        }
+
+        mod values {
+            asn1_codegen::from!("../test-asn/values.asn", types("Unsigned8" = u32));
+
+            #[test]
+            fn overridden_value_type() {
+                let default_count: u8 = DEFAULT_COUNT;
+                assert_eq!(3, default_count);
+            }
+        }
+    }
+
+    mod documented {
+        use crate::from_oer_bytes;
+
+        asn1_codegen::from!("../test-asn/documented.asn");
+
+        #[test]
+        fn update() {
+            // an untagged alternative has the tag of its type, UNIVERSAL 16
+            let oer_bytes = [0x10, 50, 0, 90];
+            assert_eq!(
+                from_oer_bytes::<Update>(&oer_bytes).unwrap(),
+                Update::Reading(Reading {
+                    speed: 50,
+                    heading: 90
+                })
+            );
+            assert_eq!(200, MAX_SPEED);
+        }
+    }
+
+    mod aliases {
+        use crate::error::Error;
+        use crate::from_oer_bytes;
+
+        asn1_codegen::from!("../test-asn/aliases.asn");
+
+        const VEHICLE: [u8; 10] = [2, 0x41, 0x42, 1, 1, 1, 0x58, 50, 0xff, 150];
+
+        #[test]
+        fn vehicle() {
+            let vehicle = Vehicle {
+                callsign: "AB".into(),
+                route: vec![Waypoint {
+                    name: "X".into(),
+                    speed: 50,
+                    arrived: true,
+                }],
+                cruising: 150,
+            };
+
+            let max_speed: MaxSpeed = DEFAULT_SPEED;
+            assert_eq!(max_speed, vehicle.route[0].speed);
+
+            let mut oer_bytes = VEHICLE;
+            assert_eq!(from_oer_bytes::<Vehicle>(&oer_bytes).unwrap(), vehicle);
+
+            // booleans are either 0 or 0xFF
+            oer_bytes[8] = 1;
+            assert_eq!(
+                from_oer_bytes::<Vehicle>(&oer_bytes),
+                Err(Error::ExpectedBoolean)
+            );
+        }
+
+        mod newtypes {
+            use super::VEHICLE;
+            use crate::from_oer_bytes;
+
+            asn1_codegen::from!("../test-asn/aliases.asn", newtypes);
+
+            #[test]
+            fn vehicle() {
+                let vehicle = Vehicle {
+                    callsign: "AB".into(),
+                    route: vec![Waypoint {
+                        name: "X".into(),
+                        speed: Speed(50),
+                        arrived: true,
+                    }],
+                    cruising: 150,
+                };
+                assert_eq!(DEFAULT_SPEED, vehicle.route[0].speed);
+
+                assert_eq!(from_oer_bytes::<Vehicle>(&VEHICLE).unwrap(), vehicle);
+            }
+        }
+
+        mod validated_newtypes {
+            use crate::{Validate, Violation};
+
+            asn1_codegen::from!("../test-asn/aliases.asn", newtypes, validate);
+
+            #[test]
+            fn vehicle() {
+                let vehicle = Vehicle {
+                    callsign: "AB".into(),
+                    route: vec![Waypoint {
+                        name: "X".into(),
+                        speed: Speed(101),
+                        arrived: true,
+                    }],
+                    cruising: 201,
+                };
+                assert_eq!(
+                    vehicle.validate(),
+                    Err(vec![
+                        Violation {
+                            path: "route[0].speed".into(),
+                            message: "101 is out of the range 0..100".into(),
+                        },
+                        Violation {
+                            path: "cruising".into(),
+                            message: "201 is out of the range 0..200".into(),
+                        },
+                    ])
+                );
+            }
+        }
+
+        mod checked_newtypes {
+            use super::VEHICLE;
+            use crate::error::Error;
+            use crate::{from_oer_bytes, RangeError, Violation};
+            use std::convert::TryFrom;
+
+            asn1_codegen::from!("../test-asn/aliases.asn", checked_newtypes);
+
+            #[test]
+            fn speed() {
+                let speed = Speed::try_from(50).unwrap();
+                assert_eq!(50, *speed);
+                assert_eq!(DEFAULT_SPEED, speed);
+
+                assert_eq!(
+                    Speed::try_from(101),
+                    Err(RangeError {
+                        value: 101,
+                        min: 0,
+                        max: 100
+                    })
+                );
+            }
+
+            #[test]
+            fn vehicle() {
+                let vehicle = from_oer_bytes::<Vehicle>(&VEHICLE).unwrap();
+                assert_eq!(50, *vehicle.route[0].speed);
+
+                // the speed is out of range
+                let mut oer_bytes = VEHICLE;
+                oer_bytes[7] = 101;
+                assert!(from_oer_bytes::<Vehicle>(&oer_bytes).is_err());
+            }
+
+            #[test]
+            fn inline_constraint() {
+                // the inline constraint of `cruising` has a newtype of its own
+                let vehicle = from_oer_bytes::<Vehicle>(&VEHICLE).unwrap();
+                let cruising: VehicleCruising = vehicle.cruising;
+                assert_eq!(150, *cruising);
+                assert_eq!(
+                    VehicleCruising::try_from(201),
+                    Err(RangeError {
+                        value: 201,
+                        min: 0,
+                        max: 200
+                    })
+                );
+
+                // which rejects values out of range when decoding
+                let mut oer_bytes = VEHICLE;
+                oer_bytes[9] = 201;
+                assert_eq!(
+                    from_oer_bytes::<Vehicle>(&oer_bytes),
+                    Err(Error::InvalidValue(Violation {
+                        path: "cruising".into(),
+                        message: "201 is out of the range 0..200".into(),
+                    }))
+                );
+            }
+        }
+    }
+
+    mod validation {
+        use crate::error::Error;
+        use crate::{from_oer_bytes, from_oer_bytes_validated, Validate, Violation};
+
+        asn1_codegen::from!("../test-asn/constraints.asn", validate);
+
+        // the station has a fixed size, so it has no length determinant
+        const MESSAGE: [u8; 13] = [
+            0x10, b'1', b'2', b'3', b'4', 1, 1, 2, b'A', b'B', 1, b'X', 50,
+        ];
+
+        fn violation(path: &str, message: &str) -> Violation {
+            Violation {
+                path: path.into(),
+                message: message.into(),
+            }
+        }
+
+        #[test]
+        fn valid() {
+            let message = from_oer_bytes_validated::<Message>(&MESSAGE).unwrap();
+            assert_eq!(
+                message,
+                Message::Report(Report {
+                    station: "1234".into(),
+                    readings: vec![Reading {
+                        code: "AB".into(),
+                        label: "X".into(),
+                        level: 50,
+                    }],
+                })
+            );
+        }
+
+        #[test]
+        fn violations() {
+            let mut oer_bytes = MESSAGE;
+            oer_bytes[8] = b'a';
+            oer_bytes[11] = b'~';
+            oer_bytes[12] = 101;
+            let violations = vec![
+                violation("report.readings[0].code", "character 'a' is not permitted"),
+                violation("report.readings[0].label", "character '~' is not permitted"),
+                violation("report.readings[0].level", "101 is out of the range 0..100"),
+            ];
+
+            // decoding stops at the first violation
+            assert_eq!(
+                from_oer_bytes::<Message>(&oer_bytes),
+                Err(Error::InvalidValue(violations[0].clone()))
+            );
+            assert_eq!(
+                from_oer_bytes_validated::<Message>(&oer_bytes),
+                Err(Error::ConstraintViolations(violations))
+            );
+        }
+
+        #[test]
+        fn sizes() {
+            let mut report = Report {
+                station: "12345".into(),
+                readings: vec![],
+            };
+            assert_eq!(
+                report.validate(),
+                Err(vec![
+                    violation("station", "size 5 is out of the range 4..4"),
+                    violation("readings", "size 0 is out of the range 1..3"),
+                ])
+            );
+
+            report.station = "12 4".into();
+            report.readings = vec![Reading {
+                code: "ABCDE".into(),
+                label: "".into(),
+                level: 0,
+            }];
+            assert_eq!(
+                report.validate(),
+                Err(vec![
+                    violation("readings[0].code", "size 5 is out of the range 2..4"),
+                    violation("readings[0].label", "size 0 is out of the range 1..MAX"),
+                ])
+            );
+
+            let note = Message::Note("a note longer than 16".into());
+            assert_eq!(
+                note.validate(),
+                Err(vec![violation("note", "size 21 is out of the range 0..16")])
+            );
+        }
+
+        #[test]
+        fn unbounded_ranges() {
+            let tally = Tally {
+                count: -1,
+                balance: 1,
+            };
+            assert_eq!(
+                tally.validate(),
+                Err(vec![
+                    violation("count", "-1 is out of the range 0..MAX"),
+                    violation("balance", "1 is out of the range MIN..0"),
+                ])
+            );
+            assert_eq!(
+                Tally {
+                    count: i128::MAX,
+                    balance: i128::MIN,
+                }
+                .validate(),
+                Ok(())
+            );
+        }
+    }
+
+    mod defaults {
+        use crate::{from_oer_bytes, to_oer_bytes};
+
+        asn1_codegen::from!("../test-asn/defaults.asn", defaults, builders);
+
+        #[test]
+        fn default() {
+            assert_eq!(
+                Settings::default(),
+                Settings {
+                    name: "default".into(),
+                    port: DEFAULT_PORT,
+                    priority: Priority::NORMAL,
+                    verbose: false,
+                    limit: None,
+                }
+            );
+            let enabled: bool = ENABLED;
+            assert!(enabled);
+            assert_eq!("hello \"world\"", GREETING);
+        }
+
+        #[test]
+        fn builder() {
+            // required components are given when creating the builder
+            let job = Job::builder(1, "ls".into())
+                .settings(Settings::builder().verbose(true).build())
+                .next(Job::builder(2, "pwd".into()).build())
+                .build();
+
+            assert_eq!(
+                job,
+                Job {
+                    id: 1,
+                    command: "ls".into(),
+                    settings: Some(Settings {
+                        verbose: true,
+                        ..Settings::default()
+                    }),
+                    retries: 3,
+                    next: Some(Box::new(Job {
+                        id: 2,
+                        command: "pwd".into(),
+                        settings: None,
+                        retries: 3,
+                        next: None,
+                    })),
+                }
+            );
+        }
+
+        #[test]
+        fn oer() {
+            let job = Job {
+                id: 1,
+                command: "ls".into(),
+                settings: Some(Settings {
+                    port: 22,
+                    ..Settings::default()
+                }),
+                retries: 3,
+                next: None,
+            };
+
+            // components equal to their DEFAULT value are left out, and the
+            // preamble of each SEQUENCE has a bit for each of the others
+            let oer_bytes = [0x80, 1, 1, 2, b'l', b's', 0x40, 0, 22];
+            assert_eq!(to_oer_bytes(&job).unwrap(), oer_bytes);
+            assert_eq!(from_oer_bytes::<Job>(&oer_bytes).unwrap(), job);
+        }
+    }
+
+    mod files {
+        use crate::{from_der_bytes, from_oer_bytes, to_der_bytes, to_oer_bytes};
+
+        asn1_codegen::from!("../test-asn/files.asn");
+
+        #[test]
+        fn values() {
+            // explicitly specify the types of these constants to verify the code generation
+            let empty_digest: &[u8] = EMPTY_DIGEST;
+            assert_eq!(&[0_u8, 0, 0, 0], empty_digest);
+            assert_eq!(Kind::Directory, DEFAULT_KIND);
+        }
+
+        #[test]
+        fn file() {
+            let file = File {
+                name: "a".into(),
+                kind: Kind::Device,
+                digest: vec![1, 2, 3, 4],
+                contents: vec![0xff],
+            };
+
+            // device is an extension addition, numbered after the root items
+            // it doesn't clash with, and the digest has a fixed size
+            let oer_bytes = [0x80, 1, b'a', 2, 1, 2, 3, 4, 1, 0xff];
+            assert_eq!(to_oer_bytes(&file).unwrap(), oer_bytes);
+            assert_eq!(from_oer_bytes::<File>(&oer_bytes).unwrap(), file);
+
+            // a kind equal to its DEFAULT value is left out
+            let file = File {
+                kind: Kind::File,
+                ..file
+            };
+            let der_bytes = [0x30, 12, 0x80, 1, b'a', 0x82, 4, 1, 2, 3, 4, 0x83, 1, 0xff];
+            assert_eq!(to_der_bytes(&file).unwrap(), der_bytes);
+            assert_eq!(from_der_bytes::<File>(&der_bytes).unwrap(), file);
+        }
+    }
+
+    mod extensions {
+        use crate::{from_aper_bytes, from_uper_bytes, to_aper_bytes, to_uper_bytes};
+
+        asn1_codegen::from!("../test-asn/extensions.asn");
+
+        #[test]
+        fn aper() {
+            let setup = Setup {
+                id: 1,
+                name: Some("ab".into()),
+                level: Some(300),
+                cells: None,
+            };
+
+            // the level is an extension addition, and out of the extensible
+            // range of its type
+            let aper_bytes = [0xc0, 0, 1, 0x08, b'a', b'b', 0x03, 0, 4, 0x80, 2, 1, 0x2c];
+            assert_eq!(to_aper_bytes(&setup).unwrap(), aper_bytes);
+            assert_eq!(from_aper_bytes::<Setup>(&aper_bytes).unwrap(), setup);
+
+            let release = Procedure::Release(5);
+            assert_eq!(to_aper_bytes(&release).unwrap(), [0x80, 1, 5]);
+            assert_eq!(
+                from_aper_bytes::<Procedure>(&[0x80, 1, 5]).unwrap(),
+                release
+            );
+        }
+
+        #[test]
+        fn uper() {
+            let setup = Setup {
+                id: 1,
+                name: None,
+                level: Some(3),
+                cells: Some(vec![10, 20]),
+            };
+
+            // the same type decodes either variant
+            let aper_bytes = to_aper_bytes(&setup).unwrap();
+            let uper_bytes = to_uper_bytes(&setup).unwrap();
+            assert_eq!(
+                uper_bytes,
+                [0x80, 0, 0x40, 0xe0, 0x26, 0, 0x68, 0x50, 0xa0, 0]
+            );
+            assert_ne!(aper_bytes, uper_bytes);
+            assert_eq!(from_aper_bytes::<Setup>(&aper_bytes).unwrap(), setup);
+            assert_eq!(from_uper_bytes::<Setup>(&uper_bytes).unwrap(), setup);
+        }
+    }
 }
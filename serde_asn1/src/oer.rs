@@ -0,0 +1,1332 @@
+//! Rec.ITU-T X.696
+//! The Octet Encoding Rules (OER), driven by the ASN.1 types of a schema.
+
+use asn1_schema::{
+    Component, Constraint, Module, Presence, StringKind, Tag, TagClass, Type, TypeKind,
+};
+use std::convert::TryFrom;
+
+use crate::constraints::{component_path, element_path};
+use crate::error::{Error, Result};
+use crate::value::{
+    check_constraint, component_values, constraint_of, enumerated_value, enumeration_item,
+    invalid_value, kind_name, lookup, octets, resolve, unsupported_type,
+};
+use crate::{Value, DEFAULT_RECURSION_LIMIT};
+
+/// Decodes a value of the type with the given name in `module` from OER
+/// bytes.
+pub fn value_from_oer_bytes(module: &Module, type_name: &str, input: &[u8]) -> Result<Value> {
+    let ty = lookup(module, type_name)?;
+
+    decode(module, ty, input, DEFAULT_RECURSION_LIMIT, true)
+}
+
+/// Encodes a value of the type with the given name in `module` as OER
+/// bytes. Components with a DEFAULT value are left out when they have it.
+pub fn value_to_oer_bytes(module: &Module, type_name: &str, value: &Value) -> Result<Vec<u8>> {
+    let ty = lookup(module, type_name)?;
+
+    encode(module, ty, value)
+}
+
+/// Decodes a value of the given type, which may nest SEQUENCE, SEQUENCE OF
+/// and CHOICE values up to `recursion_limit` deep. Unless `checked` is
+/// false, decoding fails on the first value which violates a constraint.
+pub(crate) fn decode(
+    module: &Module,
+    ty: &Type,
+    input: &[u8],
+    recursion_limit: usize,
+    checked: bool,
+) -> Result<Value> {
+    let mut decoder = OerDecoder::new(input, recursion_limit, checked);
+    let value = decoder.read_value(module, ty, "")?;
+    if decoder.input.is_empty() {
+        Ok(value)
+    } else {
+        Err(Error::TrailingCharacters)
+    }
+}
+
+pub(crate) fn encode(module: &Module, ty: &Type, value: &Value) -> Result<Vec<u8>> {
+    let mut encoder = OerEncoder {
+        module,
+        output: vec![],
+    };
+    encoder.write_value(ty, value, "")?;
+
+    Ok(encoder.output)
+}
+
+/// Rec.ITU-T X.696 10
+/// How the values of an INTEGER type are encoded, which depends on its range.
+#[derive(Debug, PartialEq)]
+enum IntegerEncoding {
+    /// A fixed-size unsigned number of the given number of octets.
+    Unsigned(usize),
+    /// A fixed-size two's complement number of the given number of octets.
+    Signed(usize),
+    /// A length determinant followed by the number in that many octets.
+    Variable { signed: bool },
+}
+
+fn integer_encoding(range: Option<(Option<i128>, Option<i128>)>) -> IntegerEncoding {
+    const OCTETS: [usize; 4] = [1, 2, 4, 8];
+
+    match range {
+        Some((Some(min), max)) if min >= 0 => OCTETS
+            .iter()
+            .find(|&&octets| max.is_some_and(|max| max < 1 << (8 * octets)))
+            .map_or(IntegerEncoding::Variable { signed: false }, |&octets| {
+                IntegerEncoding::Unsigned(octets)
+            }),
+        Some((Some(min), Some(max))) => OCTETS
+            .iter()
+            .find(|&&octets| min >= -(1 << (8 * octets - 1)) && max < 1 << (8 * octets - 1))
+            .map_or(IntegerEncoding::Variable { signed: true }, |&octets| {
+                IntegerEncoding::Signed(octets)
+            }),
+        _ => IntegerEncoding::Variable { signed: true },
+    }
+}
+
+/// Rec.ITU-T X.696 14 and 27
+/// The size of the values of a type with a fixed size, which are encoded
+/// without a length determinant if the type is an OCTET STRING or a
+/// known-multiplier character string type.
+fn fixed_size(constraint: &Constraint) -> Option<u64> {
+    match constraint.size {
+        Some((min, Some(max))) if min == max && !constraint.extensible => Some(min),
+        _ => None,
+    }
+}
+
+/// Rec.ITU-T X.696 8.7.2
+/// The bits giving the class of a tag in its first octet.
+pub(crate) fn tag_class_bits(class: TagClass) -> u8 {
+    match class {
+        TagClass::Universal => 0b00,
+        TagClass::Application => 0b01,
+        TagClass::ContextSpecific => 0b10,
+        TagClass::Private => 0b11,
+    }
+}
+
+/// Reads values from OER bytes.
+struct OerDecoder<'de> {
+    input: &'de [u8],
+    depth: usize,
+    recursion_limit: usize,
+    /// Whether the constraints of the decoded values are checked.
+    checked: bool,
+}
+
+impl<'de> OerDecoder<'de> {
+    fn new(input: &'de [u8], recursion_limit: usize, checked: bool) -> Self {
+        Self {
+            input,
+            depth: 0,
+            recursion_limit,
+            checked,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'de [u8]> {
+        if self.input.len() < len {
+            return Err(Error::Eof);
+        }
+        let (bytes, rest) = self.input.split_at(len);
+        self.input = rest;
+
+        Ok(bytes)
+    }
+
+    fn take_byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Rec.ITU-T X.696 9
+    /// A boolean is a single octet, which is 0 for false and 0xFF for true.
+    fn read_bool(&mut self) -> Result<bool> {
+        match self.take_byte()? {
+            0 => Ok(false),
+            0xff => Ok(true),
+            _ => Err(Error::ExpectedBoolean),
+        }
+    }
+
+    /// Rec.ITU-T X.696 8.6
+    /// A length determinant is either a single octet holding a length of
+    /// up to 127, or an octet with the high bit set whose remaining bits
+    /// give the number of subsequent octets holding the length.
+    fn read_length_determinant(&mut self) -> Result<usize> {
+        let first = self.take_byte()?;
+        if first & 0x80 == 0 {
+            return Ok(usize::from(first));
+        }
+
+        let len_octets = usize::from(first & 0x7f);
+        self.read_unsigned_length(len_octets)
+    }
+
+    /// Reads a big endian unsigned number of `octets` octets, which
+    /// is used as a length or number of components.
+    fn read_unsigned_length(&mut self, octets: usize) -> Result<usize> {
+        self.take(octets)?.iter().try_fold(0_usize, |len, &b| {
+            len.checked_mul(256)
+                .map(|len| len + usize::from(b))
+                .ok_or(Error::LengthOverflow)
+        })
+    }
+
+    /// Rec.ITU-T X.696 8.7
+    /// Returns the class (the top two bits of the first octet) and the
+    /// number of a tag.
+    fn read_tag(&mut self) -> Result<(u8, u32)> {
+        let first = self.take_byte()?;
+        let class = first >> 6;
+        let mut number = u32::from(first & 0x3f);
+
+        // tag numbers of 63 and above are written in subsequent octets,
+        // seven bits at a time, with the high bit set on all but the last
+        if number == 0x3f {
+            number = 0;
+            loop {
+                let b = self.take_byte()?;
+                number = number
+                    .checked_mul(128)
+                    .map(|number| number | u32::from(b & 0x7f))
+                    .ok_or(Error::InvalidChoiceTag)?;
+                if b & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+
+        Ok((class, number))
+    }
+
+    /// Runs the given function one level deeper into nested values,
+    /// failing if that goes beyond the recursion limit.
+    fn nested<F, T>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        if self.depth >= self.recursion_limit {
+            return Err(Error::RecursionLimitExceeded);
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+
+        result
+    }
+
+    /// Reads the value at `path` of the given type.
+    fn read_value(&mut self, module: &Module, ty: &Type, path: &str) -> Result<Value> {
+        let constraint = constraint_of(module, ty)?;
+        let resolved = resolve(module, ty)?;
+        let value = match &resolved.kind {
+            TypeKind::Boolean => self.read_bool().map(Value::Bool),
+            TypeKind::Integer { .. } => self
+                .read_integer(oer_range(&constraint))
+                .map(Value::Integer),
+            TypeKind::ObjectIdentifier => {
+                self.read_object_identifier().map(Value::ObjectIdentifier)
+            }
+            TypeKind::CharacterString(kind) => self.read_string(*kind, &constraint).map(Value::Str),
+            TypeKind::OctetString => self.read_octet_string(&constraint).map(Value::Bytes),
+            TypeKind::Enumerated(items) => {
+                let number = self.read_enumerated()?;
+                enumerated_value(items, number, path)
+            }
+            TypeKind::Sequence(components) => {
+                let components: Vec<&Component> = components.iter().collect();
+                self.nested(|decoder| {
+                    decoder.read_sequence(module, &components, resolved.extensible, path)
+                })
+            }
+            // Rec.ITU-T X.696 17, a SEQUENCE of the components in canonical
+            // order
+            TypeKind::Set(components) => {
+                let order = module.canonical_order(components);
+                let value = self.nested(|decoder| {
+                    decoder.read_sequence(module, &order, resolved.extensible, path)
+                })?;
+                match value {
+                    Value::Seq(mut values) => {
+                        values.sort_by_key(|(name, _)| {
+                            components
+                                .iter()
+                                .position(|component| component.name == *name)
+                        });
+                        Ok(Value::Seq(values))
+                    }
+                    _ => unreachable!("SEQUENCEs are decoded as `Value::Seq`"),
+                }
+            }
+            TypeKind::Choice(alternatives) => {
+                self.nested(|decoder| decoder.read_choice(module, alternatives, path))
+            }
+            // Rec.ITU-T X.696 22, the same as a SEQUENCE OF
+            TypeKind::SequenceOf(element) | TypeKind::SetOf(element) => {
+                self.nested(|decoder| decoder.read_sequence_of(module, element, path))
+            }
+            TypeKind::Reference(_) => unreachable!("references are resolved"),
+            _ => Err(unsupported_type(path)),
+        }?;
+        if self.checked {
+            check_constraint(module, ty, &value, path)?;
+        }
+
+        Ok(value)
+    }
+
+    fn read_integer(&mut self, range: Option<(Option<i128>, Option<i128>)>) -> Result<i128> {
+        match integer_encoding(range) {
+            IntegerEncoding::Unsigned(octets) => self.read_unsigned(octets),
+            IntegerEncoding::Signed(octets) => self.read_signed(octets),
+            IntegerEncoding::Variable { signed } => {
+                let octets = self.read_length_determinant()?;
+                if signed {
+                    self.read_signed(octets)
+                } else {
+                    self.read_unsigned(octets)
+                }
+            }
+        }
+    }
+
+    fn read_unsigned(&mut self, octets: usize) -> Result<i128> {
+        self.take(octets)?.iter().try_fold(0_i128, |value, &b| {
+            value
+                .checked_mul(256)
+                .map(|value| value + i128::from(b))
+                .ok_or(Error::IntegerOverflow)
+        })
+    }
+
+    fn read_signed(&mut self, octets: usize) -> Result<i128> {
+        let bytes = self.take(octets)?;
+        // the sign extends into the octets before the first one
+        let sign = match bytes.first() {
+            Some(&first) if first & 0x80 != 0 => -1,
+            _ => 0,
+        };
+
+        bytes.iter().try_fold(sign, |value: i128, &b| {
+            value
+                .checked_mul(256)
+                .and_then(|value| value.checked_add(i128::from(b)))
+                .ok_or(Error::IntegerOverflow)
+        })
+    }
+
+    /// Rec.ITU-T X.696 24
+    /// An object identifier is a length determinant followed by the
+    /// contents octets of its BER encoding, where each arc is written seven
+    /// bits at a time, and the first two arcs are combined into one.
+    fn read_object_identifier(&mut self) -> Result<Vec<u64>> {
+        let len = self.read_length_determinant()?;
+        object_identifier_arcs(self.take(len)?)
+    }
+
+    /// Rec.ITU-T X.696 11
+    /// The number of an item of an ENUMERATED type is a single octet if it
+    /// is from 0 to 127, and otherwise an octet with the high bit set whose
+    /// remaining bits give the number of subsequent octets holding it as a
+    /// two's complement number.
+    fn read_enumerated(&mut self) -> Result<i128> {
+        let first = self.take_byte()?;
+        if first & 0x80 == 0 {
+            return Ok(i128::from(first));
+        }
+
+        self.read_signed(usize::from(first & 0x7f))
+    }
+
+    /// Rec.ITU-T X.696 14
+    fn read_octet_string(&mut self, constraint: &Constraint) -> Result<Vec<u8>> {
+        let len = match fixed_size(constraint) {
+            Some(size) => usize::try_from(size).map_err(|_| Error::LengthOverflow)?,
+            None => self.read_length_determinant()?,
+        };
+
+        Ok(self.take(len)?.to_vec())
+    }
+
+    /// Rec.ITU-T X.696 27
+    fn read_string(&mut self, kind: StringKind, constraint: &Constraint) -> Result<String> {
+        let len = match fixed_size(constraint).filter(|_| kind != StringKind::Utf8) {
+            Some(size) => usize::try_from(size).map_err(|_| Error::LengthOverflow)?,
+            None => self.read_length_determinant()?,
+        };
+        let bytes = self.take(len)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| Error::ExpectedString)
+    }
+
+    /// Rec.ITU-T X.696 16
+    /// The components of a SEQUENCE are preceded by a preamble holding a bit
+    /// for each OPTIONAL or DEFAULT component, which is set if the component
+    /// is present, padded to a whole number of octets. The preamble of an
+    /// extensible SEQUENCE starts with a bit which is set if any extension
+    /// addition is present, in which case the additions follow the other
+    /// components.
+    fn read_sequence(
+        &mut self,
+        module: &Module,
+        components: &[&Component],
+        extensible: bool,
+        path: &str,
+    ) -> Result<Value> {
+        let (additions, root): (Vec<&Component>, Vec<&Component>) =
+            components.iter().partition(|component| component.extension);
+        let optional = root
+            .iter()
+            .filter(|component| component.presence != Presence::Required)
+            .count();
+        let preamble = self.take((usize::from(extensible) + optional).div_ceil(8))?;
+
+        let mut bits = (0..).map(|bit| preamble[bit / 8] & (0x80 >> (bit % 8)) != 0);
+        let extended = extensible && bits.next() == Some(true);
+        let mut values = vec![];
+        for component in root {
+            let present = match component.presence {
+                Presence::Required => true,
+                _ => bits.next() == Some(true),
+            };
+
+            if present {
+                let path = component_path(path, &component.name);
+                let value = self.read_value(module, &component.ty, &path)?;
+                values.push((component.name.clone(), value));
+            } else if let Presence::Default(default) = &component.presence {
+                values.push((component.name.clone(), Value::from(default)));
+            }
+        }
+
+        let present = if extended {
+            self.read_extension_bitmap()?
+        } else {
+            vec![]
+        };
+        for (index, present) in present.iter().enumerate() {
+            match additions.get(index) {
+                Some(component) if *present => {
+                    let path = component_path(path, &component.name);
+                    let value = self.read_open_type(module, &component.ty, &path)?;
+                    values.push((component.name.clone(), value));
+                }
+                // additions this schema doesn't know of are skipped
+                None if *present => {
+                    let len = self.read_length_determinant()?;
+                    self.take(len)?;
+                }
+                _ => {}
+            }
+        }
+        for (index, component) in additions.iter().enumerate() {
+            if let (false, Presence::Default(default)) = (
+                present.get(index).copied().unwrap_or_default(),
+                &component.presence,
+            ) {
+                values.push((component.name.clone(), Value::from(default)));
+            }
+        }
+
+        Ok(Value::Seq(values))
+    }
+
+    /// Rec.ITU-T X.696 16.4
+    /// The bits telling which extension additions are present, encoded as a
+    /// BIT STRING: a length determinant, an octet holding the number of
+    /// unused bits in the last octet, and the bits.
+    fn read_extension_bitmap(&mut self) -> Result<Vec<bool>> {
+        let len = self.read_length_determinant()?;
+        let (&unused, octets) = self.take(len)?.split_first().ok_or(Error::Eof)?;
+        let bits = (octets.len() * 8)
+            .checked_sub(usize::from(unused))
+            .filter(|_| unused < 8)
+            .ok_or(Error::InvalidLength)?;
+
+        Ok((0..bits)
+            .map(|bit| octets[bit / 8] & (0x80 >> (bit % 8)) != 0)
+            .collect())
+    }
+
+    /// Rec.ITU-T X.696 30
+    /// An open type is the encoding of a value, preceded by its length.
+    fn read_open_type(&mut self, module: &Module, ty: &Type, path: &str) -> Result<Value> {
+        let len = self.read_length_determinant()?;
+        let contents = self.take(len)?;
+        let rest = std::mem::replace(&mut self.input, contents);
+        let result = self.read_value(module, ty, path).and_then(|value| {
+            if self.input.is_empty() {
+                Ok(value)
+            } else {
+                Err(Error::TrailingCharacters)
+            }
+        });
+        self.input = rest;
+
+        result
+    }
+
+    /// Rec.ITU-T X.696 20
+    /// A CHOICE is encoded as the tag of the chosen alternative, followed by
+    /// the alternative itself.
+    fn read_choice(
+        &mut self,
+        module: &Module,
+        alternatives: &[Component],
+        path: &str,
+    ) -> Result<Value> {
+        let (class, number) = self.read_tag()?;
+        let alternative = alternatives
+            .iter()
+            .find(|alternative| {
+                let tag = alternative.tag.or_else(|| module.tag_of(&alternative.ty));
+                tag.is_some_and(|tag| tag_class_bits(tag.class) == class && tag.number == number)
+            })
+            .ok_or(Error::InvalidChoiceTag)?;
+
+        // extension additions are open types
+        let path = component_path(path, &alternative.name);
+        let value = if alternative.extension {
+            self.read_open_type(module, &alternative.ty, &path)?
+        } else {
+            self.read_value(module, &alternative.ty, &path)?
+        };
+        Ok(Value::Choice(alternative.name.clone(), Box::new(value)))
+    }
+
+    /// Rec.ITU-T X.696 21
+    fn read_sequence_of(&mut self, module: &Module, element: &Type, path: &str) -> Result<Value> {
+        let quantity_octets = self.read_length_determinant()?;
+        let len = self.read_unsigned_length(quantity_octets)?;
+
+        // not preallocated, since the quantity isn't checked against the
+        // length of the input
+        let mut values = vec![];
+        for index in 0..len {
+            values.push(self.read_value(module, element, &element_path(path, index))?);
+        }
+
+        Ok(Value::List(values))
+    }
+}
+
+/// Rec.ITU-T X.690 8.19
+/// The arcs of an object identifier from the contents octets of its BER
+/// encoding, which OER shares.
+pub(crate) fn object_identifier_arcs(bytes: &[u8]) -> Result<Vec<u64>> {
+    if bytes.last().is_none_or(|last| last & 0x80 != 0) {
+        return Err(Error::InvalidObjectIdentifier);
+    }
+
+    let mut arcs = vec![];
+    let mut arc = 0_u64;
+    for &b in bytes {
+        arc = arc
+            .checked_mul(128)
+            .map(|arc| arc | u64::from(b & 0x7f))
+            .ok_or(Error::IntegerOverflow)?;
+        if b & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = arc.min(80) / 40;
+                arcs.push(first);
+                arcs.push(arc - first * 40);
+            } else {
+                arcs.push(arc);
+            }
+            arc = 0;
+        }
+    }
+
+    Ok(arcs)
+}
+
+/// Rec.ITU-T X.690 8.19
+/// The contents octets of the BER encoding of an object identifier, or
+/// `None` if its first two arcs can't be combined.
+pub(crate) fn object_identifier_octets(arcs: &[u64]) -> Option<Vec<u8>> {
+    let (first, second) = match arcs {
+        [first @ 0..=2, second, ..] if *first == 2 || *second < 40 => (*first, *second),
+        _ => return None,
+    };
+
+    let mut octets = base128_octets((first * 40).checked_add(second)?);
+    for &arc in &arcs[2..] {
+        octets.extend(base128_octets(arc));
+    }
+    Some(octets)
+}
+
+/// Packs bits into octets, the first bit being the most significant bit of
+/// the first octet and the last octet padded with zero bits.
+fn bit_octets(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|bits| {
+            bits.iter()
+                .enumerate()
+                .filter(|(_, &bit)| bit)
+                .fold(0_u8, |octet, (index, _)| octet | 0x80 >> index)
+        })
+        .collect()
+}
+
+/// The range of an INTEGER as far as OER is concerned, which encodes values
+/// of a type with an extensible range as if it had none.
+fn oer_range(constraint: &Constraint) -> Option<(Option<i128>, Option<i128>)> {
+    constraint.range.filter(|_| !constraint.extensible)
+}
+
+/// The big endian octets of an unsigned number, without leading zeros
+/// other than a single zero for 0.
+pub(crate) fn unsigned_octets(value: u128) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let leading = bytes.iter().take_while(|&&b| b == 0).count().min(15);
+    bytes[leading..].to_vec()
+}
+
+/// The big endian octets of a two's complement number, without leading
+/// octets which only extend the sign.
+pub(crate) fn signed_octets(value: i128) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let leading = bytes
+        .windows(2)
+        .take_while(|pair| {
+            (pair[0] == 0 && pair[1] & 0x80 == 0) || (pair[0] == 0xff && pair[1] & 0x80 != 0)
+        })
+        .count();
+    bytes[leading..].to_vec()
+}
+
+/// A number written seven bits at a time, with the high bit set on all but
+/// the last octet, as used for tag numbers and object identifier arcs.
+pub(crate) fn base128_octets(mut value: u64) -> Vec<u8> {
+    let mut octets = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value > 0 {
+        octets.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    octets.reverse();
+    octets
+}
+
+/// Writes values as directed by a schema.
+struct OerEncoder<'a> {
+    module: &'a Module,
+    output: Vec<u8>,
+}
+
+impl<'a> OerEncoder<'a> {
+    fn write_value(&mut self, ty: &Type, value: &Value, path: &str) -> Result<()> {
+        check_constraint(self.module, ty, value, path)?;
+        let constraint = constraint_of(self.module, ty)?;
+        let resolved = resolve(self.module, ty)?;
+        match (&resolved.kind, value) {
+            (TypeKind::Boolean, Value::Bool(value)) => {
+                self.output.push(if *value { 0xff } else { 0 });
+            }
+            (TypeKind::Integer { .. }, Value::Integer(value)) => {
+                self.write_integer(*value, oer_range(&constraint));
+            }
+            (TypeKind::ObjectIdentifier, Value::ObjectIdentifier(arcs)) => {
+                self.write_object_identifier(arcs, path)?;
+            }
+            (TypeKind::CharacterString(kind), Value::Str(value)) => {
+                self.write_string(*kind, &constraint, value);
+            }
+            (TypeKind::OctetString, value) if octets(value).is_some() => {
+                self.write_octet_string(&constraint, &octets(value).unwrap_or_default());
+            }
+            (TypeKind::Enumerated(items), Value::Enum(name)) => {
+                let item = enumeration_item(items, name, path)?;
+                self.write_enumerated(item.number);
+            }
+            (TypeKind::Sequence(components), Value::Seq(values)) => {
+                let components: Vec<&Component> = components.iter().collect();
+                self.write_sequence(&components, values, resolved.extensible, path)?;
+            }
+            // Rec.ITU-T X.696 17
+            (TypeKind::Set(components), Value::Seq(values)) => {
+                let components = self.module.canonical_order(components);
+                self.write_sequence(&components, values, resolved.extensible, path)?;
+            }
+            (TypeKind::Choice(alternatives), Value::Choice(name, value)) => {
+                self.write_choice(alternatives, name, value, path)?;
+            }
+            (TypeKind::SequenceOf(element) | TypeKind::SetOf(element), Value::List(values)) => {
+                self.write_quantity(values.len());
+                for (index, value) in values.iter().enumerate() {
+                    self.write_value(element, value, &element_path(path, index))?;
+                }
+            }
+            (kind, _) => {
+                return Err(invalid_value(
+                    path,
+                    format!("expected a value of type {}", kind_name(kind)),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rec.ITU-T X.696 8.6
+    fn write_length_determinant(&mut self, len: usize) {
+        if len < 0x80 {
+            self.output.push(len as u8);
+        } else {
+            let octets = unsigned_octets(len as u128);
+            self.output.push(0x80 | octets.len() as u8);
+            self.output.extend(octets);
+        }
+    }
+
+    /// Rec.ITU-T X.696 8.7
+    fn write_tag(&mut self, tag: Tag) {
+        let class = tag_class_bits(tag.class) << 6;
+        if tag.number < 0x3f {
+            self.output.push(class | tag.number as u8);
+        } else {
+            self.output.push(class | 0x3f);
+            self.output.extend(base128_octets(u64::from(tag.number)));
+        }
+    }
+
+    /// Rec.ITU-T X.696 21
+    /// The number of elements of a SEQUENCE OF, written as a length
+    /// determinant followed by the number in that many octets.
+    fn write_quantity(&mut self, quantity: usize) {
+        let octets = unsigned_octets(quantity as u128);
+        self.write_length_determinant(octets.len());
+        self.output.extend(octets);
+    }
+
+    fn write_integer(&mut self, value: i128, range: Option<(Option<i128>, Option<i128>)>) {
+        match integer_encoding(range) {
+            IntegerEncoding::Unsigned(octets) | IntegerEncoding::Signed(octets) => {
+                // the value is checked to be in the range, so it fits
+                self.output
+                    .extend_from_slice(&value.to_be_bytes()[16 - octets..]);
+            }
+            IntegerEncoding::Variable { signed } => {
+                let octets = if signed {
+                    signed_octets(value)
+                } else {
+                    unsigned_octets(value as u128)
+                };
+                self.write_length_determinant(octets.len());
+                self.output.extend(octets);
+            }
+        }
+    }
+
+    /// Rec.ITU-T X.696 11
+    fn write_enumerated(&mut self, number: i128) {
+        if (0..0x80).contains(&number) {
+            self.output.push(number as u8);
+        } else {
+            let octets = signed_octets(number);
+            self.output.push(0x80 | octets.len() as u8);
+            self.output.extend(octets);
+        }
+    }
+
+    /// Rec.ITU-T X.696 24
+    fn write_object_identifier(&mut self, arcs: &[u64], path: &str) -> Result<()> {
+        let octets = object_identifier_octets(arcs)
+            .ok_or_else(|| invalid_value(path, "invalid object identifier"))?;
+        self.write_length_determinant(octets.len());
+        self.output.extend(octets);
+
+        Ok(())
+    }
+
+    /// Rec.ITU-T X.696 14
+    fn write_octet_string(&mut self, constraint: &Constraint, octets: &[u8]) {
+        // a value with a fixed size is checked to have it
+        if fixed_size(constraint).is_none() {
+            self.write_length_determinant(octets.len());
+        }
+        self.output.extend_from_slice(octets);
+    }
+
+    /// Rec.ITU-T X.696 27
+    fn write_string(&mut self, kind: StringKind, constraint: &Constraint, value: &str) {
+        // a string with a fixed size is checked to have it, and the
+        // characters of the kinds of string which may have one are single
+        // octets
+        if fixed_size(constraint).is_none() || kind == StringKind::Utf8 {
+            self.write_length_determinant(value.len());
+        }
+        self.output.extend_from_slice(value.as_bytes());
+    }
+
+    /// Rec.ITU-T X.696 16
+    fn write_sequence(
+        &mut self,
+        components: &[&Component],
+        values: &[(String, Value)],
+        extensible: bool,
+        path: &str,
+    ) -> Result<()> {
+        let mut present = vec![];
+        let mut additions = vec![];
+        let mut preamble = vec![false; usize::from(extensible)];
+        for (component, value) in component_values(components, values, path)? {
+            if component.extension {
+                additions.push((component, value));
+                continue;
+            }
+            if component.presence != Presence::Required {
+                preamble.push(value.is_some());
+            }
+            if let Some(value) = value {
+                present.push((component, value));
+            }
+        }
+        let extended = additions.iter().any(|(_, value)| value.is_some());
+        if extensible {
+            preamble[0] = extended;
+        }
+
+        self.output.extend(bit_octets(&preamble));
+        for (component, value) in present {
+            self.write_value(&component.ty, value, &component_path(path, &component.name))?;
+        }
+
+        // Rec.ITU-T X.696 16.4
+        if extended {
+            let bitmap: Vec<bool> = additions.iter().map(|(_, value)| value.is_some()).collect();
+            let octets = bit_octets(&bitmap);
+            self.write_length_determinant(octets.len() + 1);
+            self.output.push((octets.len() * 8 - bitmap.len()) as u8);
+            self.output.extend(octets);
+            for (component, value) in additions {
+                if let Some(value) = value {
+                    let path = component_path(path, &component.name);
+                    self.write_open_type(&component.ty, value, &path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rec.ITU-T X.696 30
+    fn write_open_type(&mut self, ty: &Type, value: &Value, path: &str) -> Result<()> {
+        let mut encoder = OerEncoder {
+            module: self.module,
+            output: vec![],
+        };
+        encoder.write_value(ty, value, path)?;
+        self.write_length_determinant(encoder.output.len());
+        self.output.extend(encoder.output);
+
+        Ok(())
+    }
+
+    /// Rec.ITU-T X.696 20
+    fn write_choice(
+        &mut self,
+        alternatives: &[Component],
+        name: &str,
+        value: &Value,
+        path: &str,
+    ) -> Result<()> {
+        let alternative = alternatives
+            .iter()
+            .find(|alternative| alternative.name == name)
+            .ok_or_else(|| invalid_value(path, format!("unknown alternative `{}`", name)))?;
+        let tag = alternative
+            .tag
+            .or_else(|| self.module.tag_of(&alternative.ty))
+            .ok_or_else(|| invalid_value(path, format!("alternative `{}` has no tag", name)))?;
+
+        self.write_tag(tag);
+        let path = component_path(path, name);
+        if alternative.extension {
+            self.write_open_type(&alternative.ty, value, &path)
+        } else {
+            self.write_value(&alternative.ty, value, &path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        integer_encoding, signed_octets, unsigned_octets, value_from_oer_bytes, value_to_oer_bytes,
+        IntegerEncoding,
+    };
+    use crate::value::helpers::{integer, seq, string};
+    use crate::{Error, Value, Violation};
+    use asn1_schema::Module;
+
+    #[test]
+    fn integer_encodings() {
+        assert_eq!(
+            IntegerEncoding::Unsigned(1),
+            integer_encoding(Some((Some(0), Some(255))))
+        );
+        assert_eq!(
+            IntegerEncoding::Unsigned(2),
+            integer_encoding(Some((Some(0), Some(256))))
+        );
+        assert_eq!(
+            IntegerEncoding::Unsigned(8),
+            integer_encoding(Some((Some(0), Some(u64::MAX.into()))))
+        );
+        assert_eq!(
+            IntegerEncoding::Variable { signed: false },
+            integer_encoding(Some((Some(0), Some(i128::from(u64::MAX) + 1))))
+        );
+        assert_eq!(
+            IntegerEncoding::Signed(1),
+            integer_encoding(Some((Some(-128), Some(127))))
+        );
+        assert_eq!(
+            IntegerEncoding::Signed(2),
+            integer_encoding(Some((Some(-129), Some(0))))
+        );
+        assert_eq!(
+            IntegerEncoding::Variable { signed: true },
+            integer_encoding(None)
+        );
+        assert_eq!(
+            IntegerEncoding::Variable { signed: false },
+            integer_encoding(Some((Some(0), None)))
+        );
+        assert_eq!(
+            IntegerEncoding::Variable { signed: true },
+            integer_encoding(Some((None, Some(0))))
+        );
+    }
+
+    #[test]
+    fn geo() {
+        let module = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+
+        assert_eq!(
+            seq(vec![("x", integer(-2)), ("y", integer(2))]),
+            value_from_oer_bytes(&module, "Point", &[1, 254, 1, 2]).unwrap()
+        );
+        assert_eq!(
+            seq(vec![("width", integer(11)), ("height", integer(258))]),
+            value_from_oer_bytes(&module, "SmallRectangle", &[0, 11, 1, 2]).unwrap()
+        );
+    }
+
+    #[test]
+    fn constraints() {
+        let module = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+
+        // the module has EXPLICIT tags, so the alternatives have universal
+        // tags, and the fixed size station has no length determinant
+        let report = [
+            0x10, b'1', b'2', b'3', b'4', 1, 1, 2, b'A', b'B', 1, b'X', 50,
+        ];
+        let reading = seq(vec![
+            ("code", string("AB")),
+            ("label", string("X")),
+            ("level", integer(50)),
+        ]);
+        assert_eq!(
+            Value::Choice(
+                "report".into(),
+                Box::new(seq(vec![
+                    ("station", string("1234")),
+                    ("readings", Value::List(vec![reading])),
+                ]))
+            ),
+            value_from_oer_bytes(&module, "Message", &report).unwrap()
+        );
+
+        assert_eq!(
+            Value::Choice("note".into(), Box::new(string("hi"))),
+            value_from_oer_bytes(&module, "Message", &[0x0c, 2, b'h', b'i']).unwrap()
+        );
+
+        // the count has no upper bound and a lower bound of 0, so it is
+        // unsigned, and the balance has no lower bound, so it is signed
+        let tally = seq(vec![("count", integer(300)), ("balance", integer(-1))]);
+        let oer_bytes = [2, 1, 0x2c, 1, 0xff];
+        assert_eq!(
+            oer_bytes.to_vec(),
+            value_to_oer_bytes(&module, "Tally", &tally).unwrap()
+        );
+        assert_eq!(
+            tally,
+            value_from_oer_bytes(&module, "Tally", &oer_bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let module = Module::parse(include_str!("../../test-asn/defaults.asn")).unwrap();
+
+        // settings is present with only a port, retries and next are absent
+        let job = [0x80, 1, 7, 2, b'l', b's', 0x40, 0x01, 0xbb];
+        let value = value_from_oer_bytes(&module, "Job", &job).unwrap();
+        assert_eq!(
+            seq(vec![
+                ("id", integer(7)),
+                ("command", string("ls")),
+                (
+                    "settings",
+                    seq(vec![
+                        ("name", string("default")),
+                        ("port", integer(443)),
+                        ("priority", integer(1)),
+                        ("verbose", Value::Bool(false)),
+                    ])
+                ),
+                ("retries", integer(3)),
+            ]),
+            value
+        );
+        assert_eq!(
+            Some(&integer(443)),
+            value
+                .get("settings")
+                .and_then(|settings| settings.get("port"))
+        );
+        assert_eq!(None, value.get("next"));
+    }
+
+    #[test]
+    fn extensions() {
+        let module = Module::parse(include_str!("../../test-asn/extensions.asn")).unwrap();
+
+        // the extension bit and name are set in the preamble, the level
+        // addition follows as an open type, and the ranges and sizes which
+        // are extensible are encoded as if unconstrained
+        let setup = seq(vec![
+            ("id", integer(1)),
+            ("name", string("ab")),
+            ("level", integer(300)),
+        ]);
+        let bytes = [0xc0, 0, 1, 2, b'a', b'b', 2, 6, 0x80, 3, 2, 1, 0x2c];
+        assert_eq!(
+            bytes.to_vec(),
+            value_to_oer_bytes(&module, "Setup", &setup).unwrap()
+        );
+        assert_eq!(
+            setup,
+            value_from_oer_bytes(&module, "Setup", &bytes).unwrap()
+        );
+
+        // without additions the extension bit is clear
+        let setup = seq(vec![("id", integer(1))]);
+        assert_eq!(
+            vec![0, 0, 1],
+            value_to_oer_bytes(&module, "Setup", &setup).unwrap()
+        );
+
+        // a third addition from a later version is skipped
+        assert_eq!(
+            setup,
+            value_from_oer_bytes(&module, "Setup", &[0x80, 0, 1, 2, 5, 0x20, 1, 0xff]).unwrap()
+        );
+
+        let release = Value::Choice("release".into(), Box::new(integer(5)));
+        assert_eq!(
+            vec![0x82, 1, 5],
+            value_to_oer_bytes(&module, "Procedure", &release).unwrap()
+        );
+        assert_eq!(
+            release,
+            value_from_oer_bytes(&module, "Procedure", &[0x82, 1, 5]).unwrap()
+        );
+    }
+
+    #[test]
+    fn tags() {
+        let module = Module::parse(
+            "Misc DEFINITIONS AUTOMATIC TAGS ::= BEGIN
+            Id ::= OBJECT IDENTIFIER
+            Temperature ::= INTEGER (-100..100)
+            Reading ::= CHOICE { id Id, temperature Temperature, flag [APPLICATION 70] BOOLEAN }
+            END",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Value::Choice(
+                "id".into(),
+                Box::new(Value::ObjectIdentifier(vec![1, 2, 840]))
+            ),
+            value_from_oer_bytes(&module, "Reading", &[0x06, 3, 42, 0x86, 0x48]).unwrap()
+        );
+        assert_eq!(
+            Value::Choice("temperature".into(), Box::new(integer(-5))),
+            value_from_oer_bytes(&module, "Reading", &[0x02, 0xfb]).unwrap()
+        );
+        assert_eq!(
+            Value::Choice("flag".into(), Box::new(Value::Bool(true))),
+            value_from_oer_bytes(&module, "Reading", &[0x7f, 0x46, 0xff]).unwrap()
+        );
+    }
+
+    #[test]
+    fn files() {
+        let module = Module::parse(include_str!("../../test-asn/files.asn")).unwrap();
+
+        // the digest has a fixed size, so it has no length determinant
+        let file = seq(vec![
+            ("name", string("a")),
+            ("kind", Value::Enum("directory".into())),
+            ("digest", Value::Bytes(vec![1, 2, 3, 4])),
+            ("contents", Value::Bytes(vec![0xff])),
+        ]);
+        let bytes = [0x80, 1, b'a', 1, 1, 2, 3, 4, 1, 0xff];
+        assert_eq!(
+            bytes.to_vec(),
+            value_to_oer_bytes(&module, "File", &file).unwrap()
+        );
+        assert_eq!(file, value_from_oer_bytes(&module, "File", &bytes).unwrap());
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: "size 3 is out of the range 4..4".into(),
+            })),
+            value_to_oer_bytes(&module, "Digest", &Value::Bytes(vec![1, 2, 3]))
+        );
+        assert_eq!(
+            seq(vec![
+                ("name", string("")),
+                ("kind", Value::Enum("file".into())),
+                ("digest", Value::Bytes(vec![0; 4])),
+                ("contents", Value::Bytes(vec![])),
+            ]),
+            value_from_oer_bytes(&module, "File", &[0, 0, 0, 0, 0, 0, 0]).unwrap()
+        );
+
+        // numbers from 0 to 127 are a single octet, others have a length
+        let module = Module::parse(
+            "Levels DEFINITIONS ::= BEGIN
+            Level ::= ENUMERATED { low(-1), normal(0), high(1000) }
+            END",
+        )
+        .unwrap();
+        for (name, bytes) in [
+            ("low", vec![0x81, 0xff]),
+            ("normal", vec![0]),
+            ("high", vec![0x82, 0x03, 0xe8]),
+        ] {
+            let value = Value::Enum(name.into());
+            assert_eq!(bytes, value_to_oer_bytes(&module, "Level", &value).unwrap());
+            assert_eq!(
+                value,
+                value_from_oer_bytes(&module, "Level", &bytes).unwrap()
+            );
+        }
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: "unknown item number 1".into(),
+            })),
+            value_from_oer_bytes(&module, "Level", &[1])
+        );
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: "unknown item `medium`".into(),
+            })),
+            value_to_oer_bytes(&module, "Level", &Value::Enum("medium".into()))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let module = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+
+        assert_eq!(
+            Err(Error::UnknownType("Circle".into())),
+            value_from_oer_bytes(&module, "Circle", &[])
+        );
+        assert_eq!(
+            Err(Error::TrailingCharacters),
+            value_from_oer_bytes(&module, "TinyRectangle", &[1, 2, 3])
+        );
+        assert_eq!(
+            Err(Error::Eof),
+            value_from_oer_bytes(&module, "Point", &[1, 254, 2, 0])
+        );
+    }
+
+    #[test]
+    fn decode_errors() {
+        let module = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+        let invalid = |message: &str| {
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: message.into(),
+            }))
+        };
+
+        assert_eq!(
+            invalid("200 is out of the range 0..100"),
+            value_from_oer_bytes(&module, "Percent", &[200])
+        );
+        assert_eq!(
+            invalid("size 5 is out of the range 2..4"),
+            value_from_oer_bytes(&module, "Code", &[5, b'A', b'B', b'C', b'D', b'E'])
+        );
+        // not in the alphabet of IA5String
+        assert_eq!(
+            invalid("character 'é' is not permitted"),
+            value_from_oer_bytes(&module, "Code", &[3, b'A', 0xc3, 0xa9])
+        );
+        assert_eq!(
+            invalid("size 0 is out of the range 1..3"),
+            value_from_oer_bytes(&module, "Readings", &[1, 0])
+        );
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "report.readings[0].level".into(),
+                message: "101 is out of the range 0..100".into(),
+            })),
+            value_from_oer_bytes(
+                &module,
+                "Message",
+                &[0x10, b'1', b'2', b'3', b'4', 1, 1, 2, b'A', b'B', 1, b'X', 101]
+            )
+        );
+    }
+
+    /// Checks that the value decodes from the bytes, and encodes to them.
+    fn round_trip(module: &Module, type_name: &str, bytes: &[u8]) {
+        let value = value_from_oer_bytes(module, type_name, bytes).unwrap();
+        assert_eq!(
+            bytes,
+            &value_to_oer_bytes(module, type_name, &value).unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn octets() {
+        assert_eq!(vec![0], unsigned_octets(0));
+        assert_eq!(vec![1, 0], unsigned_octets(256));
+        assert_eq!(vec![0], signed_octets(0));
+        assert_eq!(vec![0xff], signed_octets(-1));
+        assert_eq!(vec![0, 0x80], signed_octets(128));
+        assert_eq!(vec![0xff, 0x7f], signed_octets(-129));
+    }
+
+    #[test]
+    fn encode() {
+        let geo = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+        round_trip(&geo, "Point", &[1, 254, 1, 2]);
+        round_trip(&geo, "SmallRectangle", &[0, 11, 1, 2]);
+        round_trip(
+            &geo,
+            "Rectangle",
+            &[0, 0, 0, 0, 0, 0, 1, 0, 0xff, 0, 0, 0, 0, 0, 0, 0],
+        );
+
+        let constraints = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+        round_trip(
+            &constraints,
+            "Message",
+            &[
+                0x10, b'1', b'2', b'3', b'4', 1, 1, 2, b'A', b'B', 1, b'X', 50,
+            ],
+        );
+        round_trip(&constraints, "Message", &[0x0c, 2, b'h', b'i']);
+
+        let misc = Module::parse(
+            "Misc DEFINITIONS AUTOMATIC TAGS ::= BEGIN
+            Reading ::= CHOICE { id OBJECT IDENTIFIER, flag [APPLICATION 70] BOOLEAN }
+            END",
+        )
+        .unwrap();
+        round_trip(&misc, "Reading", &[0x06, 3, 42, 0x86, 0x48]);
+        round_trip(&misc, "Reading", &[0x7f, 0x46, 0xff]);
+    }
+
+    #[test]
+    fn encode_defaults() {
+        let module = Module::parse(include_str!("../../test-asn/defaults.asn")).unwrap();
+
+        // components with their DEFAULT value are left out
+        let job = [0x80, 1, 7, 2, b'l', b's', 0x40, 0x01, 0xbb];
+        round_trip(&module, "Job", &job);
+
+        let next = seq(vec![("id", integer(8)), ("command", string("cd"))]);
+        let value = seq(vec![
+            ("id", integer(7)),
+            ("command", string("ls")),
+            ("retries", integer(4)),
+            ("next", next),
+        ]);
+        let bytes = value_to_oer_bytes(&module, "Job", &value).unwrap();
+        assert_eq!(
+            vec![0x60, 1, 7, 2, b'l', b's', 4, 0x00, 1, 8, 2, b'c', b'd'],
+            bytes
+        );
+    }
+
+    #[test]
+    fn encode_errors() {
+        let module = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+        let invalid = |path: &str, message: &str| {
+            Err(Error::InvalidValue(Violation {
+                path: path.into(),
+                message: message.into(),
+            }))
+        };
+
+        let reading = |level| {
+            seq(vec![
+                ("code", string("AB")),
+                ("label", string("X")),
+                ("level", integer(level)),
+            ])
+        };
+        let report = |station, readings| {
+            Value::Choice(
+                "report".into(),
+                Box::new(seq(vec![
+                    ("station", string(station)),
+                    ("readings", Value::List(readings)),
+                ])),
+            )
+        };
+
+        assert_eq!(
+            invalid("report.readings[1].level", "101 is out of the range 0..100"),
+            value_to_oer_bytes(
+                &module,
+                "Message",
+                &report("1234", vec![reading(1), reading(101)])
+            )
+        );
+        assert_eq!(
+            invalid("report.station", "size 3 is out of the range 4..4"),
+            value_to_oer_bytes(&module, "Message", &report("123", vec![]))
+        );
+        assert_eq!(
+            invalid("", "unknown alternative `alarm`"),
+            value_to_oer_bytes(
+                &module,
+                "Message",
+                &Value::Choice("alarm".into(), Box::new(Value::Bool(true)))
+            )
+        );
+        assert_eq!(
+            invalid("", "component `code` is missing"),
+            value_to_oer_bytes(&module, "Reading", &seq(vec![]))
+        );
+        assert_eq!(
+            invalid("", "expected a value of type SEQUENCE"),
+            value_to_oer_bytes(&module, "Reading", &integer(1))
+        );
+    }
+}
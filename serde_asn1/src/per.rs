@@ -0,0 +1,2067 @@
+//! Rec.ITU-T X.691
+//! The Packed Encoding Rules (PER), driven by the ASN.1 types and the
+//! PER-visible constraints of a schema. The aligned variant (APER) pads some
+//! fields to start on an octet, while the unaligned variant (UPER) packs
+//! every field into as few bits as it needs.
+
+use asn1_schema::{
+    Component, Constraint, EnumerationItem, Module, Presence, StringKind, Type, TypeKind,
+};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::ops::Range;
+
+use crate::ber::{non_canonical, read_integer, set_of_order};
+use crate::constraints::{component_path, element_path};
+use crate::error::{Error, Result};
+use crate::oer::{
+    object_identifier_arcs, object_identifier_octets, signed_octets, unsigned_octets,
+};
+use crate::value::{
+    check_constraint, component_values, constraint_of, enumeration_item, invalid_value, kind_name,
+    lookup, octets, resolve, unsupported_type,
+};
+use crate::{RangeError, Value, DEFAULT_RECURSION_LIMIT};
+
+/// A variant of PER, which is aligned or unaligned, and basic or canonical.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Variant {
+    /// Whether some fields are padded to start on an octet, as in APER,
+    /// rather than packed into as few bits as they need, as in UPER.
+    aligned: bool,
+    /// Whether each value has a single encoding, in which the elements of a
+    /// SET OF are sorted by their encodings. Decoding fails with
+    /// `Error::NonCanonical` on any other encoding.
+    canonical: bool,
+}
+
+impl Variant {
+    pub(crate) const ALIGNED: Self = Self {
+        aligned: true,
+        canonical: false,
+    };
+    pub(crate) const UNALIGNED: Self = Self {
+        aligned: false,
+        canonical: false,
+    };
+    pub(crate) const CANONICAL_ALIGNED: Self = Self {
+        aligned: true,
+        canonical: true,
+    };
+    pub(crate) const CANONICAL_UNALIGNED: Self = Self {
+        aligned: false,
+        canonical: true,
+    };
+}
+
+/// Rec.ITU-T X.691 11.9.3.8
+/// Lengths of 16K items and more are split into fragments of up to four
+/// times this many items.
+const FRAGMENT_LEN: usize = 16384;
+
+/// Rec.ITU-T X.691 11.9.3.3
+/// Lengths with an upper bound below this are encoded as constrained whole
+/// numbers.
+const CONSTRAINED_LENGTH_LIMIT: u64 = 65536;
+
+/// Decodes a value of the type with the given name in `module` from
+/// aligned PER bytes.
+pub fn value_from_aper_bytes(module: &Module, type_name: &str, input: &[u8]) -> Result<Value> {
+    value_from_per_bytes(module, type_name, input, Variant::ALIGNED)
+}
+
+/// Encodes a value of the type with the given name in `module` as aligned
+/// PER bytes. Components with a DEFAULT value are left out when they have
+/// it.
+pub fn value_to_aper_bytes(module: &Module, type_name: &str, value: &Value) -> Result<Vec<u8>> {
+    value_to_per_bytes(module, type_name, value, Variant::ALIGNED)
+}
+
+/// Decodes a value of the type with the given name in `module` from
+/// unaligned PER bytes.
+pub fn value_from_uper_bytes(module: &Module, type_name: &str, input: &[u8]) -> Result<Value> {
+    value_from_per_bytes(module, type_name, input, Variant::UNALIGNED)
+}
+
+/// Encodes a value of the type with the given name in `module` as unaligned
+/// PER bytes. Components with a DEFAULT value are left out when they have
+/// it.
+pub fn value_to_uper_bytes(module: &Module, type_name: &str, value: &Value) -> Result<Vec<u8>> {
+    value_to_per_bytes(module, type_name, value, Variant::UNALIGNED)
+}
+
+/// Decodes a value of the type with the given name in `module` from
+/// canonical aligned PER bytes, failing with `Error::NonCanonical` on
+/// encodings which the basic variant allows but the canonical one doesn't.
+pub fn value_from_canonical_aper_bytes(
+    module: &Module,
+    type_name: &str,
+    input: &[u8],
+) -> Result<Value> {
+    value_from_per_bytes(module, type_name, input, Variant::CANONICAL_ALIGNED)
+}
+
+/// Encodes a value of the type with the given name in `module` as
+/// canonical aligned PER bytes, which are the single encoding of the value.
+pub fn value_to_canonical_aper_bytes(
+    module: &Module,
+    type_name: &str,
+    value: &Value,
+) -> Result<Vec<u8>> {
+    value_to_per_bytes(module, type_name, value, Variant::CANONICAL_ALIGNED)
+}
+
+/// Decodes a value of the type with the given name in `module` from
+/// canonical unaligned PER bytes, failing with `Error::NonCanonical` on
+/// encodings which the basic variant allows but the canonical one doesn't.
+pub fn value_from_canonical_uper_bytes(
+    module: &Module,
+    type_name: &str,
+    input: &[u8],
+) -> Result<Value> {
+    value_from_per_bytes(module, type_name, input, Variant::CANONICAL_UNALIGNED)
+}
+
+/// Encodes a value of the type with the given name in `module` as
+/// canonical unaligned PER bytes, which are the single encoding of the value.
+pub fn value_to_canonical_uper_bytes(
+    module: &Module,
+    type_name: &str,
+    value: &Value,
+) -> Result<Vec<u8>> {
+    value_to_per_bytes(module, type_name, value, Variant::CANONICAL_UNALIGNED)
+}
+
+fn value_from_per_bytes(
+    module: &Module,
+    type_name: &str,
+    input: &[u8],
+    variant: Variant,
+) -> Result<Value> {
+    let ty = lookup(module, type_name)?;
+
+    decode(module, ty, input, DEFAULT_RECURSION_LIMIT, variant)
+}
+
+fn value_to_per_bytes(
+    module: &Module,
+    type_name: &str,
+    value: &Value,
+    variant: Variant,
+) -> Result<Vec<u8>> {
+    let ty = lookup(module, type_name)?;
+
+    encode(module, ty, value, variant)
+}
+
+/// Decodes a value of the given type with the given variant, which may
+/// nest SEQUENCE, SEQUENCE OF and CHOICE values up to `recursion_limit`
+/// deep.
+pub(crate) fn decode(
+    module: &Module,
+    ty: &Type,
+    input: &[u8],
+    recursion_limit: usize,
+    variant: Variant,
+) -> Result<Value> {
+    let mut decoder = PerDecoder::new(input, recursion_limit, variant);
+    let value = decoder.read_value(module, ty, "")?;
+    decoder.finish("")?;
+
+    Ok(value)
+}
+
+pub(crate) fn encode(
+    module: &Module,
+    ty: &Type,
+    value: &Value,
+    variant: Variant,
+) -> Result<Vec<u8>> {
+    let mut encoder = PerEncoder::new(module, variant);
+    encoder.write_value(ty, value, "")?;
+
+    Ok(encoder.finish())
+}
+
+/// Rec.ITU-T X.691 11.5.6, 11.5.7
+/// How a constrained whole number is encoded, which depends on the number
+/// of values in its range in the aligned variant, while the unaligned one
+/// always uses a bit-field.
+#[derive(Debug, PartialEq)]
+enum WholeNumber {
+    /// A range of a single value, which needs no bits.
+    Empty,
+    /// A bit-field of the given number of bits, for up to 255 values.
+    BitField(usize),
+    /// The given number of octets, aligned, for up to 64K values.
+    Octets(usize),
+    /// The number of octets as a constrained whole number between 1 and the
+    /// given number, followed by the number in that many octets, aligned.
+    Length(usize),
+}
+
+/// The encoding of the whole numbers from 0 to `max`.
+fn whole_number(max: u128, aligned: bool) -> WholeNumber {
+    match max {
+        0 => WholeNumber::Empty,
+        _ if !aligned => WholeNumber::BitField(bits_for(max)),
+        1..=254 => WholeNumber::BitField(bits_for(max)),
+        255 => WholeNumber::Octets(1),
+        256..=65535 => WholeNumber::Octets(2),
+        _ => WholeNumber::Length(unsigned_octets(max).len()),
+    }
+}
+
+/// The number of bits needed for the whole numbers from 0 to `max`.
+fn bits_for(max: u128) -> usize {
+    (128 - max.leading_zeros()) as usize
+}
+
+/// The difference between the ends of a range, which is the largest whole
+/// number its values are encoded as.
+fn span(min: i128, max: i128) -> u128 {
+    max.wrapping_sub(min) as u128
+}
+
+/// The size constraint of a string or SEQUENCE OF as far as PER is
+/// concerned, given whether its size is outside of the root of an
+/// extensible constraint, in which case it is encoded as if it had none.
+fn per_size(constraint: &Constraint, extended: bool) -> Option<(u64, Option<u64>)> {
+    constraint.size.filter(|_| !extended)
+}
+
+/// Whether a value is in the range of a range constraint.
+fn value_in(value: i128, (min, max): (Option<i128>, Option<i128>)) -> bool {
+    min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+}
+
+/// Whether a size is in the range of a size constraint.
+fn size_in(size: usize, (min, max): (u64, Option<u64>)) -> bool {
+    let size = size as u64;
+    size >= min && max.is_none_or(|max| size <= max)
+}
+
+/// Rec.ITU-T X.691 17.6 to 17.8
+/// Whether the octets of an OCTET STRING with the given size constraint
+/// are octet-aligned in the aligned variant, which they aren't if it has a
+/// fixed size of up to two octets.
+fn octets_aligned(size: Option<(u64, Option<u64>)>) -> bool {
+    !matches!(size, Some((min, Some(max))) if min == max && max <= 2)
+}
+
+/// Rec.ITU-T X.691 14.1
+/// The extension additions of an ENUMERATED type and the items of its
+/// root, each in the order of their numbers, which their indexes refer to.
+fn enumeration_order(items: &[EnumerationItem]) -> (Vec<&EnumerationItem>, Vec<&EnumerationItem>) {
+    let (mut additions, mut root): (Vec<&EnumerationItem>, Vec<&EnumerationItem>) =
+        items.iter().partition(|item| item.extension);
+    additions.sort_by_key(|item| item.number);
+    root.sort_by_key(|item| item.number);
+    (additions, root)
+}
+
+/// Rec.ITU-T X.691 30.5
+/// The characters a known-multiplier character string type may hold, and
+/// how each of them is encoded.
+struct Alphabet {
+    /// The permitted characters, sorted.
+    characters: Vec<char>,
+    /// The number of bits for each character.
+    bits: usize,
+    /// Whether a character is encoded as its index in `characters` rather
+    /// than as its code, because the largest code doesn't fit in `bits`.
+    indexed: bool,
+}
+
+impl Alphabet {
+    /// The alphabet of the given kind of string, narrowed by a permitted
+    /// alphabet constraint, or `None` for UTF8String, which isn't a
+    /// known-multiplier type.
+    fn new(kind: StringKind, constraint: &Constraint, aligned: bool) -> Option<Self> {
+        let mut characters: Vec<char> = match kind {
+            StringKind::Utf8 => return None,
+            StringKind::Numeric => " 0123456789".chars().collect(),
+            StringKind::Printable => " '()+,-./0123456789:=?"
+                .chars()
+                .chain('A'..='Z')
+                .chain('a'..='z')
+                .collect(),
+            StringKind::Visible => (' '..='~').collect(),
+            StringKind::Ia5 => ('\0'..='\x7f').collect(),
+        };
+        if let Some(alphabet) = &constraint.alphabet {
+            characters.retain(|c| alphabet.iter().any(|(from, to)| (from..=to).contains(&c)));
+        }
+        characters.sort_unstable();
+
+        // Rec.ITU-T X.691 30.5.2, the aligned variant rounds the bits up to
+        // a power of two
+        let mut bits = bits_for(characters.len().saturating_sub(1) as u128);
+        if aligned && bits > 0 {
+            bits = bits.next_power_of_two();
+        }
+        let largest = characters.last().map_or(0, |&c| u128::from(u32::from(c)));
+        let indexed = bits_for(largest) > bits;
+
+        Some(Self {
+            characters,
+            bits,
+            indexed,
+        })
+    }
+
+    fn encode(&self, c: char) -> Option<u128> {
+        let index = self.characters.binary_search(&c).ok()?;
+        Some(if self.indexed {
+            index as u128
+        } else {
+            u128::from(u32::from(c))
+        })
+    }
+
+    fn decode(&self, value: u128) -> Option<char> {
+        if self.indexed {
+            usize::try_from(value)
+                .ok()
+                .and_then(|index| self.characters.get(index).copied())
+        } else {
+            u32::try_from(value)
+                .ok()
+                .and_then(char::from_u32)
+                .filter(|c| self.characters.binary_search(c).is_ok())
+        }
+    }
+
+    /// Rec.ITU-T X.691 30.5.6 to 30.5.8
+    /// Whether the characters of a string with the given size constraint
+    /// are octet-aligned in the aligned variant, which they are unless they
+    /// fit in 16 bits.
+    fn aligned(&self, size: Option<(u64, Option<u64>)>) -> bool {
+        match size {
+            Some((_, Some(max))) => max.saturating_mul(self.bits as u64) > 16,
+            _ => true,
+        }
+    }
+}
+
+/// Reads values from PER bits.
+struct PerDecoder<'de> {
+    input: &'de [u8],
+    /// The number of bits read.
+    position: usize,
+    variant: Variant,
+    depth: usize,
+    recursion_limit: usize,
+}
+
+impl<'de> PerDecoder<'de> {
+    fn new(input: &'de [u8], recursion_limit: usize, variant: Variant) -> Self {
+        Self {
+            input,
+            position: 0,
+            variant,
+            depth: 0,
+            recursion_limit,
+        }
+    }
+
+    /// Fails with `Error::NonCanonical` if the encoding isn't canonical
+    /// and the variant is.
+    fn require(&self, canonical: bool, path: &str, message: &str) -> Result<()> {
+        if canonical || !self.variant.canonical {
+            Ok(())
+        } else {
+            Err(non_canonical(path, message))
+        }
+    }
+
+    /// Rec.ITU-T X.691 11.1
+    /// Checks that only the padding to a whole number of octets is left,
+    /// where an empty encoding is a single octet.
+    fn finish(&mut self, path: &str) -> Result<()> {
+        if self.position == 0 && !self.input.is_empty() {
+            self.position = 8;
+            self.require(self.input[0] == 0, path, "padding bits are not zero")?;
+        }
+        self.skip_padding(path)?;
+        if self.position / 8 < self.input.len() {
+            Err(Error::TrailingCharacters)
+        } else if self.position / 8 > self.input.len() {
+            Err(Error::Eof)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn read_bit(&mut self) -> Result<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Reads a number of up to 128 bits, most significant bit first.
+    fn read_bits(&mut self, count: usize) -> Result<u128> {
+        if self.input.len() * 8 < self.position + count {
+            return Err(Error::Eof);
+        }
+
+        let mut value = 0;
+        for _ in 0..count {
+            let bit = self.input[self.position / 8] >> (7 - self.position % 8) & 1;
+            value = value << 1 | u128::from(bit);
+            self.position += 1;
+        }
+
+        Ok(value)
+    }
+
+    /// Skips the bits up to the start of the next octet, in the aligned
+    /// variant.
+    fn align(&mut self, path: &str) -> Result<()> {
+        if self.variant.aligned {
+            self.skip_padding(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Skips the bits up to the start of the next octet, which the
+    /// canonical variant requires to be zero.
+    fn skip_padding(&mut self, path: &str) -> Result<()> {
+        let padding = (8 - self.position % 8) % 8;
+        if self.input.len() * 8 < self.position + padding {
+            return Err(Error::Eof);
+        }
+        let bits = self.read_bits(padding)?;
+        self.require(bits == 0, path, "padding bits are not zero")
+    }
+
+    fn read_octets(&mut self, len: usize) -> Result<Vec<u8>> {
+        let bits = len.checked_mul(8).ok_or(Error::LengthOverflow)?;
+        if self.input.len() * 8 < self.position + bits {
+            return Err(Error::Eof);
+        }
+        if self.position.is_multiple_of(8) {
+            let start = self.position / 8;
+            self.position += bits;
+            return Ok(self.input[start..start + len].to_vec());
+        }
+
+        (0..len).map(|_| Ok(self.read_bits(8)? as u8)).collect()
+    }
+
+    /// Runs the given function one level deeper into nested values,
+    /// failing if that goes beyond the recursion limit.
+    fn nested<F, T>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        if self.depth >= self.recursion_limit {
+            return Err(Error::RecursionLimitExceeded);
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+
+        result
+    }
+
+    /// Rec.ITU-T X.691 11.5
+    /// Reads a whole number encoded as its offset from the lower bound of a
+    /// range, which may be larger than `max` in an invalid encoding.
+    fn read_constrained_whole_number(&mut self, max: u128, path: &str) -> Result<u128> {
+        match whole_number(max, self.variant.aligned) {
+            WholeNumber::Empty => Ok(0),
+            WholeNumber::BitField(bits) => self.read_bits(bits),
+            WholeNumber::Octets(octets) => {
+                self.align(path)?;
+                self.read_bits(octets * 8)
+            }
+            WholeNumber::Length(max_octets) => {
+                let len = self.read_constrained_whole_number(max_octets as u128 - 1, path)? + 1;
+                if len > max_octets as u128 {
+                    return Err(Error::InvalidLength);
+                }
+                self.align(path)?;
+                let number = self.read_bits(len as usize * 8)?;
+                self.require(
+                    len == 1 || number >> (8 * (len - 1)) != 0,
+                    path,
+                    "whole number is not encoded in the fewest octets",
+                )?;
+                Ok(number)
+            }
+        }
+    }
+
+    /// Rec.ITU-T X.691 11.6
+    fn read_normally_small_number(&mut self, path: &str) -> Result<usize> {
+        if !self.read_bit()? {
+            return Ok(self.read_bits(6)? as usize);
+        }
+
+        let octets = self.read_unconstrained_octets(path)?;
+        let number = octets.iter().try_fold(0_usize, |number, &b| {
+            number
+                .checked_mul(256)
+                .map(|number| number | usize::from(b))
+                .ok_or(Error::LengthOverflow)
+        })?;
+        self.require(
+            number > 63 && octets.first() != Some(&0),
+            path,
+            "normally small number is not encoded in the fewest bits",
+        )?;
+
+        Ok(number)
+    }
+
+    /// Rec.ITU-T X.691 11.9.3.6 to 11.9.3.8
+    /// Reads an unconstrained length, along with whether it is a fragment
+    /// which more items follow.
+    fn read_unconstrained_length(&mut self, path: &str) -> Result<(usize, bool)> {
+        self.align(path)?;
+        let first = self.read_bits(8)? as usize;
+        match first >> 6 {
+            0b00 | 0b01 => Ok((first, false)),
+            0b10 => {
+                let len = (first & 0x3f) << 8 | self.read_bits(8)? as usize;
+                self.require(
+                    len >= 0x80,
+                    path,
+                    "length is not encoded in the fewest octets",
+                )?;
+                Ok((len, false))
+            }
+            _ => match first & 0x3f {
+                m @ 1..=4 => Ok((m * FRAGMENT_LEN, true)),
+                _ => Err(Error::InvalidLength),
+            },
+        }
+    }
+
+    /// Rec.ITU-T X.691 11.9
+    /// Reads the length determinant of a value with the given size
+    /// constraint, such as a string or a SEQUENCE OF, calling `items` to read
+    /// each run of items the length counts. The items are octet-aligned if
+    /// `aligned`.
+    fn read_items<F>(
+        &mut self,
+        size: Option<(u64, Option<u64>)>,
+        aligned: bool,
+        path: &str,
+        mut items: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&mut Self, usize) -> Result<()>,
+    {
+        match size {
+            Some((min, Some(max))) if max < CONSTRAINED_LENGTH_LIMIT => {
+                let offset = self.read_constrained_whole_number(u128::from(max - min), path)?;
+                if offset > u128::from(max - min) {
+                    return Err(Error::InvalidLength);
+                }
+                let len = (u128::from(min) + offset) as usize;
+                if aligned && len > 0 {
+                    self.align(path)?;
+                }
+                items(self, len)
+            }
+            _ => loop {
+                let (len, more) = self.read_unconstrained_length(path)?;
+                items(self, len)?;
+                if !more {
+                    return Ok(());
+                }
+            },
+        }
+    }
+
+    /// The octets of an unconstrained length octet string, as used for
+    /// unconstrained whole numbers and open types.
+    fn read_unconstrained_octets(&mut self, path: &str) -> Result<Vec<u8>> {
+        let mut octets = vec![];
+        self.read_items(None, true, path, |decoder, len| {
+            octets.extend(decoder.read_octets(len)?);
+            Ok(())
+        })?;
+
+        Ok(octets)
+    }
+
+    fn read_value(&mut self, module: &Module, ty: &Type, path: &str) -> Result<Value> {
+        let constraint = constraint_of(module, ty)?;
+        let resolved = resolve(module, ty)?;
+        let value = match &resolved.kind {
+            // Rec.ITU-T X.691 12
+            TypeKind::Boolean => self.read_bit().map(Value::Bool),
+            TypeKind::Integer { .. } => self.read_integer(&constraint, path).map(Value::Integer),
+            // Rec.ITU-T X.691 24
+            TypeKind::ObjectIdentifier => {
+                let octets = self.read_unconstrained_octets(path)?;
+                object_identifier_arcs(&octets).map(Value::ObjectIdentifier)
+            }
+            TypeKind::CharacterString(kind) => {
+                self.read_string(*kind, &constraint, path).map(Value::Str)
+            }
+            TypeKind::OctetString => self.read_octet_string(&constraint, path).map(Value::Bytes),
+            TypeKind::Enumerated(items) => self.read_enumerated(items, resolved.extensible, path),
+            TypeKind::Sequence(components) => {
+                let components: Vec<&Component> = components.iter().collect();
+                self.nested(|decoder| {
+                    decoder.read_sequence(module, &components, resolved.extensible, path)
+                })
+            }
+            // Rec.ITU-T X.691 21, a SEQUENCE of the components in canonical
+            // order
+            TypeKind::Set(components) => {
+                let order = module.canonical_order(components);
+                let value = self.nested(|decoder| {
+                    decoder.read_sequence(module, &order, resolved.extensible, path)
+                })?;
+                match value {
+                    Value::Seq(mut values) => {
+                        values.sort_by_key(|(name, _)| {
+                            components
+                                .iter()
+                                .position(|component| component.name == *name)
+                        });
+                        Ok(Value::Seq(values))
+                    }
+                    _ => unreachable!("SEQUENCEs are decoded as `Value::Seq`"),
+                }
+            }
+            TypeKind::Choice(alternatives) => self.nested(|decoder| {
+                decoder.read_choice(module, alternatives, resolved.extensible, path)
+            }),
+            // Rec.ITU-T X.691 20
+            TypeKind::SequenceOf(element) => self.nested(|decoder| {
+                decoder.read_sequence_of(module, element, &constraint, false, path)
+            }),
+            // Rec.ITU-T X.691 22
+            TypeKind::SetOf(element) => self.nested(|decoder| {
+                decoder.read_sequence_of(module, element, &constraint, true, path)
+            }),
+            TypeKind::Reference(_) => unreachable!("references are resolved"),
+            _ => Err(unsupported_type(path)),
+        }?;
+        // the constraints PER doesn't enforce by how values are encoded,
+        // such as the SIZE of strings which aren't known-multiplier
+        check_constraint(module, ty, &value, path)?;
+
+        Ok(value)
+    }
+
+    /// Rec.ITU-T X.691 13
+    /// An INTEGER with a range is encoded as a constrained whole number, and
+    /// one with only a lower bound, as in `(0..MAX)`, as a semi-constrained
+    /// whole number, unless the range is extensible and the value is outside
+    /// of it, which an extension bit tells. Otherwise it is a two's
+    /// complement number preceded by its length in octets.
+    fn read_integer(&mut self, constraint: &Constraint, path: &str) -> Result<i128> {
+        let extended = constraint.range.is_some() && constraint.extensible && self.read_bit()?;
+        match constraint.range.filter(|_| !extended) {
+            Some((Some(min), Some(max))) => {
+                let offset = self.read_constrained_whole_number(span(min, max), path)?;
+                let value = i128::try_from(offset)
+                    .ok()
+                    .and_then(|offset| min.checked_add(offset))
+                    .ok_or(Error::IntegerOverflow)?;
+                if value > max {
+                    return Err(invalid_value(
+                        path,
+                        RangeError { value, min, max }.to_string(),
+                    ));
+                }
+                return Ok(value);
+            }
+            // Rec.ITU-T X.691 13.2.6, the offset from the lower bound as an
+            // unsigned number preceded by its length in octets
+            Some((Some(min), None)) => {
+                let octets = self.read_unconstrained_octets(path)?;
+                if octets.is_empty() {
+                    return Err(Error::ExpectedInteger);
+                }
+                self.require(
+                    !matches!(octets[..], [0, _, ..]),
+                    path,
+                    "whole number is not encoded in the fewest octets",
+                )?;
+                return octets
+                    .iter()
+                    .try_fold(0_i128, |offset, &b| {
+                        offset.checked_mul(256).map(|offset| offset | i128::from(b))
+                    })
+                    .and_then(|offset| min.checked_add(offset))
+                    .ok_or(Error::IntegerOverflow);
+            }
+            _ => {}
+        }
+
+        let octets = self.read_unconstrained_octets(path)?;
+        self.require(
+            !matches!(octets[..], [0, b, ..] if b & 0x80 == 0)
+                && !matches!(octets[..], [0xff, b, ..] if b & 0x80 != 0),
+            path,
+            "INTEGER is not encoded in the fewest octets",
+        )?;
+        let value = read_integer(&octets)?;
+        if extended {
+            self.require(
+                constraint.range.is_none_or(|range| !value_in(value, range)),
+                path,
+                "the extension bit is set for a value in the extension root",
+            )?;
+        }
+
+        Ok(value)
+    }
+
+    /// Rec.ITU-T X.691 14
+    /// An item of an ENUMERATED type is encoded as its index among the
+    /// items of the root, as a constrained whole number. In an extensible
+    /// ENUMERATED, an extension bit tells whether the item is an extension
+    /// addition, whose index among the additions is a normally small number.
+    fn read_enumerated(
+        &mut self,
+        items: &[EnumerationItem],
+        extensible: bool,
+        path: &str,
+    ) -> Result<Value> {
+        let (additions, root) = enumeration_order(items);
+        let (index, items) = if extensible && self.read_bit()? {
+            (self.read_normally_small_number(path)? as u128, additions)
+        } else {
+            let max = root.len().saturating_sub(1) as u128;
+            (self.read_constrained_whole_number(max, path)?, root)
+        };
+
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| items.get(index))
+            .map(|item| Value::Enum(item.name.clone()))
+            .ok_or_else(|| invalid_value(path, format!("unknown item index {}", index)))
+    }
+
+    /// Rec.ITU-T X.691 17
+    /// The octets of an OCTET STRING, preceded by their number as a length
+    /// determinant unless the size is fixed.
+    fn read_octet_string(&mut self, constraint: &Constraint, path: &str) -> Result<Vec<u8>> {
+        let extended = constraint.extensible && constraint.size.is_some() && self.read_bit()?;
+        let size = per_size(constraint, extended);
+        let mut octets = vec![];
+        let aligned = self.variant.aligned && octets_aligned(size);
+        self.read_items(size, aligned, path, |decoder, len| {
+            octets.extend(decoder.read_octets(len)?);
+            Ok(())
+        })?;
+        self.check_extended_size(constraint, extended, octets.len(), path)?;
+
+        Ok(octets)
+    }
+
+    /// Rec.ITU-T X.691 30
+    /// The characters of a known-multiplier string type are encoded in as
+    /// few bits as its alphabet needs, while a UTF8String is encoded as its
+    /// UTF-8 octets.
+    fn read_string(
+        &mut self,
+        kind: StringKind,
+        constraint: &Constraint,
+        path: &str,
+    ) -> Result<String> {
+        let alphabet = match Alphabet::new(kind, constraint, self.variant.aligned) {
+            Some(alphabet) => alphabet,
+            None => {
+                let octets = self.read_unconstrained_octets(path)?;
+                return String::from_utf8(octets).map_err(|_| Error::ExpectedString);
+            }
+        };
+
+        let extended = constraint.extensible && constraint.size.is_some() && self.read_bit()?;
+        let size = per_size(constraint, extended);
+        let mut value = String::new();
+        let aligned = self.variant.aligned && alphabet.aligned(size);
+        self.read_items(size, aligned, path, |decoder, len| {
+            for _ in 0..len {
+                let bits = decoder.read_bits(alphabet.bits)?;
+                let c = alphabet.decode(bits).ok_or_else(|| {
+                    invalid_value(path, "character is not in the permitted alphabet")
+                })?;
+                value.push(c);
+            }
+            Ok(())
+        })?;
+        self.check_extended_size(constraint, extended, value.chars().count(), path)?;
+
+        Ok(value)
+    }
+
+    /// The canonical variant only sets the extension bit of a value with
+    /// an extensible size constraint if its size is outside of the root.
+    fn check_extended_size(
+        &self,
+        constraint: &Constraint,
+        extended: bool,
+        size: usize,
+        path: &str,
+    ) -> Result<()> {
+        self.require(
+            !extended || constraint.size.is_none_or(|root| !size_in(size, root)),
+            path,
+            "the extension bit is set for a value in the extension root",
+        )
+    }
+
+    /// Rec.ITU-T X.691 19
+    /// The components of a SEQUENCE are preceded by a bit for each OPTIONAL
+    /// or DEFAULT component, which is set if the component is present. An
+    /// extensible SEQUENCE starts with a bit which is set if any extension
+    /// addition is present, in which case the additions follow the other
+    /// components as open types, after a bit for each telling whether it is
+    /// present.
+    fn read_sequence(
+        &mut self,
+        module: &Module,
+        components: &[&Component],
+        extensible: bool,
+        path: &str,
+    ) -> Result<Value> {
+        let (additions, root): (Vec<&Component>, Vec<&Component>) =
+            components.iter().partition(|component| component.extension);
+        let extended = extensible && self.read_bit()?;
+        let mut preamble = vec![];
+        for component in &root {
+            if component.presence != Presence::Required {
+                preamble.push(self.read_bit()?);
+            }
+        }
+
+        let mut bits = preamble.into_iter();
+        let mut values = vec![];
+        for component in root {
+            let present = match component.presence {
+                Presence::Required => true,
+                _ => bits.next() == Some(true),
+            };
+
+            if present {
+                let value = self.read_component(module, component, path)?;
+                values.push((component.name.clone(), value));
+            } else if let Presence::Default(default) = &component.presence {
+                values.push((component.name.clone(), Value::from(default)));
+            }
+        }
+
+        let present = if extended {
+            self.read_extension_bitmap(path)?
+        } else {
+            vec![]
+        };
+        self.require(
+            !extended || present.contains(&true),
+            path,
+            "the extension bit is set without extension additions",
+        )?;
+        for (index, present) in present.iter().enumerate() {
+            match additions.get(index) {
+                Some(component) if *present => {
+                    let value = self.read_component(module, component, path)?;
+                    values.push((component.name.clone(), value));
+                }
+                // additions this schema doesn't know of are skipped
+                None if *present => {
+                    self.read_unconstrained_octets(path)?;
+                }
+                _ => {}
+            }
+        }
+        for (index, component) in additions.iter().enumerate() {
+            if let (false, Presence::Default(default)) = (
+                present.get(index).copied().unwrap_or_default(),
+                &component.presence,
+            ) {
+                values.push((component.name.clone(), Value::from(default)));
+            }
+        }
+
+        Ok(Value::Seq(values))
+    }
+
+    /// The value of a component which is present, which is an open type if
+    /// it is an extension addition. The canonical variant requires
+    /// components with their DEFAULT value to be left out.
+    fn read_component(
+        &mut self,
+        module: &Module,
+        component: &Component,
+        path: &str,
+    ) -> Result<Value> {
+        let path = component_path(path, &component.name);
+        let value = if component.extension {
+            self.read_open_type(module, &component.ty, &path)?
+        } else {
+            self.read_value(module, &component.ty, &path)?
+        };
+        if let Presence::Default(default) = &component.presence {
+            self.require(
+                value != Value::from(default),
+                &path,
+                "the DEFAULT value is encoded",
+            )?;
+        }
+
+        Ok(value)
+    }
+
+    /// Rec.ITU-T X.691 19.8
+    /// The bits telling which extension additions are present, preceded by
+    /// their number as a normally small length.
+    fn read_extension_bitmap(&mut self, path: &str) -> Result<Vec<bool>> {
+        let mut bits = vec![];
+        if self.read_bit()? {
+            self.read_items(None, false, path, |decoder, len| {
+                for _ in 0..len {
+                    bits.push(decoder.read_bit()?);
+                }
+                Ok(())
+            })?;
+        } else {
+            let len = self.read_bits(6)? + 1;
+            for _ in 0..len {
+                bits.push(self.read_bit()?);
+            }
+        }
+
+        Ok(bits)
+    }
+
+    /// Rec.ITU-T X.691 11.2
+    /// An open type is the complete encoding of a value, as an octet string
+    /// with an unconstrained length.
+    fn read_open_type(&mut self, module: &Module, ty: &Type, path: &str) -> Result<Value> {
+        let octets = self.read_unconstrained_octets(path)?;
+        let mut decoder = PerDecoder::new(&octets, self.recursion_limit, self.variant);
+        decoder.depth = self.depth;
+        let value = decoder.read_value(module, ty, path)?;
+        decoder.finish(path)?;
+
+        Ok(value)
+    }
+
+    /// Rec.ITU-T X.691 23
+    /// A CHOICE is encoded as the index of the chosen alternative, in the
+    /// canonical order of their tags, followed by its value. In an
+    /// extensible CHOICE, an extension bit tells whether the alternative is
+    /// an extension addition, whose index is a normally small number and
+    /// whose value is an open type.
+    fn read_choice(
+        &mut self,
+        module: &Module,
+        alternatives: &[Component],
+        extensible: bool,
+        path: &str,
+    ) -> Result<Value> {
+        let (additions, root): (Vec<&Component>, Vec<&Component>) = module
+            .canonical_order(alternatives)
+            .into_iter()
+            .partition(|alternative| alternative.extension);
+
+        let (alternative, value) = if extensible && self.read_bit()? {
+            let index = self.read_normally_small_number(path)?;
+            let alternative = *additions.get(index).ok_or(Error::InvalidChoiceTag)?;
+            let path = component_path(path, &alternative.name);
+            (
+                alternative,
+                self.read_open_type(module, &alternative.ty, &path)?,
+            )
+        } else {
+            let max = root.len().saturating_sub(1) as u128;
+            let index = self.read_constrained_whole_number(max, path)?;
+            let alternative = *usize::try_from(index)
+                .ok()
+                .and_then(|index| root.get(index))
+                .ok_or(Error::InvalidChoiceTag)?;
+            let path = component_path(path, &alternative.name);
+            (
+                alternative,
+                self.read_value(module, &alternative.ty, &path)?,
+            )
+        };
+
+        Ok(Value::Choice(alternative.name.clone(), Box::new(value)))
+    }
+
+    /// Rec.ITU-T X.691 20, 22
+    /// The elements of a SEQUENCE OF or, if `set`, a SET OF, preceded by
+    /// their number as a length determinant. The canonical variant requires
+    /// the elements of a SET OF to be sorted by their encodings.
+    fn read_sequence_of(
+        &mut self,
+        module: &Module,
+        element: &Type,
+        constraint: &Constraint,
+        set: bool,
+        path: &str,
+    ) -> Result<Value> {
+        let extended = constraint.extensible && constraint.size.is_some() && self.read_bit()?;
+
+        // not preallocated, since the number isn't checked against the
+        // length of the input
+        let mut values = vec![];
+        let size = per_size(constraint, extended);
+        self.read_items(size, false, path, |decoder, len| {
+            for _ in 0..len {
+                let path = element_path(path, values.len());
+                values.push(decoder.read_value(module, element, &path)?);
+            }
+            Ok(())
+        })?;
+        self.check_extended_size(constraint, extended, values.len(), path)?;
+
+        if set && self.variant.canonical {
+            let encodings = values
+                .iter()
+                .map(|value| encode(module, element, value, self.variant))
+                .collect::<Result<Vec<_>>>()?;
+            self.require(
+                encodings
+                    .windows(2)
+                    .all(|pair| set_of_order(&pair[0], &pair[1]) != Ordering::Greater),
+                path,
+                "SET OF elements are not sorted",
+            )?;
+        }
+
+        Ok(Value::List(values))
+    }
+}
+
+/// Writes values as directed by a schema.
+struct PerEncoder<'a> {
+    module: &'a Module,
+    output: Vec<u8>,
+    /// The number of bits written.
+    len: usize,
+    variant: Variant,
+}
+
+impl<'a> PerEncoder<'a> {
+    fn new(module: &'a Module, variant: Variant) -> Self {
+        Self {
+            module,
+            output: vec![],
+            len: 0,
+            variant,
+        }
+    }
+
+    /// Rec.ITU-T X.691 11.1
+    /// The bits written, padded to a whole number of octets, where an
+    /// empty encoding is a single octet.
+    fn finish(mut self) -> Vec<u8> {
+        if self.output.is_empty() {
+            self.output.push(0);
+        }
+
+        self.output
+    }
+
+    fn write_bit(&mut self, bit: bool) {
+        if self.len.is_multiple_of(8) {
+            self.output.push(0);
+        }
+        if bit {
+            *self.output.last_mut().expect("an octet was pushed") |= 0x80 >> (self.len % 8);
+        }
+        self.len += 1;
+    }
+
+    /// Writes the lowest `count` bits of a number, most significant bit
+    /// first.
+    fn write_bits(&mut self, value: u128, count: usize) {
+        for bit in (0..count).rev() {
+            self.write_bit(value >> bit & 1 == 1);
+        }
+    }
+
+    /// Pads the bits written with zero bits up to the start of the next
+    /// octet, in the aligned variant.
+    fn align(&mut self) {
+        if self.variant.aligned {
+            self.len = self.output.len() * 8;
+        }
+    }
+
+    fn write_octets(&mut self, octets: &[u8]) {
+        if self.len.is_multiple_of(8) {
+            self.output.extend_from_slice(octets);
+            self.len += octets.len() * 8;
+        } else {
+            for &octet in octets {
+                self.write_bits(u128::from(octet), 8);
+            }
+        }
+    }
+
+    /// Rec.ITU-T X.691 11.5
+    /// Writes a whole number as its offset from the lower bound of a range
+    /// whose largest offset is `max`.
+    fn write_constrained_whole_number(&mut self, offset: u128, max: u128) {
+        match whole_number(max, self.variant.aligned) {
+            WholeNumber::Empty => {}
+            WholeNumber::BitField(bits) => self.write_bits(offset, bits),
+            WholeNumber::Octets(octets) => {
+                self.align();
+                self.write_bits(offset, octets * 8);
+            }
+            WholeNumber::Length(max_octets) => {
+                let octets = unsigned_octets(offset);
+                self.write_constrained_whole_number(
+                    octets.len() as u128 - 1,
+                    max_octets as u128 - 1,
+                );
+                self.align();
+                self.write_octets(&octets);
+            }
+        }
+    }
+
+    /// Rec.ITU-T X.691 11.6
+    fn write_normally_small_number(&mut self, number: usize) -> Result<()> {
+        if number < 64 {
+            self.write_bit(false);
+            self.write_bits(number as u128, 6);
+            Ok(())
+        } else {
+            self.write_bit(true);
+            self.write_unconstrained_octets(&unsigned_octets(number as u128), "")
+        }
+    }
+
+    /// Rec.ITU-T X.691 11.9
+    /// Writes the length determinant of a value with `len` items and the
+    /// given size constraint, such as a string or a SEQUENCE OF, calling
+    /// `items` to write each run of items the length counts. The items are
+    /// octet-aligned if `aligned`.
+    fn write_items<F>(
+        &mut self,
+        size: Option<(u64, Option<u64>)>,
+        aligned: bool,
+        len: usize,
+        path: &str,
+        mut items: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&mut Self, Range<usize>) -> Result<()>,
+    {
+        if let Some((min, max)) = size.filter(|&size| !size_in(len, size)) {
+            let max = max.map_or_else(|| "MAX".to_string(), |max| max.to_string());
+            return Err(invalid_value(
+                path,
+                format!("size {} is out of the range {}..{}", len, min, max),
+            ));
+        }
+
+        match size {
+            Some((min, Some(max))) if max < CONSTRAINED_LENGTH_LIMIT => {
+                self.write_constrained_whole_number(
+                    len as u128 - u128::from(min),
+                    u128::from(max - min),
+                );
+                if aligned && len > 0 {
+                    self.align();
+                }
+                items(self, 0..len)
+            }
+            // Rec.ITU-T X.691 11.9.3.6 to 11.9.3.8, an unconstrained length
+            // of up to 127 is one octet, of up to 16K - 1 two octets, and
+            // larger ones are split into fragments of 16K to 64K items
+            _ => {
+                let mut start = 0;
+                loop {
+                    let rest = len - start;
+                    self.align();
+                    if rest >= FRAGMENT_LEN {
+                        let m = (rest / FRAGMENT_LEN).min(4);
+                        self.write_bits(0xc0 | m as u128, 8);
+                        items(self, start..start + m * FRAGMENT_LEN)?;
+                        start += m * FRAGMENT_LEN;
+                    } else {
+                        if rest < 0x80 {
+                            self.write_bits(rest as u128, 8);
+                        } else {
+                            self.write_bits(0x8000 | rest as u128, 16);
+                        }
+                        return items(self, start..len);
+                    }
+                }
+            }
+        }
+    }
+
+    /// An octet string with an unconstrained length, as used for
+    /// unconstrained whole numbers and open types.
+    fn write_unconstrained_octets(&mut self, octets: &[u8], path: &str) -> Result<()> {
+        self.write_items(None, true, octets.len(), path, |encoder, range| {
+            encoder.write_octets(&octets[range]);
+            Ok(())
+        })
+    }
+
+    fn write_value(&mut self, ty: &Type, value: &Value, path: &str) -> Result<()> {
+        check_constraint(self.module, ty, value, path)?;
+        let constraint = constraint_of(self.module, ty)?;
+        let resolved = resolve(self.module, ty)?;
+        match (&resolved.kind, value) {
+            // Rec.ITU-T X.691 12
+            (TypeKind::Boolean, Value::Bool(value)) => self.write_bit(*value),
+            (TypeKind::Integer { .. }, Value::Integer(value)) => {
+                self.write_integer(*value, &constraint, path)?;
+            }
+            // Rec.ITU-T X.691 24
+            (TypeKind::ObjectIdentifier, Value::ObjectIdentifier(arcs)) => {
+                let octets = object_identifier_octets(arcs)
+                    .ok_or_else(|| invalid_value(path, "invalid object identifier"))?;
+                self.write_unconstrained_octets(&octets, path)?;
+            }
+            (TypeKind::CharacterString(kind), Value::Str(value)) => {
+                self.write_string(*kind, &constraint, value, path)?;
+            }
+            (TypeKind::OctetString, value) if octets(value).is_some() => {
+                self.write_octet_string(&constraint, &octets(value).unwrap_or_default(), path)?;
+            }
+            (TypeKind::Enumerated(items), Value::Enum(name)) => {
+                self.write_enumerated(items, resolved.extensible, name, path)?;
+            }
+            (TypeKind::Sequence(components), Value::Seq(values)) => {
+                let components: Vec<&Component> = components.iter().collect();
+                self.write_sequence(&components, values, resolved.extensible, path)?;
+            }
+            // Rec.ITU-T X.691 21
+            (TypeKind::Set(components), Value::Seq(values)) => {
+                let components = self.module.canonical_order(components);
+                self.write_sequence(&components, values, resolved.extensible, path)?;
+            }
+            (TypeKind::Choice(alternatives), Value::Choice(name, value)) => {
+                self.write_choice(alternatives, resolved.extensible, name, value, path)?;
+            }
+            // Rec.ITU-T X.691 20, 22
+            (TypeKind::SequenceOf(element) | TypeKind::SetOf(element), Value::List(values)) => {
+                let order = if matches!(resolved.kind, TypeKind::SetOf(_)) && self.variant.canonical
+                {
+                    self.set_of_order(element, values, path)?
+                } else {
+                    (0..values.len()).collect()
+                };
+                let extended = constraint.extensible
+                    && constraint
+                        .size
+                        .is_some_and(|size| !size_in(values.len(), size));
+                if constraint.extensible && constraint.size.is_some() {
+                    self.write_bit(extended);
+                }
+                let size = per_size(&constraint, extended);
+                self.write_items(size, false, values.len(), path, |encoder, range| {
+                    for &index in &order[range] {
+                        encoder.write_value(element, &values[index], &element_path(path, index))?;
+                    }
+                    Ok(())
+                })?;
+            }
+            (kind, _) => {
+                return Err(invalid_value(
+                    path,
+                    format!("expected a value of type {}", kind_name(kind)),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rec.ITU-T X.691 13
+    fn write_integer(&mut self, value: i128, constraint: &Constraint, path: &str) -> Result<()> {
+        // a value outside of a range without an extension marker has been
+        // rejected when its constraint was checked
+        if let Some(range) = constraint.range {
+            let in_range = value_in(value, range);
+            if constraint.extensible {
+                self.write_bit(!in_range);
+            }
+            match range {
+                (Some(min), Some(max)) if in_range => {
+                    self.write_constrained_whole_number(span(min, value), span(min, max));
+                    return Ok(());
+                }
+                // Rec.ITU-T X.691 13.2.6
+                (Some(min), None) if in_range => {
+                    return self
+                        .write_unconstrained_octets(&unsigned_octets(span(min, value)), path)
+                }
+                _ => {}
+            }
+        }
+
+        self.write_unconstrained_octets(&signed_octets(value), path)
+    }
+
+    /// Rec.ITU-T X.691 14
+    fn write_enumerated(
+        &mut self,
+        items: &[EnumerationItem],
+        extensible: bool,
+        name: &str,
+        path: &str,
+    ) -> Result<()> {
+        let item = enumeration_item(items, name, path)?;
+        let (additions, root) = enumeration_order(items);
+        if let Some(index) = root.iter().position(|root| root.name == item.name) {
+            if extensible {
+                self.write_bit(false);
+            }
+            self.write_constrained_whole_number(index as u128, root.len() as u128 - 1);
+            Ok(())
+        } else {
+            // the item is an extension addition
+            let index = additions
+                .iter()
+                .position(|addition| addition.name == item.name)
+                .unwrap_or_default();
+            self.write_bit(true);
+            self.write_normally_small_number(index)
+        }
+    }
+
+    /// Rec.ITU-T X.691 17
+    fn write_octet_string(
+        &mut self,
+        constraint: &Constraint,
+        octets: &[u8],
+        path: &str,
+    ) -> Result<()> {
+        let extended = constraint.extensible
+            && constraint
+                .size
+                .is_some_and(|size| !size_in(octets.len(), size));
+        if constraint.extensible && constraint.size.is_some() {
+            self.write_bit(extended);
+        }
+        let size = per_size(constraint, extended);
+        self.write_items(
+            size,
+            self.variant.aligned && octets_aligned(size),
+            octets.len(),
+            path,
+            |encoder, range| {
+                encoder.write_octets(&octets[range]);
+                Ok(())
+            },
+        )
+    }
+
+    /// Rec.ITU-T X.691 30
+    fn write_string(
+        &mut self,
+        kind: StringKind,
+        constraint: &Constraint,
+        value: &str,
+        path: &str,
+    ) -> Result<()> {
+        let alphabet = match Alphabet::new(kind, constraint, self.variant.aligned) {
+            Some(alphabet) => alphabet,
+            None => return self.write_unconstrained_octets(value.as_bytes(), path),
+        };
+
+        let characters = value
+            .chars()
+            .map(|c| alphabet.encode(c))
+            .collect::<Option<Vec<u128>>>()
+            .ok_or_else(|| invalid_value(path, "character is not in the permitted alphabet"))?;
+        let extended = constraint.extensible
+            && constraint
+                .size
+                .is_some_and(|size| !size_in(characters.len(), size));
+        if constraint.extensible && constraint.size.is_some() {
+            self.write_bit(extended);
+        }
+        let size = per_size(constraint, extended);
+        self.write_items(
+            size,
+            self.variant.aligned && alphabet.aligned(size),
+            characters.len(),
+            path,
+            |encoder, range| {
+                for &c in &characters[range] {
+                    encoder.write_bits(c, alphabet.bits);
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Rec.ITU-T X.691 19
+    fn write_sequence(
+        &mut self,
+        components: &[&Component],
+        values: &[(String, Value)],
+        extensible: bool,
+        path: &str,
+    ) -> Result<()> {
+        let mut present = vec![];
+        let mut additions = vec![];
+        let mut preamble = vec![];
+        for (component, value) in component_values(components, values, path)? {
+            if component.extension {
+                additions.push((component, value));
+                continue;
+            }
+            if component.presence != Presence::Required {
+                preamble.push(value.is_some());
+            }
+            if let Some(value) = value {
+                present.push((component, value));
+            }
+        }
+
+        let extended = additions.iter().any(|(_, value)| value.is_some());
+        if extensible {
+            self.write_bit(extended);
+        }
+        for bit in preamble {
+            self.write_bit(bit);
+        }
+        for (component, value) in present {
+            self.write_value(&component.ty, value, &component_path(path, &component.name))?;
+        }
+
+        if extended {
+            let bitmap: Vec<bool> = additions.iter().map(|(_, value)| value.is_some()).collect();
+            self.write_extension_bitmap(&bitmap, path)?;
+            for (component, value) in additions {
+                if let Some(value) = value {
+                    let path = component_path(path, &component.name);
+                    self.write_open_type(&component.ty, value, &path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rec.ITU-T X.691 19.8
+    fn write_extension_bitmap(&mut self, bits: &[bool], path: &str) -> Result<()> {
+        if bits.len() <= 64 {
+            self.write_bit(false);
+            self.write_bits(bits.len() as u128 - 1, 6);
+            for &bit in bits {
+                self.write_bit(bit);
+            }
+            Ok(())
+        } else {
+            self.write_bit(true);
+            self.write_items(None, false, bits.len(), path, |encoder, range| {
+                for &bit in &bits[range] {
+                    encoder.write_bit(bit);
+                }
+                Ok(())
+            })
+        }
+    }
+
+    /// Rec.ITU-T X.691 22.1
+    /// The indices of the elements of a SET OF in the order of their
+    /// encodings, in which the canonical variant writes them.
+    fn set_of_order(&self, element: &Type, values: &[Value], path: &str) -> Result<Vec<usize>> {
+        let encodings = values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let mut encoder = PerEncoder::new(self.module, self.variant);
+                encoder.write_value(element, value, &element_path(path, index))?;
+                Ok(encoder.finish())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        order.sort_by(|a, b| set_of_order(&encodings[*a], &encodings[*b]));
+
+        Ok(order)
+    }
+
+    /// Rec.ITU-T X.691 11.2
+    fn write_open_type(&mut self, ty: &Type, value: &Value, path: &str) -> Result<()> {
+        let mut encoder = PerEncoder::new(self.module, self.variant);
+        encoder.write_value(ty, value, path)?;
+        self.write_unconstrained_octets(&encoder.finish(), path)
+    }
+
+    /// Rec.ITU-T X.691 23
+    fn write_choice(
+        &mut self,
+        alternatives: &[Component],
+        extensible: bool,
+        name: &str,
+        value: &Value,
+        path: &str,
+    ) -> Result<()> {
+        let (additions, root): (Vec<&Component>, Vec<&Component>) = self
+            .module
+            .canonical_order(alternatives)
+            .into_iter()
+            .partition(|alternative| alternative.extension);
+
+        if let Some(index) = root.iter().position(|alternative| alternative.name == name) {
+            if extensible {
+                self.write_bit(false);
+            }
+            let max = root.len() as u128 - 1;
+            self.write_constrained_whole_number(index as u128, max);
+            self.write_value(&root[index].ty, value, &component_path(path, name))
+        } else if let Some(index) = additions
+            .iter()
+            .position(|alternative| alternative.name == name)
+        {
+            self.write_bit(true);
+            self.write_normally_small_number(index)?;
+            let path = component_path(path, name);
+            self.write_open_type(&additions[index].ty, value, &path)
+        } else {
+            Err(invalid_value(
+                path,
+                format!("unknown alternative `{}`", name),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        value_from_aper_bytes, value_from_canonical_aper_bytes, value_from_canonical_uper_bytes,
+        value_from_uper_bytes, value_to_aper_bytes, value_to_canonical_aper_bytes,
+        value_to_canonical_uper_bytes, value_to_uper_bytes, whole_number, WholeNumber,
+    };
+    use crate::value::helpers::{integer, seq, string};
+    use crate::{Error, Value, Violation};
+    use asn1_schema::Module;
+
+    fn round_trip(module: &Module, type_name: &str, value: &Value, bytes: &[u8]) {
+        assert_eq!(
+            bytes.to_vec(),
+            value_to_aper_bytes(module, type_name, value).unwrap()
+        );
+        assert_eq!(
+            *value,
+            value_from_aper_bytes(module, type_name, bytes).unwrap()
+        );
+    }
+
+    fn uper_round_trip(module: &Module, type_name: &str, value: &Value, bytes: &[u8]) {
+        assert_eq!(
+            bytes.to_vec(),
+            value_to_uper_bytes(module, type_name, value).unwrap()
+        );
+        assert_eq!(
+            *value,
+            value_from_uper_bytes(module, type_name, bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn whole_numbers() {
+        assert_eq!(WholeNumber::Empty, whole_number(0, true));
+        assert_eq!(WholeNumber::BitField(1), whole_number(1, true));
+        assert_eq!(WholeNumber::BitField(8), whole_number(254, true));
+        assert_eq!(WholeNumber::Octets(1), whole_number(255, true));
+        assert_eq!(WholeNumber::Octets(2), whole_number(256, true));
+        assert_eq!(WholeNumber::Octets(2), whole_number(65535, true));
+        assert_eq!(WholeNumber::Length(3), whole_number(65536, true));
+        assert_eq!(WholeNumber::Length(8), whole_number(u64::MAX.into(), true));
+
+        assert_eq!(WholeNumber::Empty, whole_number(0, false));
+        assert_eq!(WholeNumber::BitField(8), whole_number(255, false));
+        assert_eq!(WholeNumber::BitField(17), whole_number(65536, false));
+    }
+
+    #[test]
+    fn geo() {
+        let module = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+
+        let point = seq(vec![("x", integer(-2)), ("y", integer(2))]);
+        round_trip(&module, "Point", &point, &[1, 0xfe, 1, 2]);
+
+        let tiny = seq(vec![("width", integer(11)), ("height", integer(200))]);
+        round_trip(&module, "TinyRectangle", &tiny, &[11, 200]);
+
+        let small = seq(vec![("width", integer(11)), ("height", integer(258))]);
+        round_trip(&module, "SmallRectangle", &small, &[0, 11, 1, 2]);
+
+        // the number of octets of each is a 3 bit field, followed by the
+        // octets aligned
+        let large = seq(vec![("width", integer(1)), ("height", integer(256))]);
+        round_trip(&module, "Rectangle", &large, &[0, 1, 0x20, 1, 0]);
+    }
+
+    #[test]
+    fn constraints() {
+        let module = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+
+        // report is the second alternative in canonical order, the station
+        // is four 4 bit indexes in 16 bits, which aren't aligned, and the
+        // code, label and level follow the sizes of the readings and code
+        let reading = seq(vec![
+            ("code", string("AB")),
+            ("label", string("X")),
+            ("level", integer(50)),
+        ]);
+        let report = Value::Choice(
+            "report".into(),
+            Box::new(seq(vec![
+                ("station", string("1234")),
+                ("readings", Value::List(vec![reading])),
+            ])),
+        );
+        round_trip(
+            &module,
+            "Message",
+            &report,
+            &[0x91, 0xa2, 0x80, b'A', b'B', 1, b'X', 0x64],
+        );
+
+        let note = Value::Choice("note".into(), Box::new(string("hi")));
+        round_trip(&module, "Message", &note, &[0, 2, b'h', b'i']);
+
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "report.station".into(),
+                message: "character 'a' is not permitted".into(),
+            })),
+            value_to_aper_bytes(
+                &module,
+                "Message",
+                &Value::Choice(
+                    "report".into(),
+                    Box::new(seq(vec![
+                        ("station", string("12a4")),
+                        ("readings", Value::List(vec![])),
+                    ]))
+                )
+            )
+        );
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: "101 is out of the range 0..100".into(),
+            })),
+            value_to_aper_bytes(&module, "Percent", &integer(101))
+        );
+
+        // the count has no upper bound, so it is a semi-constrained whole
+        // number, its offset from 0 preceded by its length in octets, and
+        // the balance has no lower bound, so it is unconstrained
+        let tally = seq(vec![("count", integer(300)), ("balance", integer(-1))]);
+        round_trip(&module, "Tally", &tally, &[2, 1, 0x2c, 1, 0xff]);
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "count".into(),
+                message: "-1 is out of the range 0..MAX".into(),
+            })),
+            value_to_aper_bytes(
+                &module,
+                "Tally",
+                &seq(vec![("count", integer(-1)), ("balance", integer(0))])
+            )
+        );
+    }
+
+    #[test]
+    fn defaults_and_sets() {
+        let module = Module::parse(include_str!("../../test-asn/defaults.asn")).unwrap();
+
+        // settings is present with only a port, retries and next are absent
+        let job = [0x80, 1, 7, 2, b'l', b's', 0x40, 0x01, 0xbb];
+        let value = value_from_aper_bytes(&module, "Job", &job).unwrap();
+        assert_eq!(
+            Some(&integer(443)),
+            value
+                .get("settings")
+                .and_then(|settings| settings.get("port"))
+        );
+        assert_eq!(Some(&integer(3)), value.get("retries"));
+        assert_eq!(
+            job.to_vec(),
+            value_to_aper_bytes(&module, "Job", &value).unwrap()
+        );
+
+        // the components of a SET are in the canonical order of their tags
+        let module = Module::parse(include_str!("../../test-asn/sets.asn")).unwrap();
+        let attribute = seq(vec![("type", integer(1)), ("value", string("a"))]);
+        let entry = seq(vec![
+            ("name", Value::List(vec![attribute])),
+            ("serial", integer(5)),
+            ("active", Value::Bool(false)),
+        ]);
+        round_trip(&module, "Entry", &entry, &[0x80, 1, 5, 1, 1, 1, 1, b'a']);
+    }
+
+    #[test]
+    fn extensions() {
+        let module = Module::parse(include_str!("../../test-asn/extensions.asn")).unwrap();
+
+        round_trip(&module, "Level", &integer(3), &[0x30]);
+        round_trip(&module, "Level", &integer(300), &[0x80, 2, 1, 0x2c]);
+
+        // the extension bit and name bit, the aligned id, the name size in
+        // 4 bits, the number of additions and their bits, and the level as
+        // an open type
+        let setup = seq(vec![
+            ("id", integer(1)),
+            ("name", string("ab")),
+            ("level", integer(300)),
+        ]);
+        round_trip(
+            &module,
+            "Setup",
+            &setup,
+            &[0xc0, 0, 1, 0x08, b'a', b'b', 0x03, 0, 4, 0x80, 2, 1, 0x2c],
+        );
+
+        let setup = seq(vec![("id", integer(1))]);
+        round_trip(&module, "Setup", &setup, &[0, 0, 1]);
+
+        // a third addition from a later version is skipped
+        assert_eq!(
+            setup,
+            value_from_aper_bytes(&module, "Setup", &[0x80, 0, 1, 0x04, 0x40, 1, 0]).unwrap()
+        );
+
+        let reset = Value::Choice("reset".into(), Box::new(Value::Bool(true)));
+        round_trip(&module, "Procedure", &reset, &[0x60]);
+        let release = Value::Choice("release".into(), Box::new(integer(5)));
+        round_trip(&module, "Procedure", &release, &[0x80, 1, 5]);
+        assert_eq!(
+            Err(Error::InvalidChoiceTag),
+            value_from_aper_bytes(&module, "Procedure", &[0x81, 1, 5])
+        );
+    }
+
+    #[test]
+    fn unaligned() {
+        let module = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+
+        let small = seq(vec![("width", integer(11)), ("height", integer(258))]);
+        uper_round_trip(&module, "SmallRectangle", &small, &[0, 11, 1, 2]);
+
+        // a range of 2^64 values is a 64 bit field
+        let large = seq(vec![("width", integer(1)), ("height", integer(256))]);
+        uper_round_trip(
+            &module,
+            "Rectangle",
+            &large,
+            &[0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0],
+        );
+
+        // the code has 36 characters, which are 6 bit indexes, and the label
+        // is a PrintableString of 7 bit characters
+        let module = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+        let reading = seq(vec![
+            ("code", string("AB")),
+            ("label", string("X")),
+            ("level", integer(50)),
+        ]);
+        let report = Value::Choice(
+            "report".into(),
+            Box::new(seq(vec![
+                ("station", string("1234")),
+                ("readings", Value::List(vec![reading])),
+            ])),
+        );
+        uper_round_trip(
+            &module,
+            "Message",
+            &report,
+            &[0x91, 0xa2, 0x81, 0x45, 0x80, 0xd8, 0x64],
+        );
+        let note = Value::Choice("note".into(), Box::new(string("hi")));
+        uper_round_trip(&module, "Message", &note, &[0x01, 0x34, 0x34, 0x80]);
+        let tally = seq(vec![("count", integer(300)), ("balance", integer(-1))]);
+        uper_round_trip(&module, "Tally", &tally, &[2, 1, 0x2c, 1, 0xff]);
+
+        let module = Module::parse(include_str!("../../test-asn/sets.asn")).unwrap();
+        let attribute = seq(vec![("type", integer(1)), ("value", string("a"))]);
+        let entry = seq(vec![
+            ("name", Value::List(vec![attribute])),
+            ("serial", integer(5)),
+            ("active", Value::Bool(false)),
+        ]);
+        uper_round_trip(
+            &module,
+            "Entry",
+            &entry,
+            &[0x80, 0x20, 0xa0, 0x20, 0x20, 0x20, 0x2c, 0x20],
+        );
+
+        // the open type of the level is padded to whole octets, but isn't
+        // aligned
+        let module = Module::parse(include_str!("../../test-asn/extensions.asn")).unwrap();
+        uper_round_trip(&module, "Level", &integer(300), &[0x81, 0, 0x96, 0]);
+        let setup = seq(vec![
+            ("id", integer(1)),
+            ("name", string("ab")),
+            ("level", integer(300)),
+        ]);
+        uper_round_trip(
+            &module,
+            "Setup",
+            &setup,
+            &[0xc0, 0, 0x43, 0x87, 0x10, 0x18, 0x12, 0x04, 0x02, 0x58, 0],
+        );
+        let setup = seq(vec![("id", integer(1))]);
+        uper_round_trip(&module, "Setup", &setup, &[0, 0, 0x40]);
+    }
+
+    #[test]
+    fn fragments() {
+        let module = Module::parse(include_str!("../../test-asn/defaults.asn")).unwrap();
+
+        // 16K octets, then the rest after a length of two octets
+        let name = string(&"a".repeat(20000));
+        let bytes = value_to_aper_bytes(&module, "Name", &name).unwrap();
+        assert_eq!(20003, bytes.len());
+        assert_eq!(0xc1, bytes[0]);
+        assert_eq!([0x8e, 0x20], bytes[16385..16387]);
+        assert_eq!(
+            name,
+            value_from_aper_bytes(&module, "Name", &bytes).unwrap()
+        );
+
+        // a multiple of 16K ends with an empty fragment
+        let name = string(&"a".repeat(32768));
+        let bytes = value_to_aper_bytes(&module, "Name", &name).unwrap();
+        assert_eq!(32770, bytes.len());
+        assert_eq!((0xc2, 0), (bytes[0], bytes[32769]));
+        assert_eq!(
+            name,
+            value_from_aper_bytes(&module, "Name", &bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn canonical() {
+        let module = Module::parse(include_str!("../../test-asn/sets.asn")).unwrap();
+        let attribute =
+            |r#type, value| seq(vec![("type", integer(r#type)), ("value", string(value))]);
+        let entry = seq(vec![
+            (
+                "name",
+                Value::List(vec![attribute(2, "b"), attribute(1, "a")]),
+            ),
+            ("serial", integer(5)),
+            ("active", Value::Bool(true)),
+        ]);
+
+        // the attributes are sorted by their encodings
+        let unsorted = [0, 1, 5, 2, 1, 2, 1, b'b', 1, 1, 1, b'a'];
+        let sorted = [0, 1, 5, 2, 1, 1, 1, b'a', 1, 2, 1, b'b'];
+        assert_eq!(
+            unsorted.to_vec(),
+            value_to_aper_bytes(&module, "Entry", &entry).unwrap()
+        );
+        assert_eq!(
+            sorted.to_vec(),
+            value_to_canonical_aper_bytes(&module, "Entry", &entry).unwrap()
+        );
+        assert!(value_from_canonical_aper_bytes(&module, "Entry", &sorted).is_ok());
+        assert!(value_from_aper_bytes(&module, "Entry", &unsorted).is_ok());
+        assert_eq!(
+            Err(Error::NonCanonical(Violation {
+                path: "name".into(),
+                message: "SET OF elements are not sorted".into(),
+            })),
+            value_from_canonical_aper_bytes(&module, "Entry", &unsorted)
+        );
+
+        let uper_bytes = value_to_canonical_uper_bytes(&module, "Entry", &entry).unwrap();
+        let value = value_from_canonical_uper_bytes(&module, "Entry", &uper_bytes).unwrap();
+        assert_eq!(
+            Some(&Value::List(vec![attribute(1, "a"), attribute(2, "b")])),
+            value.get("name")
+        );
+
+        // retries is encoded with its DEFAULT value
+        let module = Module::parse(include_str!("../../test-asn/defaults.asn")).unwrap();
+        let job = [0x40, 1, 1, 2, b'l', b's', 0x30];
+        assert_eq!(
+            Some(&integer(3)),
+            value_from_aper_bytes(&module, "Job", &job)
+                .unwrap()
+                .get("retries")
+        );
+        assert_eq!(
+            Err(Error::NonCanonical(Violation {
+                path: "retries".into(),
+                message: "the DEFAULT value is encoded".into(),
+            })),
+            value_from_canonical_aper_bytes(&module, "Job", &job)
+        );
+
+        // the padding after the preamble is not zero
+        let job = [0x01, 1, 1, 2, b'l', b's'];
+        assert!(value_from_aper_bytes(&module, "Job", &job).is_ok());
+        assert_eq!(
+            Err(Error::NonCanonical(Violation {
+                path: "id".into(),
+                message: "padding bits are not zero".into(),
+            })),
+            value_from_canonical_aper_bytes(&module, "Job", &job)
+        );
+
+        // a Level within the root with the extension bit set
+        let module = Module::parse(include_str!("../../test-asn/extensions.asn")).unwrap();
+        let level = [0x80, 1, 3];
+        assert_eq!(
+            Ok(integer(3)),
+            value_from_aper_bytes(&module, "Level", &level)
+        );
+        assert_eq!(
+            Err(Error::NonCanonical(Violation {
+                path: "".into(),
+                message: "the extension bit is set for a value in the extension root".into(),
+            })),
+            value_from_canonical_aper_bytes(&module, "Level", &level)
+        );
+
+        // a semi-constrained whole number with a leading zero octet
+        let module = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+        let count = [2, 0, 5];
+        assert_eq!(
+            Ok(integer(5)),
+            value_from_aper_bytes(&module, "Count", &count)
+        );
+        assert_eq!(
+            Err(Error::NonCanonical(Violation {
+                path: "".into(),
+                message: "whole number is not encoded in the fewest octets".into(),
+            })),
+            value_from_canonical_aper_bytes(&module, "Count", &count)
+        );
+    }
+
+    #[test]
+    fn files() {
+        let module = Module::parse(include_str!("../../test-asn/files.asn")).unwrap();
+
+        // the kind is the index of directory among the root items, after
+        // the extension bit, and the digest of a fixed size has no length
+        let file = seq(vec![
+            ("name", string("a")),
+            ("kind", Value::Enum("directory".into())),
+            ("digest", Value::Bytes(vec![1, 2, 3, 4])),
+            ("contents", Value::Bytes(vec![0xff])),
+        ]);
+        round_trip(
+            &module,
+            "File",
+            &file,
+            &[0x80, 1, b'a', 0x20, 1, 2, 3, 4, 1, 0xff],
+        );
+        uper_round_trip(
+            &module,
+            "File",
+            &file,
+            &[0x80, 0xb0, 0x90, 0x10, 0x20, 0x30, 0x40, 0x1f, 0xf0],
+        );
+
+        // an extension addition is the extension bit followed by its index
+        // among the additions as a normally small number
+        round_trip(&module, "Kind", &Value::Enum("device".into()), &[0x80]);
+
+        // octets of a fixed size of up to two octets aren't aligned
+        let module = Module::parse(
+            "Codes DEFINITIONS AUTOMATIC TAGS ::= BEGIN
+            Code ::= SEQUENCE { flag BOOLEAN, code OCTET STRING (SIZE(2)) }
+            END",
+        )
+        .unwrap();
+        let code = seq(vec![
+            ("flag", Value::Bool(true)),
+            ("code", Value::Bytes(vec![0xab, 0xcd])),
+        ]);
+        round_trip(&module, "Code", &code, &[0xd5, 0xe6, 0x80]);
+    }
+
+    #[test]
+    fn decode_errors() {
+        // the SIZE and FROM constraints of UTF8String aren't PER-visible, so
+        // they aren't enforced by how values are encoded
+        let module = Module::parse(
+            "Checked DEFINITIONS ::= BEGIN
+            Short ::= UTF8String (SIZE(1..3))
+            Digits ::= UTF8String (FROM(\"0\"..\"9\"))
+            END",
+        )
+        .unwrap();
+        fn invalid<T>(message: &str) -> Result<T, Error> {
+            Err(Error::InvalidValue(Violation {
+                path: "".into(),
+                message: message.into(),
+            }))
+        }
+        let short = [6, b'a', b'b', b'c', b'd', b'e', b'f'];
+        let digits = [1, b'x'];
+
+        assert_eq!(
+            invalid("size 6 is out of the range 1..3"),
+            value_from_aper_bytes(&module, "Short", &short)
+        );
+        assert_eq!(
+            invalid("size 6 is out of the range 1..3"),
+            value_from_uper_bytes(&module, "Short", &short)
+        );
+        assert_eq!(
+            invalid("character 'x' is not permitted"),
+            value_from_aper_bytes(&module, "Digits", &digits)
+        );
+        assert_eq!(
+            invalid("character 'x' is not permitted"),
+            value_from_uper_bytes(&module, "Digits", &digits)
+        );
+
+        // and neither are they when encoding
+        assert_eq!(
+            invalid("size 6 is out of the range 1..3"),
+            value_to_aper_bytes(&module, "Short", &string("abcdef"))
+        );
+        assert_eq!(
+            invalid("character 'x' is not permitted"),
+            value_to_uper_bytes(&module, "Digits", &string("x"))
+        );
+
+        let module = Module::parse(include_str!("../../test-asn/constraints.asn")).unwrap();
+        let mut note = vec![0, 17];
+        note.extend_from_slice(b"seventeen letters");
+        assert_eq!(
+            Err(Error::InvalidValue(Violation {
+                path: "note".into(),
+                message: "size 17 is out of the range 0..16".into(),
+            })),
+            value_from_aper_bytes(&module, "Message", &note)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let module = Module::parse(include_str!("../../test-asn/geo.asn")).unwrap();
+
+        assert_eq!(
+            Err(Error::UnknownType("Circle".into())),
+            value_from_aper_bytes(&module, "Circle", &[])
+        );
+        assert_eq!(
+            Err(Error::TrailingCharacters),
+            value_from_aper_bytes(&module, "TinyRectangle", &[1, 2, 3])
+        );
+        assert_eq!(
+            Err(Error::Eof),
+            value_from_aper_bytes(&module, "Point", &[1, 254, 2, 0])
+        );
+    }
+}
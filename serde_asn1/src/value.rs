@@ -0,0 +1,377 @@
+//! A value of any ASN.1 type, for working with types which aren't known
+//! until runtime. Generated types are coded by converting them to and from
+//! values.
+
+use asn1_schema::{Component, Constraint, EnumerationItem, Module, Presence, Type, TypeKind};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+use crate::constraints::{check_alphabet, check_range, check_size};
+use crate::{Error, Violation};
+
+mod de;
+mod ser;
+
+pub use de::from_value;
+pub use ser::to_value;
+
+/// A value of an ASN.1 type, as decoded with a schema rather than into a
+/// generated type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Integer(i128),
+    /// The arcs of an OBJECT IDENTIFIER, starting from the root.
+    ObjectIdentifier(Vec<u64>),
+    /// A value of one of the character string types.
+    Str(String),
+    /// The octets of an OCTET STRING.
+    Bytes(Vec<u8>),
+    /// The identifier of an item of an ENUMERATED type.
+    Enum(String),
+    /// The components of a SEQUENCE or SET, in the order they are defined in.
+    /// Absent OPTIONAL components are left out, while absent components
+    /// with a DEFAULT value are given it.
+    Seq(Vec<(String, Value)>),
+    /// The name and value of the chosen alternative of a CHOICE.
+    Choice(String, Box<Value>),
+    /// The elements of a SEQUENCE OF or SET OF.
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// The component of a SEQUENCE or SET value with the given name, if the
+    /// value is one which has it.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Seq(components) => components
+                .iter()
+                .find(|(component, _)| component == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+impl From<&asn1_schema::Value> for Value {
+    fn from(value: &asn1_schema::Value) -> Self {
+        match value {
+            asn1_schema::Value::Boolean(value) => Value::Bool(*value),
+            asn1_schema::Value::Integer(value) => Value::Integer(*value),
+            asn1_schema::Value::ObjectIdentifier(arcs) => Value::ObjectIdentifier(arcs.clone()),
+            asn1_schema::Value::String(value) => Value::Str(value.clone()),
+            asn1_schema::Value::OctetString(octets) => Value::Bytes(octets.clone()),
+            asn1_schema::Value::Enumerated(name) => Value::Enum(name.clone()),
+        }
+    }
+}
+
+/// The octets of an OCTET STRING value. Values converted from generated
+/// types have them as a list of integers, since serde serializes a
+/// `Vec<u8>` as a sequence.
+pub(crate) fn octets(value: &Value) -> Option<Cow<'_, [u8]>> {
+    match value {
+        Value::Bytes(octets) => Some(Cow::Borrowed(octets)),
+        Value::List(values) => values
+            .iter()
+            .map(|value| match value {
+                Value::Integer(value) => u8::try_from(*value).ok(),
+                _ => None,
+            })
+            .collect::<Option<Vec<u8>>>()
+            .map(Cow::Owned),
+        _ => None,
+    }
+}
+
+/// The item of an ENUMERATED type which a value at `path` is the identifier
+/// of.
+pub(crate) fn enumeration_item<'a>(
+    items: &'a [EnumerationItem],
+    name: &str,
+    path: &str,
+) -> Result<&'a EnumerationItem, Error> {
+    items
+        .iter()
+        .find(|item| item.name == name)
+        .ok_or_else(|| invalid_value(path, format!("unknown item `{}`", name)))
+}
+
+/// The value at `path` of the item of an ENUMERATED type with the given
+/// number.
+pub(crate) fn enumerated_value(
+    items: &[EnumerationItem],
+    number: i128,
+    path: &str,
+) -> Result<Value, Error> {
+    items
+        .iter()
+        .find(|item| item.number == number)
+        .map(|item| Value::Enum(item.name.clone()))
+        .ok_or_else(|| invalid_value(path, format!("unknown item number {}", number)))
+}
+
+/// The type with the given name in `module`. Fails if it, or a type it is
+/// made of, refers to a type which isn't defined in the module, which only
+/// a module that wasn't parsed can do, so that coding it can't panic.
+pub(crate) fn lookup<'a>(module: &'a Module, type_name: &str) -> Result<&'a Type, Error> {
+    let assignment = module
+        .get_type(type_name)
+        .ok_or_else(|| Error::UnknownType(type_name.to_string()))?;
+
+    let mut checked = vec![type_name];
+    let mut types = vec![&assignment.ty];
+    while let Some(ty) = types.pop() {
+        match &ty.kind {
+            TypeKind::Reference(name) if !checked.contains(&name.as_str()) => {
+                let referred = module
+                    .get_type(name)
+                    .ok_or_else(|| Error::UnknownType(name.clone()))?;
+                checked.push(name);
+                types.push(&referred.ty);
+            }
+            TypeKind::Sequence(components)
+            | TypeKind::Set(components)
+            | TypeKind::Choice(components) => {
+                types.extend(components.iter().map(|component| &component.ty))
+            }
+            TypeKind::SequenceOf(element) | TypeKind::SetOf(element) => types.push(element),
+            _ => {}
+        }
+    }
+
+    Ok(&assignment.ty)
+}
+
+/// The type a type refers to, see `Module::resolve`. Fails if one of its
+/// references can't be followed, which `lookup` rules out for the types it
+/// returns.
+pub(crate) fn resolve<'a>(module: &'a Module, ty: &'a Type) -> Result<&'a Type, Error> {
+    module.resolve(ty).ok_or_else(|| unknown_type(ty))
+}
+
+/// The constraints which apply to a type, see `Module::constraint_of`.
+pub(crate) fn constraint_of(module: &Module, ty: &Type) -> Result<Constraint, Error> {
+    module.constraint_of(ty).ok_or_else(|| unknown_type(ty))
+}
+
+/// The error of a type whose references can't be followed, named after the
+/// type it refers to.
+fn unknown_type(ty: &Type) -> Error {
+    match &ty.kind {
+        TypeKind::Reference(name) => Error::UnknownType(name.clone()),
+        kind => Error::UnknownType(kind_name(kind).to_string()),
+    }
+}
+
+/// The error for a value which can't be coded as the type at `path`.
+pub(crate) fn invalid_value(path: &str, message: impl Into<String>) -> Error {
+    Error::InvalidValue(Violation {
+        path: path.to_string(),
+        message: message.into(),
+    })
+}
+
+/// The error for a value of a kind of type which was added to the schema
+/// after this crate, so that it can't be coded.
+pub(crate) fn unsupported_type(path: &str) -> Error {
+    invalid_value(path, "values of this type are not supported")
+}
+
+/// Checks that a value at `path` satisfies the range, SIZE and permitted
+/// alphabet constraints of its type, including the alphabet of its kind of
+/// character string, for the encoding rules which don't enforce them by
+/// how values are encoded. The range and SIZE of an extensible constraint
+/// aren't checked, since values outside of them are valid too.
+pub(crate) fn check_constraint(
+    module: &Module,
+    ty: &Type,
+    value: &Value,
+    path: &str,
+) -> Result<(), Error> {
+    let constraint = constraint_of(module, ty)?;
+    let range = constraint.range.filter(|_| !constraint.extensible);
+    let size = constraint.size.filter(|_| !constraint.extensible);
+    let mut violations = vec![];
+    match (&resolve(module, ty)?.kind, value) {
+        (TypeKind::Integer { .. }, Value::Integer(value)) => {
+            if let Some((min, max)) = range {
+                check_range(path, *value, min, max, &mut violations);
+            }
+        }
+        (TypeKind::CharacterString(kind), Value::Str(value)) => {
+            if let Some((min, max)) = size {
+                check_size(path, value.chars().count(), min, max, &mut violations);
+            }
+            if let Some(alphabet) = kind.alphabet() {
+                check_alphabet(path, value, alphabet, &mut violations);
+            }
+            if let Some(alphabet) = &constraint.alphabet {
+                check_alphabet(path, value, alphabet, &mut violations);
+            }
+        }
+        (TypeKind::OctetString, value) => {
+            if let (Some((min, max)), Some(octets)) = (size, octets(value)) {
+                check_size(path, octets.len(), min, max, &mut violations);
+            }
+        }
+        (TypeKind::SequenceOf(_) | TypeKind::SetOf(_), Value::List(values)) => {
+            if let Some((min, max)) = size {
+                check_size(path, values.len(), min, max, &mut violations);
+            }
+        }
+        _ => {}
+    }
+
+    match violations.into_iter().next() {
+        Some(violation) => Err(Error::InvalidValue(violation)),
+        None => Ok(()),
+    }
+}
+
+/// The name of a kind of type, as used in error messages.
+pub(crate) fn kind_name(kind: &TypeKind) -> &'static str {
+    match kind {
+        TypeKind::Boolean => "BOOLEAN",
+        TypeKind::Integer { .. } => "INTEGER",
+        TypeKind::ObjectIdentifier => "OBJECT IDENTIFIER",
+        TypeKind::CharacterString(kind) => kind.name(),
+        TypeKind::OctetString => "OCTET STRING",
+        TypeKind::Enumerated(_) => "ENUMERATED",
+        TypeKind::Sequence(_) => "SEQUENCE",
+        TypeKind::Set(_) => "SET",
+        TypeKind::Choice(_) => "CHOICE",
+        TypeKind::SequenceOf(_) => "SEQUENCE OF",
+        TypeKind::SetOf(_) => "SET OF",
+        TypeKind::Reference(_) => unreachable!("references are resolved"),
+        _ => "unsupported type",
+    }
+}
+
+/// The values of the components of a SEQUENCE or SET which are encoded, in
+/// the order of `components`. Absent components, and those equal to their
+/// DEFAULT value, have none.
+pub(crate) fn component_values<'a>(
+    components: &[&'a Component],
+    values: &'a [(String, Value)],
+    path: &str,
+) -> Result<Vec<(&'a Component, Option<&'a Value>)>, Error> {
+    if let Some((name, _)) = values
+        .iter()
+        .find(|(name, _)| !components.iter().any(|component| component.name == *name))
+    {
+        return Err(invalid_value(path, format!("unknown component `{}`", name)));
+    }
+
+    components
+        .iter()
+        .map(|&component| {
+            let value = values
+                .iter()
+                .find(|(name, _)| *name == component.name)
+                .map(|(_, value)| value);
+            match &component.presence {
+                Presence::Required if value.is_none() => Err(invalid_value(
+                    path,
+                    format!("component `{}` is missing", component.name),
+                )),
+                Presence::Default(default) => {
+                    Ok((component, value.filter(|value| !is_default(value, default))))
+                }
+                _ => Ok((component, value)),
+            }
+        })
+        .collect()
+}
+
+/// Whether a component's value is its DEFAULT value.
+fn is_default(value: &Value, default: &asn1_schema::Value) -> bool {
+    match default {
+        asn1_schema::Value::OctetString(default) => {
+            octets(value).is_some_and(|octets| *octets == **default)
+        }
+        default => *value == Value::from(default),
+    }
+}
+
+/// Shorthands for building values in the tests of the codecs.
+#[cfg(test)]
+pub(crate) mod helpers {
+    use super::Value;
+
+    pub(crate) fn integer(value: i128) -> Value {
+        Value::Integer(value)
+    }
+
+    pub(crate) fn string(value: &str) -> Value {
+        Value::Str(value.to_string())
+    }
+
+    pub(crate) fn seq(components: Vec<(&str, Value)>) -> Value {
+        Value::Seq(
+            components
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use asn1_schema::Module;
+
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn undefined_type() {
+        // parsing rejects this, but a module may also be built by hand
+        let mut module = Module::parse(
+            "Undefined DEFINITIONS ::= BEGIN
+            A ::= SEQUENCE { x SEQUENCE OF B }
+            B ::= INTEGER (0..7)
+            END",
+        )
+        .unwrap();
+        module.types.retain(|assignment| assignment.name != "B");
+        let unknown = Some(Error::UnknownType("B".into()));
+
+        let value = Value::Seq(vec![("x".into(), Value::List(vec![Value::Integer(1)]))]);
+        assert_eq!(unknown, value_to_oer_bytes(&module, "A", &value).err());
+        assert_eq!(unknown, value_to_ber_bytes(&module, "A", &value).err());
+        assert_eq!(unknown, value_to_der_bytes(&module, "A", &value).err());
+        assert_eq!(unknown, value_to_cer_bytes(&module, "A", &value).err());
+        assert_eq!(unknown, value_to_aper_bytes(&module, "A", &value).err());
+        assert_eq!(unknown, value_to_uper_bytes(&module, "A", &value).err());
+        assert_eq!(
+            unknown,
+            value_to_canonical_aper_bytes(&module, "A", &value).err()
+        );
+        assert_eq!(
+            unknown,
+            value_to_canonical_uper_bytes(&module, "A", &value).err()
+        );
+
+        let input = [0x30, 0x05, 0x30, 0x03, 0x02, 0x01, 0x01];
+        assert_eq!(unknown, value_from_oer_bytes(&module, "A", &input).err());
+        assert_eq!(unknown, value_from_ber_bytes(&module, "A", &input).err());
+        assert_eq!(unknown, value_from_der_bytes(&module, "A", &input).err());
+        assert_eq!(unknown, value_from_cer_bytes(&module, "A", &input).err());
+        assert_eq!(unknown, value_from_aper_bytes(&module, "A", &input).err());
+        assert_eq!(unknown, value_from_uper_bytes(&module, "A", &input).err());
+        assert_eq!(
+            unknown,
+            value_from_canonical_aper_bytes(&module, "A", &input).err()
+        );
+        assert_eq!(
+            unknown,
+            value_from_canonical_uper_bytes(&module, "A", &input).err()
+        );
+        #[cfg(feature = "jer")]
+        assert_eq!(
+            unknown,
+            value_from_jer(&module, "A", &serde_json::json!({ "x": [1] })).err()
+        );
+    }
+}
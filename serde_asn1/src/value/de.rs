@@ -0,0 +1,179 @@
+//! Conversion of `Value`s into values of deserializable types, such as
+//! generated types.
+
+use serde::de::value::{MapDeserializer, SeqDeserializer, StringDeserializer};
+use serde::de::{self, Deserialize, IntoDeserializer, Visitor};
+use std::convert::TryFrom;
+
+use crate::error::{Error, Result};
+use crate::Value;
+
+/// Converts a `Value` into a value of a deserializable type, such as a
+/// generated type.
+pub fn from_value<'de, T>(value: Value) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(value)
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Bool(value) => visitor.visit_bool(value),
+            // the visitors of narrower integer types check the range
+            Value::Integer(value) => match (i64::try_from(value), u64::try_from(value)) {
+                (Ok(value), _) => visitor.visit_i64(value),
+                (_, Ok(value)) => visitor.visit_u64(value),
+                _ => visitor.visit_i128(value),
+            },
+            Value::ObjectIdentifier(arcs) => {
+                let mut arcs = SeqDeserializer::new(arcs.into_iter());
+                let value = visitor.visit_seq(&mut arcs)?;
+                arcs.end()?;
+                Ok(value)
+            }
+            Value::Str(value) => visitor.visit_string(value),
+            // generated types hold octets in a `Vec<u8>`, which serde
+            // deserializes from a sequence
+            Value::Bytes(octets) => {
+                let mut octets = SeqDeserializer::new(octets.into_iter());
+                let value = visitor.visit_seq(&mut octets)?;
+                octets.end()?;
+                Ok(value)
+            }
+            Value::Enum(name) => visitor.visit_enum(ChoiceAccess { name, value: None }),
+            Value::Seq(components) => {
+                let mut components = MapDeserializer::new(components.into_iter());
+                let value = visitor.visit_map(&mut components)?;
+                components.end()?;
+                Ok(value)
+            }
+            Value::Choice(name, value) => visitor.visit_enum(ChoiceAccess {
+                name,
+                value: Some(*value),
+            }),
+            Value::List(values) => {
+                let mut values = SeqDeserializer::new(values.into_iter());
+                let value = visitor.visit_seq(&mut values)?;
+                values.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    /// Absent OPTIONAL components are left out of SEQUENCE values, and serde
+    /// takes missing fields of an `Option` type to be `None`, so any value
+    /// that is there is `Some`.
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Bytes(octets) => visitor.visit_byte_buf(octets),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct enum
+        identifier ignored_any
+    }
+}
+
+/// Gives access to the chosen alternative of a CHOICE, or to an item of an
+/// ENUMERATED type, which has no value.
+struct ChoiceAccess {
+    name: String,
+    value: Option<Value>,
+}
+
+impl<'de> de::EnumAccess<'de> for ChoiceAccess {
+    type Error = Error;
+    type Variant = VariantAccess;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantAccess)>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let name: StringDeserializer<Error> = self.name.into_deserializer();
+        Ok((seed.deserialize(name)?, VariantAccess(self.value)))
+    }
+}
+
+/// The value of a CHOICE alternative, or none for an ENUMERATED item.
+struct VariantAccess(Option<Value>);
+
+impl VariantAccess {
+    fn value(self) -> Result<Value> {
+        self.0
+            .ok_or_else(|| Error::Message("expected a CHOICE value".into()))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.0 {
+            None => Ok(()),
+            Some(_) => Err(Error::Message("expected an ENUMERATED value".into())),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.value()?)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(self.value()?, visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(self.value()?, visitor)
+    }
+}
@@ -0,0 +1,283 @@
+//! Conversion of values of serializable types, such as generated types,
+//! into `Value`s.
+
+use serde::ser::{self, Impossible, Serialize};
+use std::convert::TryFrom;
+
+use crate::error::{Error, Result};
+use crate::Value;
+
+/// Converts a value of a serializable type, such as a generated type, into
+/// a `Value`.
+pub fn to_value<T>(value: &T) -> Result<Value>
+where
+    T: Serialize + ?Sized,
+{
+    value
+        .serialize(Serializer)?
+        .ok_or_else(|| Error::Message("expected a value, found None".into()))
+}
+
+fn unsupported(what: &str) -> Error {
+    Error::Message(format!("{} are not supported", what))
+}
+
+/// Serializes into `Some` value, or into `None` for a `None` option, which
+/// stands for an absent OPTIONAL component.
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = Impossible<Option<Value>, Error>;
+    type SerializeMap = Impossible<Option<Value>, Error>;
+    type SerializeStruct = SerializeComponents;
+    type SerializeStructVariant = Impossible<Option<Value>, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Option<Value>> {
+        Ok(Some(Value::Bool(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Option<Value>> {
+        self.serialize_i128(i128::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Option<Value>> {
+        self.serialize_i128(i128::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Option<Value>> {
+        self.serialize_i128(i128::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Option<Value>> {
+        self.serialize_i128(i128::from(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Option<Value>> {
+        Ok(Some(Value::Integer(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Option<Value>> {
+        self.serialize_i128(i128::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Option<Value>> {
+        self.serialize_i128(i128::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Option<Value>> {
+        self.serialize_i128(i128::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Option<Value>> {
+        self.serialize_i128(i128::from(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Option<Value>> {
+        self.serialize_i128(i128::try_from(v).map_err(|_| Error::IntegerOverflow)?)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Option<Value>> {
+        Err(unsupported("floating point numbers"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Option<Value>> {
+        Err(unsupported("floating point numbers"))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Option<Value>> {
+        Ok(Some(Value::Str(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Option<Value>> {
+        Ok(Some(Value::Str(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Option<Value>> {
+        Ok(Some(Value::Bytes(v.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Option<Value>>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<Value>> {
+        Err(unsupported("unit values"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<Value>> {
+        Err(unsupported("unit values"))
+    }
+
+    /// The unit variants of generated enums are the items of an
+    /// ENUMERATED type.
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Option<Value>> {
+        Ok(Some(Value::Enum(variant.to_string())))
+    }
+
+    /// Newtypes have no value of their own, so the wrapped value is
+    /// converted in their place.
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Option<Value>>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    /// The variants of generated enums are the alternatives of a CHOICE.
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Option<Value>>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(Some(Value::Choice(
+            variant.to_string(),
+            Box::new(to_value(value)?),
+        )))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList> {
+        Ok(SerializeList {
+            values: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(unsupported("tuple variants"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(unsupported("maps"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeComponents> {
+        Ok(SerializeComponents {
+            components: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(unsupported("struct variants"))
+    }
+}
+
+/// Collects the elements of a SEQUENCE OF.
+struct SerializeList {
+    values: Vec<Value>,
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.values.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        Ok(Some(Value::List(self.values)))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Collects the components of a SEQUENCE, leaving out absent OPTIONAL
+/// components.
+struct SerializeComponents {
+    components: Vec<(String, Value)>,
+}
+
+impl ser::SerializeStruct for SerializeComponents {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        if let Some(value) = value.serialize(Serializer)? {
+            self.components.push((key.to_string(), value));
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        Ok(Some(Value::Seq(self.components)))
+    }
+}